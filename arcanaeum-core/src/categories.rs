@@ -0,0 +1,321 @@
+//! Sorting plugins into categories picked by the user, with rules on the plugin name, the
+//! MO2 category of the mod it came from or its LOOT group.
+
+use std::fmt::{self, Display};
+use std::io::{Error, ErrorKind};
+
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Category {
+    pub name: String,
+    pub color: [u8; 3],
+}
+
+impl Category {
+    fn new(name: &str, color: [u8; 3]) -> Category {
+        Category {
+            name: name.to_string(),
+            color,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum RuleMatch {
+    /**
+     * A file name pattern where `*` is any text and `?` any character.
+     */
+    Glob(String),
+    Regex(String),
+    Mo2Category(String),
+    LootGroup(String),
+}
+
+impl RuleMatch {
+    pub const KINDS: [&'static str; 4] = ["Name glob", "Name regex", "MO2 category", "LOOT group"];
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            RuleMatch::Glob(_) => RuleMatch::KINDS[0],
+            RuleMatch::Regex(_) => RuleMatch::KINDS[1],
+            RuleMatch::Mo2Category(_) => RuleMatch::KINDS[2],
+            RuleMatch::LootGroup(_) => RuleMatch::KINDS[3],
+        }
+    }
+
+    pub fn pattern(&self) -> &str {
+        match self {
+            RuleMatch::Glob(pattern)
+            | RuleMatch::Regex(pattern)
+            | RuleMatch::Mo2Category(pattern)
+            | RuleMatch::LootGroup(pattern) => pattern,
+        }
+    }
+
+    pub fn pattern_mut(&mut self) -> &mut String {
+        match self {
+            RuleMatch::Glob(pattern)
+            | RuleMatch::Regex(pattern)
+            | RuleMatch::Mo2Category(pattern)
+            | RuleMatch::LootGroup(pattern) => pattern,
+        }
+    }
+
+    /**
+     * The same pattern matched another way, for switching the kind of a rule.
+     */
+    pub fn with_kind(&self, kind: &str) -> RuleMatch {
+        let pattern = self.pattern().to_string();
+        match kind {
+            "Name regex" => RuleMatch::Regex(pattern),
+            "MO2 category" => RuleMatch::Mo2Category(pattern),
+            "LOOT group" => RuleMatch::LootGroup(pattern),
+            _ => RuleMatch::Glob(pattern),
+        }
+    }
+}
+
+impl Display for RuleMatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.kind(), self.pattern())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CategoryRule {
+    pub matches: RuleMatch,
+    pub category: String,
+}
+
+impl CategoryRule {
+    fn new(matches: RuleMatch, category: &str) -> CategoryRule {
+        CategoryRule {
+            matches,
+            category: category.to_string(),
+        }
+    }
+}
+
+/**
+ * The categories in the order they are shown, and the rules assigning them. The first
+ * rule that matches a plugin wins.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CategorySettings {
+    pub categories: Vec<Category>,
+    pub rules: Vec<CategoryRule>,
+}
+
+impl Default for CategorySettings {
+    fn default() -> Self {
+        CategorySettings {
+            categories: vec![
+                Category::new("Framework", [90, 140, 230]),
+                Category::new("Gameplay", [230, 140, 30]),
+                Category::new("Cosmetic", [200, 90, 200]),
+                Category::new("Patch", [120, 120, 120]),
+            ],
+            rules: vec![
+                CategoryRule::new(RuleMatch::Glob("*patch*".to_string()), "Patch"),
+                CategoryRule::new(RuleMatch::Mo2Category("Patches".to_string()), "Patch"),
+                CategoryRule::new(
+                    RuleMatch::Mo2Category("Modders Resources".to_string()),
+                    "Framework",
+                ),
+                CategoryRule::new(
+                    RuleMatch::Mo2Category("Visuals and Graphics".to_string()),
+                    "Cosmetic",
+                ),
+                CategoryRule::new(RuleMatch::Mo2Category("Gameplay".to_string()), "Gameplay"),
+            ],
+        }
+    }
+}
+
+impl CategorySettings {
+    pub fn get(&self, name: &str) -> Option<&Category> {
+        self.categories.iter().find(|c| c.name == name)
+    }
+}
+
+/**
+ * What the rules can look at for a plugin.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct PluginFacts<'a> {
+    pub name: &'a str,
+    pub mo2_categories: Vec<String>,
+    pub loot_group: Option<String>,
+}
+
+fn glob_to_regex(glob: &str) -> String {
+    let mut regex = String::from("^");
+    for c in glob.chars() {
+        match c {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push('$');
+    regex
+}
+
+enum Matcher {
+    Name(Regex),
+    Mo2Category(String),
+    LootGroup(String),
+}
+
+impl Matcher {
+    fn new(matches: &RuleMatch) -> Result<Matcher, Error> {
+        let name_regex = |pattern: &str| {
+            RegexBuilder::new(pattern)
+                .case_insensitive(true)
+                .build()
+                .map(Matcher::Name)
+                .map_err(|e| Error::new(ErrorKind::InvalidInput, e.to_string()))
+        };
+        match matches {
+            RuleMatch::Glob(glob) => name_regex(&glob_to_regex(glob)),
+            RuleMatch::Regex(pattern) => name_regex(pattern),
+            RuleMatch::Mo2Category(category) => Ok(Matcher::Mo2Category(category.clone())),
+            RuleMatch::LootGroup(group) => Ok(Matcher::LootGroup(group.clone())),
+        }
+    }
+
+    fn is_match(&self, facts: &PluginFacts) -> bool {
+        match self {
+            Matcher::Name(regex) => regex.is_match(facts.name),
+            Matcher::Mo2Category(category) => facts
+                .mo2_categories
+                .iter()
+                .any(|c| c.eq_ignore_ascii_case(category)),
+            Matcher::LootGroup(group) => facts
+                .loot_group
+                .as_ref()
+                .is_some_and(|g| g.eq_ignore_ascii_case(group)),
+        }
+    }
+}
+
+/**
+ * Checks that a rule can be used, to point out broken regexes while editing.
+ */
+pub fn validate_rule(rule: &CategoryRule) -> Result<(), Error> {
+    Matcher::new(&rule.matches).map(|_| ())
+}
+
+pub struct Categorizer {
+    rules: Vec<(Matcher, String)>,
+}
+
+impl Categorizer {
+    /**
+     * Rules that don't compile are left out.
+     */
+    pub fn new(rules: &[CategoryRule]) -> Categorizer {
+        let rules = rules
+            .iter()
+            .filter_map(|rule| match Matcher::new(&rule.matches) {
+                Ok(matcher) => Some((matcher, rule.category.clone())),
+                Err(e) => {
+                    tracing::error!("Skipping category rule {}: {}", rule.matches, e);
+                    None
+                }
+            })
+            .collect();
+        Categorizer { rules }
+    }
+
+    pub fn categorize(&self, facts: &PluginFacts) -> Option<&str> {
+        self.rules
+            .iter()
+            .find(|(matcher, _)| matcher.is_match(facts))
+            .map(|(_, category)| category.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn facts(name: &str) -> PluginFacts<'_> {
+        PluginFacts {
+            name,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn first_matching_rule_wins() {
+        let categorizer = Categorizer::new(&[
+            CategoryRule::new(RuleMatch::Glob("SkyUI*.esp".to_string()), "Framework"),
+            CategoryRule::new(RuleMatch::Regex(r"^.*\.esp$".to_string()), "Gameplay"),
+        ]);
+        assert_eq!(
+            categorizer.categorize(&facts("skyui_se.esp")),
+            Some("Framework")
+        );
+        assert_eq!(
+            categorizer.categorize(&facts("Ordinator.esp")),
+            Some("Gameplay")
+        );
+        assert_eq!(categorizer.categorize(&facts("Skyrim.esm")), None);
+    }
+
+    #[test]
+    fn globs_match_the_whole_name() {
+        let categorizer = Categorizer::new(&[CategoryRule::new(
+            RuleMatch::Glob("?atch.esp".to_string()),
+            "Patch",
+        )]);
+        assert_eq!(categorizer.categorize(&facts("Patch.esp")), Some("Patch"));
+        assert_eq!(categorizer.categorize(&facts("A Patch.esp")), None);
+        assert_eq!(categorizer.categorize(&facts("Patch.esp.bak")), None);
+    }
+
+    #[test]
+    fn mod_manager_and_loot_facts_are_matched() {
+        let categorizer = Categorizer::new(&[
+            CategoryRule::new(RuleMatch::Mo2Category("Armour".to_string()), "Cosmetic"),
+            CategoryRule::new(RuleMatch::LootGroup("Late Loaders".to_string()), "Patch"),
+        ]);
+        let armour = PluginFacts {
+            name: "Armour.esp",
+            mo2_categories: vec!["Models and Textures".to_string(), "armour".to_string()],
+            loot_group: None,
+        };
+        assert_eq!(categorizer.categorize(&armour), Some("Cosmetic"));
+        let late = PluginFacts {
+            name: "Bashed Patch, 0.esp",
+            mo2_categories: Vec::new(),
+            loot_group: Some("Late Loaders".to_string()),
+        };
+        assert_eq!(categorizer.categorize(&late), Some("Patch"));
+    }
+
+    #[test]
+    fn broken_regexes_are_skipped() {
+        let rules = [
+            CategoryRule::new(RuleMatch::Regex("(".to_string()), "Patch"),
+            CategoryRule::new(RuleMatch::Glob("*".to_string()), "Gameplay"),
+        ];
+        assert!(validate_rule(&rules[0]).is_err());
+        let categorizer = Categorizer::new(&rules);
+        assert_eq!(categorizer.categorize(&facts("A.esp")), Some("Gameplay"));
+    }
+
+    #[test]
+    fn settings_round_trip() {
+        let settings = CategorySettings::default();
+        let json = serde_json::to_string(&settings).unwrap();
+        assert_eq!(
+            serde_json::from_str::<CategorySettings>(&json).unwrap(),
+            settings
+        );
+    }
+}
@@ -0,0 +1,182 @@
+use std::io::{Error, ErrorKind};
+
+use super::{read_length_prefixed, Record};
+
+const BLOCK_SIZE: usize = 32768;
+const HEADER_SIZE: usize = 7;
+
+const FULL: u8 = 1;
+const FIRST: u8 = 2;
+const MIDDLE: u8 = 3;
+const LAST: u8 = 4;
+
+const TYPE_DELETION: u8 = 0;
+const TYPE_VALUE: u8 = 1;
+
+/**
+ * Splits a log file into its records. Records are split into fragments so that none
+ * crosses a 32KiB block boundary.
+ */
+pub fn read_log_records(buf: &[u8]) -> Vec<Vec<u8>> {
+    let mut records = Vec::new();
+    let mut pending: Option<Vec<u8>> = None;
+
+    for block in buf.chunks(BLOCK_SIZE) {
+        let mut cursor = 0;
+        while cursor + HEADER_SIZE <= block.len() {
+            let length = u16::from_le_bytes([block[cursor + 4], block[cursor + 5]]) as usize;
+            let record_type = block[cursor + 6];
+            let start = cursor + HEADER_SIZE;
+            let data = match block.get(start..start + length) {
+                Some(data) if record_type != 0 => data,
+                // Zero type or a truncated fragment is padding at the end of the block
+                _ => break,
+            };
+            cursor = start + length;
+
+            match record_type {
+                FULL => records.push(data.to_vec()),
+                FIRST => pending = Some(data.to_vec()),
+                MIDDLE => {
+                    if let Some(pending) = pending.as_mut() {
+                        pending.extend_from_slice(data);
+                    }
+                }
+                LAST => {
+                    if let Some(mut record) = pending.take() {
+                        record.extend_from_slice(data);
+                        records.push(record);
+                    }
+                }
+                _ => tracing::error!("Unknown log record type {}", record_type),
+            }
+        }
+    }
+    records
+}
+
+/**
+ * Reads the writes in a batch. The batch has one sequence number, every write after the
+ * first gets the next one.
+ */
+pub fn read_write_batch(buf: &[u8]) -> Result<Vec<Record>, Error> {
+    if buf.len() < 12 {
+        return Err(Error::new(ErrorKind::InvalidData, "Truncated write batch"));
+    }
+    let sequence = u64::from_le_bytes(buf[0..8].try_into().unwrap());
+    let count = u32::from_le_bytes(buf[8..12].try_into().unwrap()) as u64;
+
+    let mut records = Vec::new();
+    let mut cursor = 12;
+    for offset in 0..count {
+        let tag = *buf
+            .get(cursor)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Truncated write batch"))?;
+        let (key, used) = read_length_prefixed(&buf[cursor + 1..])?;
+        cursor += 1 + used;
+        let value = match tag {
+            TYPE_VALUE => {
+                let (value, used) = read_length_prefixed(&buf[cursor..])?;
+                cursor += used;
+                Some(value.to_vec())
+            }
+            TYPE_DELETION => None,
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Unknown write type {tag}"),
+                ))
+            }
+        };
+        let sequence = sequence
+            .checked_add(offset)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Sequence number out of range"))?;
+        records.push(Record {
+            key: key.to_vec(),
+            sequence,
+            value,
+        });
+    }
+    Ok(records)
+}
+
+pub fn read_log(buf: &[u8]) -> Result<Vec<Record>, Error> {
+    let mut records = Vec::new();
+    for batch in read_log_records(buf) {
+        records.extend(read_write_batch(&batch)?);
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+pub mod test_data {
+    pub fn write_batch(sequence: u64, writes: &[(&str, Option<&str>)]) -> Vec<u8> {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&sequence.to_le_bytes());
+        buf.extend_from_slice(&(writes.len() as u32).to_le_bytes());
+        for (key, value) in writes {
+            buf.push(value.is_some() as u8);
+            buf.push(key.len() as u8);
+            buf.extend_from_slice(key.as_bytes());
+            if let Some(value) = value {
+                buf.push(value.len() as u8);
+                buf.extend_from_slice(value.as_bytes());
+            }
+        }
+        buf
+    }
+
+    pub fn log_fragment(record_type: u8, data: &[u8]) -> Vec<u8> {
+        let mut buf = vec![0; 4];
+        buf.extend_from_slice(&(data.len() as u16).to_le_bytes());
+        buf.push(record_type);
+        buf.extend_from_slice(data);
+        buf
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_data::{log_fragment, write_batch};
+    use super::*;
+
+    #[test]
+    fn fragments_are_joined_into_batches() {
+        let first = write_batch(10, &[("a", Some("1")), ("b", None)]);
+        let second = write_batch(12, &[("c", Some("3"))]);
+
+        let mut log = log_fragment(FULL, &first);
+        log.extend(log_fragment(FIRST, &second[..5]));
+        log.extend(log_fragment(MIDDLE, &second[5..9]));
+        log.extend(log_fragment(LAST, &second[9..]));
+        // Padding at the end of the block
+        log.extend([0; 6]);
+
+        let records = read_log(&log).unwrap();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[1].key, b"b");
+        assert_eq!(records[1].sequence, 11);
+        assert_eq!(records[1].value, None);
+        assert_eq!(records[2].value, Some(b"3".to_vec()));
+    }
+
+    #[test]
+    fn corrupt_batches_are_errors() {
+        let overflowing = write_batch(u64::MAX, &[("a", Some("1")), ("b", Some("2"))]);
+        assert_eq!(
+            read_write_batch(&overflowing).unwrap_err().kind(),
+            ErrorKind::InvalidData
+        );
+
+        // The key length is a varint running to the largest value
+        let mut corrupt = write_batch(1, &[]);
+        corrupt[8] = 1;
+        corrupt.push(TYPE_VALUE);
+        corrupt.extend_from_slice(&[0xff; 9]);
+        corrupt.push(0x01);
+        assert_eq!(
+            read_write_batch(&corrupt).unwrap_err().kind(),
+            ErrorKind::InvalidData
+        );
+    }
+}
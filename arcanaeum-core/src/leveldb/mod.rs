@@ -0,0 +1,143 @@
+//! A read-only reader for LevelDB databases, enough to read the state other tools keep in
+//! them. It merges the sorted tables and the write-ahead log by sequence number instead of
+//! following the manifest, and doesn't verify checksums.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+
+pub mod log;
+pub mod table;
+
+/**
+ * A write to a key. A missing value is a deletion.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    pub key: Vec<u8>,
+    pub sequence: u64,
+    pub value: Option<Vec<u8>>,
+}
+
+fn invalid_data(message: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, message.to_string())
+}
+
+/**
+ * Reads a varint from the start of the buffer, returning it and the number of bytes used.
+ */
+pub fn read_varint(buf: &[u8]) -> Result<(u64, usize), Error> {
+    let mut value = 0u64;
+    for (i, byte) in buf.iter().enumerate().take(10) {
+        value |= ((byte & 0x7f) as u64) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Ok((value, i + 1));
+        }
+    }
+    Err(invalid_data("Truncated varint"))
+}
+
+/**
+ * Reads a varint prefixed byte string, returning it and the number of bytes used.
+ */
+pub fn read_length_prefixed(buf: &[u8]) -> Result<(&[u8], usize), Error> {
+    let (length, used) = read_varint(buf)?;
+    let end = usize::try_from(length)
+        .ok()
+        .and_then(|length| used.checked_add(length))
+        .ok_or_else(|| invalid_data("String length out of range"))?;
+    let data = buf
+        .get(used..end)
+        .ok_or_else(|| invalid_data("Truncated string"))?;
+    Ok((data, end))
+}
+
+/**
+ * Keeps the newest record of every key and drops deleted keys.
+ */
+pub fn merge_records(records: impl IntoIterator<Item = Record>) -> BTreeMap<Vec<u8>, Vec<u8>> {
+    let mut newest: HashMap<Vec<u8>, (u64, Option<Vec<u8>>)> = HashMap::new();
+    for record in records {
+        match newest.get(&record.key) {
+            Some((sequence, _)) if *sequence > record.sequence => {}
+            _ => {
+                newest.insert(record.key, (record.sequence, record.value));
+            }
+        }
+    }
+    newest
+        .into_iter()
+        .filter_map(|(key, (_, value))| Some((key, value?)))
+        .collect()
+}
+
+/**
+ * Reads every table and log file in a database folder into a sorted map.
+ */
+pub fn read_database(path: &Path) -> Result<BTreeMap<Vec<u8>, Vec<u8>>, Error> {
+    let mut records = Vec::new();
+    for entry in fs::read_dir(path)?.filter_map(|e| e.ok()) {
+        let file_path = entry.path();
+        let extension = file_path
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase());
+        let read = match extension.as_deref() {
+            Some("ldb") | Some("sst") => table::read_table(&fs::read(&file_path)?),
+            Some("log") => log::read_log(&fs::read(&file_path)?),
+            _ => continue,
+        };
+        match read {
+            Ok(file_records) => records.extend(file_records),
+            Err(e) => tracing::error!("Could not read {:?}: {}", file_path, e),
+        }
+    }
+    Ok(merge_records(records))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn varints_are_little_endian_groups_of_seven_bits() {
+        assert_eq!(read_varint(&[0x01]).unwrap(), (1, 1));
+        assert_eq!(read_varint(&[0xac, 0x02, 0xff]).unwrap(), (300, 2));
+        assert!(read_varint(&[0x80]).is_err());
+    }
+
+    #[test]
+    fn corrupt_lengths_are_errors() {
+        // A length close to u64::MAX would wrap around past the end of the buffer
+        let mut buf = vec![0xff; 9];
+        buf.push(0x01);
+        buf.extend_from_slice(b"key");
+        assert_eq!(
+            read_length_prefixed(&buf).unwrap_err().kind(),
+            ErrorKind::InvalidData
+        );
+        assert_eq!(
+            read_length_prefixed(&[0x05, b'k']).unwrap_err().kind(),
+            ErrorKind::InvalidData
+        );
+    }
+
+    #[test]
+    fn newest_records_win_and_deletions_remove_keys() {
+        let record = |key: &str, sequence, value: Option<&str>| Record {
+            key: key.as_bytes().to_vec(),
+            sequence,
+            value: value.map(|v| v.as_bytes().to_vec()),
+        };
+        let merged = merge_records(vec![
+            record("a", 5, Some("new")),
+            record("a", 2, Some("old")),
+            record("b", 3, Some("kept")),
+            record("c", 1, Some("gone")),
+            record("c", 4, None),
+        ]);
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged.get(b"a".as_slice()), Some(&b"new".to_vec()));
+        assert_eq!(merged.get(b"b".as_slice()), Some(&b"kept".to_vec()));
+    }
+}
@@ -0,0 +1,250 @@
+use std::io::{Error, ErrorKind};
+
+use super::{read_varint, Record};
+
+const FOOTER_SIZE: usize = 48;
+const TABLE_MAGIC: u64 = 0xdb4775248b80fb57;
+const BLOCK_TRAILER_SIZE: usize = 5;
+
+/**
+ * A key and value as stored in a block.
+ */
+type BlockEntry = (Vec<u8>, Vec<u8>);
+
+const NO_COMPRESSION: u8 = 0;
+const SNAPPY_COMPRESSION: u8 = 1;
+
+fn invalid_data(message: String) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+/**
+ * The offset and size of a block in the table.
+ */
+fn read_block_handle(buf: &[u8]) -> Result<((usize, usize), usize), Error> {
+    let (offset, used_offset) = read_varint(buf)?;
+    let (size, used_size) = read_varint(&buf[used_offset..])?;
+    Ok(((offset as usize, size as usize), used_offset + used_size))
+}
+
+fn read_block(buf: &[u8], (offset, size): (usize, usize)) -> Result<Vec<u8>, Error> {
+    // Handles come from the file, so the ranges they make can overflow
+    let out_of_table = || invalid_data(format!("Block at {offset} is out of the table"));
+    let end = offset.checked_add(size).ok_or_else(out_of_table)?;
+    let data = buf.get(offset..end).ok_or_else(out_of_table)?;
+    // The trailer is the compression type and a checksum
+    let compression = match end
+        .checked_add(BLOCK_TRAILER_SIZE)
+        .and_then(|trailer_end| buf.get(end..trailer_end))
+    {
+        Some(trailer) => trailer[0],
+        None => return Err(invalid_data(format!("Block at {offset} has no trailer"))),
+    };
+    match compression {
+        NO_COMPRESSION => Ok(data.to_vec()),
+        SNAPPY_COMPRESSION => snap::raw::Decoder::new()
+            .decompress_vec(data)
+            .map_err(|e| invalid_data(e.to_string())),
+        other => Err(invalid_data(format!(
+            "Unsupported block compression {other}"
+        ))),
+    }
+}
+
+/**
+ * Reads the key/value entries of a block. Keys share a prefix with the key before them,
+ * the restart points at the end of the block are only needed for seeking.
+ */
+pub fn read_block_entries(block: &[u8]) -> Result<Vec<BlockEntry>, Error> {
+    if block.len() < 4 {
+        return Err(invalid_data("Truncated block".to_string()));
+    }
+    let restart_count = u32::from_le_bytes(block[block.len() - 4..].try_into().unwrap()) as usize;
+    let data_end = block
+        .len()
+        .checked_sub(4 + 4 * restart_count)
+        .ok_or_else(|| invalid_data("Block has too many restarts".to_string()))?;
+
+    let mut entries = Vec::new();
+    let mut key: Vec<u8> = Vec::new();
+    let mut cursor = 0;
+    while cursor < data_end {
+        let (shared, used) = read_varint(&block[cursor..])?;
+        cursor += used;
+        let (non_shared, used) = read_varint(&block[cursor..])?;
+        cursor += used;
+        let (value_length, used) = read_varint(&block[cursor..])?;
+        cursor += used;
+
+        let key_end = cursor.checked_add(non_shared as usize);
+        let value_end = key_end.and_then(|key_end| key_end.checked_add(value_length as usize));
+        let (key_end, value_end) = match (key_end, value_end) {
+            (Some(key_end), Some(value_end))
+                if shared as usize <= key.len() && value_end <= data_end =>
+            {
+                (key_end, value_end)
+            }
+            _ => return Err(invalid_data("Corrupt block entry".to_string())),
+        };
+        key.truncate(shared as usize);
+        key.extend_from_slice(&block[cursor..key_end]);
+        entries.push((key.clone(), block[key_end..value_end].to_vec()));
+        cursor = value_end;
+    }
+    Ok(entries)
+}
+
+/**
+ * Splits an internal key into the user key, sequence number and whether it is a value.
+ */
+fn parse_internal_key(key: &[u8]) -> Result<(Vec<u8>, u64, bool), Error> {
+    if key.len() < 8 {
+        return Err(invalid_data("Internal key is too short".to_string()));
+    }
+    let (user_key, tag) = key.split_at(key.len() - 8);
+    let tag = u64::from_le_bytes(tag.try_into().unwrap());
+    Ok((user_key.to_vec(), tag >> 8, tag & 0xff == 1))
+}
+
+/**
+ * Reads every record in a sorted table (.ldb or .sst) file.
+ */
+pub fn read_table(buf: &[u8]) -> Result<Vec<Record>, Error> {
+    if buf.len() < FOOTER_SIZE {
+        return Err(invalid_data("Table is too short".to_string()));
+    }
+    let footer = &buf[buf.len() - FOOTER_SIZE..];
+    let magic = u64::from_le_bytes(footer[FOOTER_SIZE - 8..].try_into().unwrap());
+    if magic != TABLE_MAGIC {
+        return Err(invalid_data("Not a LevelDB table".to_string()));
+    }
+    let (_, used) = read_block_handle(footer)?;
+    let (index_handle, _) = read_block_handle(&footer[used..])?;
+
+    let mut records = Vec::new();
+    for (_, handle) in read_block_entries(&read_block(buf, index_handle)?)? {
+        let (data_handle, _) = read_block_handle(&handle)?;
+        for (key, value) in read_block_entries(&read_block(buf, data_handle)?)? {
+            let (key, sequence, is_value) = parse_internal_key(&key)?;
+            records.push(Record {
+                key,
+                sequence,
+                value: is_value.then_some(value),
+            });
+        }
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+pub mod test_data {
+    use super::{BLOCK_TRAILER_SIZE, FOOTER_SIZE, TABLE_MAGIC};
+
+    fn push_varint(buf: &mut Vec<u8>, mut value: u64) {
+        while value >= 0x80 {
+            buf.push((value as u8) | 0x80);
+            value >>= 7;
+        }
+        buf.push(value as u8);
+    }
+
+    fn block(entries: &[(Vec<u8>, Vec<u8>)]) -> Vec<u8> {
+        let mut buf = Vec::new();
+        let mut previous: &[u8] = &[];
+        for (key, value) in entries {
+            let shared = key.iter().zip(previous).take_while(|(a, b)| a == b).count();
+            push_varint(&mut buf, shared as u64);
+            push_varint(&mut buf, (key.len() - shared) as u64);
+            push_varint(&mut buf, value.len() as u64);
+            buf.extend_from_slice(&key[shared..]);
+            buf.extend_from_slice(value);
+            previous = key;
+        }
+        buf.extend_from_slice(&0u32.to_le_bytes());
+        buf.extend_from_slice(&1u32.to_le_bytes());
+        buf
+    }
+
+    fn push_block(table: &mut Vec<u8>, block: &[u8], snappy: bool) -> Vec<u8> {
+        let data = match snappy {
+            true => snap::raw::Encoder::new().compress_vec(block).unwrap(),
+            false => block.to_vec(),
+        };
+        let mut handle = Vec::new();
+        push_varint(&mut handle, table.len() as u64);
+        push_varint(&mut handle, data.len() as u64);
+        table.extend_from_slice(&data);
+        table.push(snappy as u8);
+        table.extend_from_slice(&[0; BLOCK_TRAILER_SIZE - 1]);
+        handle
+    }
+
+    /**
+     * Builds a table with one data block per group of (key, sequence, value) writes.
+     */
+    pub fn table(blocks: &[&[(&str, u64, Option<&str>)]]) -> Vec<u8> {
+        let mut table = Vec::new();
+        let mut index = Vec::new();
+        for (i, writes) in blocks.iter().enumerate() {
+            let entries: Vec<(Vec<u8>, Vec<u8>)> = writes
+                .iter()
+                .map(|(key, sequence, value)| {
+                    let mut internal_key = key.as_bytes().to_vec();
+                    let tag = (sequence << 8) | value.is_some() as u64;
+                    internal_key.extend_from_slice(&tag.to_le_bytes());
+                    (internal_key, value.unwrap_or_default().as_bytes().to_vec())
+                })
+                .collect();
+            let last_key = entries.last().unwrap().0.clone();
+            let handle = push_block(&mut table, &block(&entries), i % 2 == 1);
+            index.push((last_key, handle));
+        }
+        let metaindex_handle = push_block(&mut table, &block(&[]), false);
+        let index_handle = push_block(&mut table, &block(&index), false);
+
+        let mut footer = Vec::new();
+        footer.extend_from_slice(&metaindex_handle);
+        footer.extend_from_slice(&index_handle);
+        footer.resize(FOOTER_SIZE - 8, 0);
+        footer.extend_from_slice(&TABLE_MAGIC.to_le_bytes());
+        table.extend_from_slice(&footer);
+        table
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_data::table;
+    use super::*;
+
+    #[test]
+    fn reads_plain_and_snappy_blocks() {
+        let buf = table(&[
+            &[
+                ("persistent###mods###a", 3, Some("\"first\"")),
+                ("persistent###mods###b", 4, None),
+            ],
+            &[("persistent###profiles###x", 7, Some("\"Survival\""))],
+        ]);
+        let records = read_table(&buf).unwrap();
+
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].key, b"persistent###mods###a");
+        assert_eq!(records[0].sequence, 3);
+        assert_eq!(records[1].value, None);
+        assert_eq!(records[2].value, Some(b"\"Survival\"".to_vec()));
+    }
+
+    #[test]
+    fn other_files_are_rejected() {
+        assert!(read_table(&[0; 64]).is_err());
+    }
+
+    #[test]
+    fn handles_past_the_end_are_errors() {
+        let buf = [0; 16];
+        assert!(read_block(&buf, (usize::MAX, 1)).is_err());
+        assert!(read_block(&buf, (4, usize::MAX - 4)).is_err());
+        assert!(read_block(&buf, (8, 8)).is_err());
+    }
+}
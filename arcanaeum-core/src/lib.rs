@@ -0,0 +1,100 @@
+//! Reading, analysing and writing Skyrim saves, and scanning mod manager installations.
+//!
+//! This is the library behind the Arcanaeum GUI and command line interface.
+
+use std::collections::HashMap;
+use std::io::Error;
+use std::io::Read;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use loot::masterlist::MasterlistIndex;
+use loot::sources::{masterlist_cache, MasterlistSource};
+use mod_search::vortex_scanner::parse_plugin;
+use mod_search::vortex_scanner::Plugin;
+use save_file_parser::SaveInfo;
+
+use crate::save_file_parser::parse;
+
+pub mod categories;
+pub mod leveldb;
+pub mod load_order;
+pub mod loot;
+pub mod mod_manager;
+pub mod mod_search;
+pub mod plugin_file;
+pub mod save_file_parser;
+pub mod save_file_writer;
+pub mod utils;
+
+/**
+ * The plugins the masterlist has links for, keyed by name.
+ */
+pub fn load_mod_map(game: &str, source: &MasterlistSource) -> HashMap<String, Plugin> {
+    let mut map = HashMap::new();
+    if let Ok(index) = load_masterlist(game, source) {
+        for plugin in index.masterlist.plugins.iter().map(parse_plugin) {
+            map.insert(plugin.name.clone(), plugin);
+        }
+    }
+    map
+}
+
+/**
+ * The LOOT masterlist for the game from the source with the userlists merged in. It is
+ * parsed once and kept until one of its files changes.
+ */
+pub fn load_masterlist(
+    game: &str,
+    source: &MasterlistSource,
+) -> Result<Arc<MasterlistIndex>, Error> {
+    masterlist_cache().get(game, source).map_err(|e| {
+        tracing::info!("Could not read the masterlist for {}: {}", game, e);
+        e
+    })
+}
+
+pub fn load_saveinfo_from_path(path: String) -> Result<SaveInfo, Error> {
+    tracing::info!("Loading file: {:?}", path);
+    let mut file = std::fs::File::open(path)?;
+
+    let mut buf: Vec<u8> = Vec::new();
+    file.read_to_end(&mut buf)?;
+
+    let parsed = parse(buf);
+    Ok(parsed)
+}
+
+pub fn write_saveinfo_to_path(save_info: &SaveInfo, path: &Path) -> Result<(), Error> {
+    tracing::info!("Writing file: {:?}", path);
+    let buf = save_file_writer::write(save_info)?;
+    std::fs::write(path, buf)
+}
+
+pub fn write_saveinfo_json_to_path(save_info: &SaveInfo, path: &Path) -> Result<(), Error> {
+    tracing::info!("Exporting JSON to: {:?}", path);
+    let json = serde_json::to_string_pretty(save_info)?;
+    std::fs::write(path, json)
+}
+
+pub fn load_saveinfo_from_json_path(path: &Path) -> Result<SaveInfo, Error> {
+    tracing::info!("Importing JSON from: {:?}", path);
+    let json = std::fs::read_to_string(path)?;
+    let save_info = serde_json::from_str(json.as_str())?;
+    Ok(save_info)
+}
+
+/**
+ * Writes an edited save next to the original, leaving the original untouched.
+ */
+pub fn write_saveinfo_copy(
+    original_path: &str,
+    save_info: &SaveInfo,
+    suffix: &str,
+) -> Result<PathBuf, Error> {
+    let path = save_file_writer::get_edited_save_path(original_path, suffix);
+    write_saveinfo_to_path(save_info, &path)?;
+    save_file_writer::copy_skse_cosave(Path::new(original_path), &path)?;
+    Ok(path)
+}
@@ -0,0 +1,182 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum LoadOrderChange {
+    /**
+     * Active in the profile but not used by the save.
+     */
+    Added { name: String, new_index: usize },
+    /**
+     * Used by the save but not active in the profile.
+     */
+    Missing { name: String, old_index: usize },
+    /**
+     * In both, but in a different place relative to the other plugins.
+     */
+    Moved {
+        name: String,
+        old_index: usize,
+        new_index: usize,
+    },
+}
+
+impl LoadOrderChange {
+    pub fn name(&self) -> &str {
+        match self {
+            LoadOrderChange::Added { name, .. } => name,
+            LoadOrderChange::Missing { name, .. } => name,
+            LoadOrderChange::Moved { name, .. } => name,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LoadOrderComparison {
+    pub changes: Vec<LoadOrderChange>,
+    pub unchanged: usize,
+}
+
+impl LoadOrderComparison {
+    pub fn is_same(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+/**
+ * Finds the plugins that keep their relative order, using the longest common subsequence.
+ * Returns pairs of (old index, new index).
+ */
+fn get_stable_pairs(old: &[String], new: &[String]) -> Vec<(usize, usize)> {
+    let old: Vec<String> = old.iter().map(|p| p.to_lowercase()).collect();
+    let new: Vec<String> = new.iter().map(|p| p.to_lowercase()).collect();
+
+    let mut lengths = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i][j] = match old[i] == new[j] {
+                true => lengths[i + 1][j + 1] + 1,
+                false => lengths[i + 1][j].max(lengths[i][j + 1]),
+            };
+        }
+    }
+
+    let mut pairs = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            pairs.push((i, j));
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    pairs
+}
+
+/**
+ * Compares the plugin order stored in a save (old) with a profile's active plugins (new).
+ * Indices are positions in the given lists.
+ */
+pub fn compare_load_orders(old: &[String], new: &[String]) -> LoadOrderComparison {
+    let stable = get_stable_pairs(old, new);
+    let find_new = |name: &str| new.iter().position(|p| p.eq_ignore_ascii_case(name));
+    let find_old = |name: &str| old.iter().position(|p| p.eq_ignore_ascii_case(name));
+
+    let mut changes = Vec::new();
+    for (old_index, name) in old.iter().enumerate() {
+        if stable.iter().any(|(o, _)| *o == old_index) {
+            continue;
+        }
+        match find_new(name) {
+            Some(new_index) => changes.push(LoadOrderChange::Moved {
+                name: name.clone(),
+                old_index,
+                new_index,
+            }),
+            None => changes.push(LoadOrderChange::Missing {
+                name: name.clone(),
+                old_index,
+            }),
+        }
+    }
+    for (new_index, name) in new.iter().enumerate() {
+        if find_old(name).is_none() {
+            changes.push(LoadOrderChange::Added {
+                name: name.clone(),
+                new_index,
+            });
+        }
+    }
+
+    LoadOrderComparison {
+        changes,
+        unchanged: stable.len(),
+    }
+}
+
+/**
+ * Saves keep light plugins in their own list after the regular ones, so the profile's
+ * active plugins are put in the same shape before comparing.
+ */
+pub fn light_plugins_last(active: &[String], is_light: impl Fn(&str) -> bool) -> Vec<String> {
+    let (light, regular): (Vec<String>, Vec<String>) =
+        active.iter().cloned().partition(|name| is_light(name));
+    regular.into_iter().chain(light).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn identical_orders_have_no_changes() {
+        let order = names(&["Skyrim.esm", "Update.esm", "SkyUI_SE.esp"]);
+        let comparison = compare_load_orders(
+            &order,
+            &names(&["skyrim.esm", "Update.esm", "SKYUI_SE.esp"]),
+        );
+        assert!(comparison.is_same());
+        assert_eq!(comparison.unchanged, 3);
+    }
+
+    #[test]
+    fn reports_added_missing_and_moved_plugins() {
+        let save = names(&["Skyrim.esm", "A.esp", "B.esp", "C.esp", "D.esp"]);
+        let profile = names(&["Skyrim.esm", "B.esp", "C.esp", "A.esp", "E.esp"]);
+        let comparison = compare_load_orders(&save, &profile);
+
+        assert_eq!(
+            comparison.changes,
+            vec![
+                LoadOrderChange::Moved {
+                    name: "A.esp".to_string(),
+                    old_index: 1,
+                    new_index: 3
+                },
+                LoadOrderChange::Missing {
+                    name: "D.esp".to_string(),
+                    old_index: 4
+                },
+                LoadOrderChange::Added {
+                    name: "E.esp".to_string(),
+                    new_index: 4
+                },
+            ]
+        );
+        assert_eq!(comparison.unchanged, 3);
+    }
+
+    #[test]
+    fn light_plugins_are_moved_to_the_end() {
+        let active = names(&["Skyrim.esm", "Fish.esl", "SkyUI_SE.esp"]);
+        let ordered = light_plugins_last(&active, |name| name.ends_with(".esl"));
+        assert_eq!(ordered, names(&["Skyrim.esm", "SkyUI_SE.esp", "Fish.esl"]));
+    }
+}
@@ -0,0 +1,225 @@
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::plugin_file::read_plugin_header;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum MasterProblem {
+    /**
+     * The master is not installed at all.
+     */
+    Missing(String),
+    /**
+     * The master is loaded after the plugin that depends on it.
+     */
+    LoadsAfter(String),
+}
+
+impl Display for MasterProblem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MasterProblem::Missing(master) => write!(f, "missing master: {}", master),
+            MasterProblem::LoadsAfter(master) => write!(f, "master loads later: {}", master),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PluginNode {
+    pub name: String,
+    /**
+     * Position in the save's plugin list. Light plugins are stored in their own list, so
+     * their position relative to regular plugins is unknown.
+     */
+    pub position: Option<usize>,
+    pub masters: Vec<String>,
+    pub header_found: bool,
+    pub problems: Vec<MasterProblem>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DependencyGraph {
+    pub nodes: Vec<PluginNode>,
+}
+
+impl DependencyGraph {
+    pub fn get(&self, name: &str) -> Option<&PluginNode> {
+        self.nodes
+            .iter()
+            .find(|node| node.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn dependents_of(&self, name: &str) -> Vec<&PluginNode> {
+        self.nodes
+            .iter()
+            .filter(|node| node.masters.iter().any(|m| m.eq_ignore_ascii_case(name)))
+            .collect()
+    }
+
+    pub fn has_problems(&self) -> bool {
+        self.nodes.iter().any(|node| !node.problems.is_empty())
+    }
+}
+
+/**
+ * Builds the master dependency graph for the plugins in a save. `read_masters` returns the
+ * masters of an installed plugin, `is_installed` whether a plugin exists at all.
+ */
+pub fn build_dependency_graph(
+    plugins: &[String],
+    light_plugins: &[String],
+    read_masters: impl Fn(&str) -> Option<Vec<String>>,
+    is_installed: impl Fn(&str) -> bool,
+) -> DependencyGraph {
+    let positions: HashMap<String, usize> = plugins
+        .iter()
+        .enumerate()
+        .map(|(index, name)| (name.to_lowercase(), index))
+        .collect();
+
+    let entries = plugins
+        .iter()
+        .enumerate()
+        .map(|(index, name)| (name, Some(index)))
+        .chain(light_plugins.iter().map(|name| (name, None)));
+
+    let nodes = entries
+        .map(|(name, position)| {
+            let masters = read_masters(name);
+            let header_found = masters.is_some();
+            let masters = masters.unwrap_or_default();
+
+            let problems = masters
+                .iter()
+                .filter_map(|master| {
+                    let master_position = positions.get(&master.to_lowercase());
+                    if !is_installed(master) {
+                        Some(MasterProblem::Missing(master.clone()))
+                    } else if let (Some(position), Some(master_position)) =
+                        (position, master_position)
+                    {
+                        match *master_position > position {
+                            true => Some(MasterProblem::LoadsAfter(master.clone())),
+                            false => None,
+                        }
+                    } else {
+                        None
+                    }
+                })
+                .collect();
+
+            PluginNode {
+                name: name.clone(),
+                position,
+                masters,
+                header_found,
+                problems,
+            }
+        })
+        .collect();
+
+    DependencyGraph { nodes }
+}
+
+/**
+ * Builds the dependency graph from the plugin headers. `find_plugin` gives the file of an
+ * installed plugin, which is outside the Data folder for mod managers like MO2.
+ */
+pub fn build_dependency_graph_from_files(
+    plugins: &[String],
+    light_plugins: &[String],
+    find_plugin: impl Fn(&str) -> Option<PathBuf>,
+) -> DependencyGraph {
+    build_dependency_graph(
+        plugins,
+        light_plugins,
+        |name| {
+            let path = find_plugin(name)?;
+            match read_plugin_header(&path) {
+                Ok(header) => Some(header.masters),
+                Err(e) => {
+                    tracing::error!("Could not read header of {}: {}", name, e);
+                    None
+                }
+            }
+        },
+        |name| find_plugin(name).is_some(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    fn masters_of(name: &str) -> Option<Vec<String>> {
+        match name {
+            "Skyrim.esm" => Some(Vec::new()),
+            "SkyUI_SE.esp" => Some(names(&["Skyrim.esm"])),
+            "Patch.esp" => Some(names(&["skyrim.esm", "Lore.esp", "Missing.esm"])),
+            "Lore.esp" => Some(names(&["Skyrim.esm"])),
+            "Fish.esl" => Some(names(&["Lore.esp"])),
+            _ => None,
+        }
+    }
+
+    fn is_installed(name: &str) -> bool {
+        name != "Missing.esm" && name != "Gone.esp"
+    }
+
+    #[test]
+    fn flags_missing_masters_and_masters_loading_later() {
+        let graph = build_dependency_graph(
+            &names(&[
+                "Skyrim.esm",
+                "SkyUI_SE.esp",
+                "Patch.esp",
+                "Lore.esp",
+                "Gone.esp",
+            ]),
+            &names(&["Fish.esl"]),
+            masters_of,
+            is_installed,
+        );
+
+        assert!(graph.get("SkyUI_SE.esp").unwrap().problems.is_empty());
+        assert_eq!(
+            graph.get("Patch.esp").unwrap().problems,
+            vec![
+                MasterProblem::LoadsAfter("Lore.esp".to_string()),
+                MasterProblem::Missing("Missing.esm".to_string())
+            ]
+        );
+        assert!(!graph.get("Gone.esp").unwrap().header_found);
+        assert!(graph.get("fish.esl").unwrap().problems.is_empty());
+        assert!(graph.has_problems());
+    }
+
+    #[test]
+    fn dependents_are_matched_without_case() {
+        let graph = build_dependency_graph(
+            &names(&["Skyrim.esm", "SkyUI_SE.esp", "Patch.esp"]),
+            &[],
+            masters_of,
+            |_| true,
+        );
+        let dependents: Vec<&str> = graph
+            .dependents_of("Skyrim.esm")
+            .iter()
+            .map(|node| node.name.as_str())
+            .collect();
+        assert_eq!(dependents, vec!["SkyUI_SE.esp", "Patch.esp"]);
+    }
+
+    #[test]
+    fn problems_read_like_warnings() {
+        let problem = MasterProblem::Missing("Dawnguard.esm".to_string());
+        assert_eq!(problem.to_string(), "missing master: Dawnguard.esm");
+    }
+}
@@ -0,0 +1,4 @@
+pub mod comparison;
+pub mod dependency_graph;
+pub mod official;
+pub mod profile;
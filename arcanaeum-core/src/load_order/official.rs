@@ -0,0 +1,225 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io::Error;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+/**
+ * Plugins that come with every install of the game: the base masters, the resource pack
+ * added in 1.6.1130 and the Creation Club content given away with the Anniversary update.
+ */
+pub const SHIPPED_PLUGINS: [&str; 10] = [
+    "Skyrim.esm",
+    "Update.esm",
+    "Dawnguard.esm",
+    "HearthFires.esm",
+    "Dragonborn.esm",
+    "_ResourcePack.esl",
+    "ccBGSSSE001-Fish.esm",
+    "ccQDRSSE001-SurvivalMode.esl",
+    "ccBGSSSE037-Curios.esl",
+    "ccBGSSSE025-AdvDSGS.esm",
+];
+
+/**
+ * Creation Club plugins are named after the author's code and a number, like
+ * ccBGSSSE001-Fish.esm, which community mods starting with "cc" don't follow.
+ */
+fn has_creation_club_name(name: &str) -> bool {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN
+        .get_or_init(|| Regex::new(r"(?i)^cc[a-z]{3}sse\d{3}-").unwrap())
+        .is_match(name)
+}
+
+/**
+ * Skyrim.ccc lists the Creation Club plugins the game knows about, one per line.
+ */
+pub fn parse_ccc(contents: &str) -> Vec<String> {
+    contents
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .map(|line| line.to_string())
+        .collect()
+}
+
+/**
+ * Reads the Skyrim.ccc next to the Data folder.
+ */
+pub fn read_ccc(data_path: &Path) -> Result<Vec<String>, Error> {
+    let install_path = data_path.parent().unwrap_or(data_path);
+    Ok(parse_ccc(&fs::read_to_string(
+        install_path.join("Skyrim.ccc"),
+    )?))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum OfficialKind {
+    Shipped,
+    CreationClub,
+}
+
+/**
+ * Tells the plugins that came from Bethesda apart from mods.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct OfficialPlugins {
+    creation_club: HashSet<String>,
+}
+
+impl OfficialPlugins {
+    pub fn new(creation_club: &[String]) -> OfficialPlugins {
+        OfficialPlugins {
+            creation_club: creation_club.iter().map(|p| p.to_lowercase()).collect(),
+        }
+    }
+
+    /**
+     * Uses the game's Skyrim.ccc when it can be read, and the naming of Creation Club
+     * plugins otherwise.
+     */
+    pub fn from_data_path(data_path: Option<&Path>) -> OfficialPlugins {
+        match data_path.map(read_ccc) {
+            Some(Ok(creation_club)) => OfficialPlugins::new(&creation_club),
+            Some(Err(e)) => {
+                tracing::info!("Could not read Skyrim.ccc: {}", e);
+                OfficialPlugins::default()
+            }
+            None => OfficialPlugins::default(),
+        }
+    }
+
+    fn is_creation_club(&self, name: &str) -> bool {
+        self.creation_club.contains(&name.to_lowercase()) || has_creation_club_name(name)
+    }
+
+    pub fn kind(&self, name: &str) -> Option<OfficialKind> {
+        if SHIPPED_PLUGINS.iter().any(|p| p.eq_ignore_ascii_case(name)) {
+            Some(OfficialKind::Shipped)
+        } else if self.is_creation_club(name) {
+            Some(OfficialKind::CreationClub)
+        } else {
+            None
+        }
+    }
+
+    /**
+     * The Creation Club plugins among the given ones that are not in the Data folder,
+     * as happens when a save made with the Anniversary Edition is loaded without it. This
+     * includes the ones given away with the Anniversary update, which 1.5.97 doesn't have.
+     */
+    pub fn missing_creation_club(
+        &self,
+        plugins: &[String],
+        data_path: &Path,
+    ) -> Result<Vec<String>, Error> {
+        let installed: HashSet<String> = fs::read_dir(data_path)?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.file_name().to_string_lossy().to_lowercase())
+            .map(|name| match name.strip_suffix(".ghost") {
+                Some(name) => name.to_string(),
+                None => name,
+            })
+            .collect();
+        Ok(plugins
+            .iter()
+            .filter(|p| self.is_creation_club(p))
+            .filter(|p| !installed.contains(&p.to_lowercase()))
+            .cloned()
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn community_mods_starting_with_cc_are_not_creation_club() {
+        let official = OfficialPlugins::default();
+        assert_eq!(
+            official.kind("cceejsse001-hstead.esm"),
+            Some(OfficialKind::CreationClub)
+        );
+        assert_eq!(official.kind("ccbetterbows.esp"), None);
+        assert_eq!(
+            official.kind("_resourcepack.esl"),
+            Some(OfficialKind::Shipped)
+        );
+        assert_eq!(
+            official.kind("ccBGSSSE001-Fish.esm"),
+            Some(OfficialKind::Shipped)
+        );
+    }
+
+    #[test]
+    fn ccc_lists_creation_club_plugins() {
+        let ccc = parse_ccc("ccBGSSSE002-ExoticArrows.esl\r\n\r\nccOddNaming.esl\r\n");
+        assert_eq!(ccc, vec!["ccBGSSSE002-ExoticArrows.esl", "ccOddNaming.esl"]);
+        let official = OfficialPlugins::new(&ccc);
+        assert_eq!(
+            official.kind("ccoddnaming.esl"),
+            Some(OfficialKind::CreationClub)
+        );
+    }
+
+    #[test]
+    fn missing_creation_club_plugins() {
+        let install_path = env::temp_dir().join(format!("arcanaeum-ccc-{}", std::process::id()));
+        let data_path = install_path.join("Data");
+        fs::create_dir_all(&data_path).unwrap();
+        fs::write(
+            install_path.join("Skyrim.ccc"),
+            "ccBGSSSE002-ExoticArrows.esl\nccBGSSSE003-Zombies.esl\n",
+        )
+        .unwrap();
+        fs::write(data_path.join("ccBGSSSE002-ExoticArrows.esl"), "").unwrap();
+        fs::write(data_path.join("ccBGSSSE003-Zombies.esl.ghost"), "").unwrap();
+
+        let official = OfficialPlugins::from_data_path(Some(&data_path));
+        let plugins: Vec<String> = [
+            "Skyrim.esm",
+            "ccbgssse002-exoticarrows.esl",
+            "ccBGSSSE003-Zombies.esl",
+            "ccBGSSSE016-Umbra.esm",
+            "ccbetterbows.esp",
+        ]
+        .iter()
+        .map(|p| p.to_string())
+        .collect();
+        assert_eq!(
+            official
+                .missing_creation_club(&plugins, &data_path)
+                .unwrap(),
+            vec!["ccBGSSSE016-Umbra.esm"]
+        );
+        fs::remove_dir_all(install_path).unwrap();
+    }
+
+    #[test]
+    fn anniversary_saves_on_1_5_97_miss_the_free_creation_club() {
+        let data_path = env::temp_dir().join(format!("arcanaeum-1597-{}", std::process::id()));
+        fs::create_dir_all(&data_path).unwrap();
+        for master in &SHIPPED_PLUGINS[..5] {
+            fs::write(data_path.join(master), "").unwrap();
+        }
+
+        let plugins: Vec<String> = SHIPPED_PLUGINS.iter().map(|p| p.to_string()).collect();
+        assert_eq!(
+            OfficialPlugins::default()
+                .missing_creation_club(&plugins, &data_path)
+                .unwrap(),
+            vec![
+                "ccBGSSSE001-Fish.esm",
+                "ccQDRSSE001-SurvivalMode.esl",
+                "ccBGSSSE037-Curios.esl",
+                "ccBGSSSE025-AdvDSGS.esm",
+            ]
+        );
+        fs::remove_dir_all(data_path).unwrap();
+    }
+}
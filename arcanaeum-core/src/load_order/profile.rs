@@ -0,0 +1,368 @@
+use std::fs;
+use std::io::Error;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/**
+ * The base game masters are always loaded first and are left out of plugins.txt.
+ */
+pub const IMPLICIT_MASTERS: [&str; 5] = [
+    "Skyrim.esm",
+    "Update.esm",
+    "Dawnguard.esm",
+    "HearthFires.esm",
+    "Dragonborn.esm",
+];
+
+/**
+ * The masters the game loads without them being in plugins.txt. Skyrim LE only loads
+ * Skyrim.esm and Update.esm this way, its DLC have to be listed like any other plugin.
+ */
+pub fn get_implicit_masters(game: &str) -> &'static [&'static str] {
+    match game {
+        "skyrim" => &IMPLICIT_MASTERS[..2],
+        _ => &IMPLICIT_MASTERS,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProfilePlugin {
+    pub name: String,
+    pub enabled: bool,
+}
+
+fn content_lines(contents: &str) -> impl Iterator<Item = &str> {
+    contents
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+}
+
+/**
+ * Parses a plugins.txt. Skyrim SE marks enabled plugins with a '*' and keeps the disabled
+ * ones, while Skyrim LE only lists the enabled plugins, without a prefix.
+ */
+pub fn parse_plugins_txt(contents: &str, game: &str) -> Vec<ProfilePlugin> {
+    if game == "skyrim" {
+        return content_lines(contents)
+            .map(|line| ProfilePlugin {
+                name: line.to_string(),
+                enabled: true,
+            })
+            .collect();
+    }
+    content_lines(contents)
+        .map(|line| match line.strip_prefix('*') {
+            Some(name) => ProfilePlugin {
+                name: name.to_string(),
+                enabled: true,
+            },
+            None => ProfilePlugin {
+                name: line.to_string(),
+                enabled: false,
+            },
+        })
+        .collect()
+}
+
+pub fn parse_loadorder_txt(contents: &str) -> Vec<String> {
+    content_lines(contents)
+        .map(|line| line.to_string())
+        .collect()
+}
+
+/**
+ * Reads the load order of a profile folder. loadorder.txt, when there is one, holds the
+ * full order while plugins.txt says which plugins are enabled.
+ */
+pub fn read_profile_load_order(
+    profile_path: &Path,
+    game: &str,
+) -> Result<Vec<ProfilePlugin>, Error> {
+    let plugins_txt = fs::read_to_string(profile_path.join("plugins.txt"))?;
+    let plugins = parse_plugins_txt(&plugins_txt, game);
+
+    let load_order = match fs::read_to_string(profile_path.join("loadorder.txt")) {
+        Ok(contents) => parse_loadorder_txt(&contents),
+        Err(_) => return Ok(plugins),
+    };
+
+    let mut ordered: Vec<ProfilePlugin> = load_order
+        .iter()
+        .map(|name| ProfilePlugin {
+            name: name.clone(),
+            enabled: plugins
+                .iter()
+                .any(|p| p.enabled && p.name.eq_ignore_ascii_case(name))
+                || is_implicit_master(name, game),
+        })
+        .collect();
+
+    // Anything only in plugins.txt goes at the end, like the game does
+    for plugin in plugins {
+        if !ordered
+            .iter()
+            .any(|p| p.name.eq_ignore_ascii_case(&plugin.name))
+        {
+            ordered.push(plugin);
+        }
+    }
+    Ok(ordered)
+}
+
+/**
+ * The plugins the game will actually load for a profile, in order, including the base
+ * game masters that plugins.txt leaves out.
+ */
+pub fn get_active_plugins(profile_plugins: &[ProfilePlugin], game: &str) -> Vec<String> {
+    let mut active: Vec<String> = get_implicit_masters(game)
+        .iter()
+        .filter(|master| {
+            !profile_plugins
+                .iter()
+                .any(|p| p.name.eq_ignore_ascii_case(master))
+        })
+        .map(|master| master.to_string())
+        .collect();
+    active.extend(
+        profile_plugins
+            .iter()
+            .filter(|p| p.enabled)
+            .map(|p| p.name.clone()),
+    );
+    active
+}
+
+fn is_implicit_master(name: &str, game: &str) -> bool {
+    get_implicit_masters(game)
+        .iter()
+        .any(|m| m.eq_ignore_ascii_case(name))
+}
+
+/**
+ * Builds a profile load order matching a save: the save's plugins come first, enabled and in
+ * the save's order, then every other plugin of the profile, disabled.
+ */
+pub fn restore_load_order(profile: &[ProfilePlugin], saved: &[String]) -> Vec<ProfilePlugin> {
+    let find = |name: &str| profile.iter().find(|p| p.name.eq_ignore_ascii_case(name));
+
+    let mut restored: Vec<ProfilePlugin> = saved
+        .iter()
+        .map(|name| ProfilePlugin {
+            // Keep the profile's spelling of names that only differ in case
+            name: find(name).map_or_else(|| name.clone(), |p| p.name.clone()),
+            enabled: true,
+        })
+        .collect();
+    for plugin in profile {
+        if !saved
+            .iter()
+            .any(|name| name.eq_ignore_ascii_case(&plugin.name))
+        {
+            restored.push(ProfilePlugin {
+                name: plugin.name.clone(),
+                enabled: false,
+            });
+        }
+    }
+    restored
+}
+
+/**
+ * Writes a plugins.txt in the game's own format. Skyrim LE can't keep disabled plugins in
+ * it, their place in the load order is only kept by loadorder.txt.
+ */
+pub fn format_plugins_txt(plugins: &[ProfilePlugin], game: &str) -> String {
+    let mut contents = String::from("# This file was automatically generated by Arcanaeum.\r\n");
+    for plugin in plugins
+        .iter()
+        .filter(|p| !is_implicit_master(&p.name, game))
+    {
+        match game {
+            "skyrim" if !plugin.enabled => continue,
+            "skyrim" => {}
+            _ if plugin.enabled => contents.push('*'),
+            _ => {}
+        }
+        contents.push_str(&plugin.name);
+        contents.push_str("\r\n");
+    }
+    contents
+}
+
+pub fn format_loadorder_txt(plugins: &[ProfilePlugin]) -> String {
+    let mut contents = String::from("# This file was automatically generated by Arcanaeum.\r\n");
+    for plugin in plugins {
+        contents.push_str(&plugin.name);
+        contents.push_str("\r\n");
+    }
+    contents
+}
+
+/**
+ * Finds a free name for a backup next to the file: plugins.txt.bak, then plugins.txt.bak2
+ * and so on, so older backups are never overwritten.
+ */
+pub fn get_backup_path(path: &Path) -> PathBuf {
+    let file_name = path
+        .file_name()
+        .map(|f| f.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let mut backup = path.with_file_name(format!("{file_name}.bak"));
+    let mut number = 2;
+    while backup.exists() {
+        backup = path.with_file_name(format!("{file_name}.bak{number}"));
+        number += 1;
+    }
+    backup
+}
+
+/**
+ * Writes plugins.txt, and loadorder.txt if the profile has one, after backing up the
+ * current files. Returns the paths of the backups.
+ */
+pub fn write_profile_load_order(
+    profile_path: &Path,
+    plugins: &[ProfilePlugin],
+    game: &str,
+) -> Result<Vec<PathBuf>, Error> {
+    let plugins_path = profile_path.join("plugins.txt");
+    let load_order_path = profile_path.join("loadorder.txt");
+
+    let mut files = vec![(plugins_path, format_plugins_txt(plugins, game))];
+    if load_order_path.exists() {
+        files.push((load_order_path, format_loadorder_txt(plugins)));
+    }
+
+    let mut backups = Vec::new();
+    for (path, _) in &files {
+        if path.exists() {
+            let backup = get_backup_path(path);
+            fs::copy(path, &backup)?;
+            backups.push(backup);
+        }
+    }
+    for (path, contents) in &files {
+        fs::write(path, contents)?;
+    }
+    Ok(backups)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_plugins_txt_reads_enabled_state() {
+        let plugins = parse_plugins_txt(
+            "# This file is used by Skyrim to keep track of your downloaded content.\r\n\
+             *SkyUI_SE.esp\r\n\
+             Alternate Start - Live Another Life.esp\r\n",
+            "skyrimse",
+        );
+        assert_eq!(
+            plugins,
+            vec![
+                ProfilePlugin {
+                    name: "SkyUI_SE.esp".to_string(),
+                    enabled: true
+                },
+                ProfilePlugin {
+                    name: "Alternate Start - Live Another Life.esp".to_string(),
+                    enabled: false
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn active_plugins_start_with_the_base_game() {
+        let plugins = parse_plugins_txt(
+            "*Unofficial Skyrim Special Edition Patch.esp\nOff.esp",
+            "skyrimse",
+        );
+        let active = get_active_plugins(&plugins, "skyrimse");
+        assert_eq!(active.len(), 6);
+        assert_eq!(active[0], "Skyrim.esm");
+        assert_eq!(active[5], "Unofficial Skyrim Special Edition Patch.esp");
+    }
+
+    #[test]
+    fn restore_enables_saved_plugins_in_order() {
+        let profile = parse_plugins_txt("*B.esp\n*Extra.esp\nA.esp\n", "skyrimse");
+        let saved = vec![
+            "Skyrim.esm".to_string(),
+            "a.esp".to_string(),
+            "B.esp".to_string(),
+            "Gone.esp".to_string(),
+        ];
+        let restored = restore_load_order(&profile, &saved);
+
+        let names: Vec<&str> = restored.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["Skyrim.esm", "A.esp", "B.esp", "Gone.esp", "Extra.esp"]
+        );
+        assert!(restored[..4].iter().all(|p| p.enabled));
+        assert!(!restored[4].enabled);
+    }
+
+    #[test]
+    fn plugins_txt_leaves_out_the_base_game() {
+        let plugins = restore_load_order(&[], &["Skyrim.esm".to_string(), "A.esp".to_string()]);
+        let contents = format_plugins_txt(&plugins, "skyrimse");
+        assert_eq!(
+            parse_plugins_txt(&contents, "skyrimse"),
+            vec![ProfilePlugin {
+                name: "A.esp".to_string(),
+                enabled: true
+            }]
+        );
+        assert_eq!(
+            parse_loadorder_txt(&format_loadorder_txt(&plugins)).len(),
+            2
+        );
+    }
+
+    #[test]
+    fn le_plugins_txt_leaves_out_disabled_plugins() {
+        let plugins = restore_load_order(
+            &parse_plugins_txt("Off.esp\r\n", "skyrim"),
+            &[
+                "Skyrim.esm".to_string(),
+                "Update.esm".to_string(),
+                "Dawnguard.esm".to_string(),
+                "A.esp".to_string(),
+            ],
+        );
+        let contents = format_plugins_txt(&plugins, "skyrim");
+        assert!(!contents.contains('*'));
+        assert!(!contents.contains("Off.esp"));
+        assert_eq!(
+            parse_plugins_txt(&contents, "skyrim"),
+            vec![
+                ProfilePlugin {
+                    name: "Dawnguard.esm".to_string(),
+                    enabled: true
+                },
+                ProfilePlugin {
+                    name: "A.esp".to_string(),
+                    enabled: true
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn le_plugins_txt_only_lists_enabled_plugins() {
+        let plugins = parse_plugins_txt("Dawnguard.esm\r\nSkyUI.esp\r\n", "skyrim");
+        assert!(plugins.iter().all(|p| p.enabled));
+
+        let active = get_active_plugins(&plugins, "skyrim");
+        assert_eq!(
+            active,
+            vec!["Skyrim.esm", "Update.esm", "Dawnguard.esm", "SkyUI.esp"]
+        );
+    }
+}
@@ -0,0 +1,801 @@
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+
+use regex::{Regex, RegexBuilder};
+
+use crate::plugin_file::read_plugin_header;
+
+use super::file_crc;
+use super::version_info::{read_version_info, VersionInfo};
+
+/**
+ * What conditions are checked against. Paths are relative to the Data folder and use
+ * forward slashes.
+ */
+pub trait Environment {
+    fn file_exists(&self, path: &str) -> bool;
+
+    /**
+     * The names of the files in a folder, for paths whose file name is a regex.
+     */
+    fn list_folder(&self, folder: &str) -> Vec<String>;
+
+    fn active_plugins(&self) -> Vec<String>;
+
+    fn is_active(&self, plugin: &str) -> bool {
+        self.active_plugins()
+            .iter()
+            .any(|p| p.eq_ignore_ascii_case(plugin))
+    }
+
+    fn is_master(&self, plugin: &str) -> bool;
+
+    fn crc(&self, path: &str) -> Option<u32>;
+
+    /**
+     * The version of an existing file, if it can be read: the file version of executables
+     * and DLLs, or the one written in a plugin's description.
+     */
+    fn version(&self, path: &str) -> Option<String>;
+
+    /**
+     * The product version of an executable or DLL, which is often written differently
+     * from its file version.
+     */
+    fn product_version(&self, path: &str) -> Option<String>;
+
+    fn description(&self, plugin: &str) -> Option<String>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparator {
+    Equal,
+    NotEqual,
+    Less,
+    Greater,
+    LessOrEqual,
+    GreaterOrEqual,
+}
+
+impl Comparator {
+    fn parse(text: &str) -> Option<Comparator> {
+        match text {
+            "==" => Some(Comparator::Equal),
+            "!=" => Some(Comparator::NotEqual),
+            "<" => Some(Comparator::Less),
+            ">" => Some(Comparator::Greater),
+            "<=" => Some(Comparator::LessOrEqual),
+            ">=" => Some(Comparator::GreaterOrEqual),
+            _ => None,
+        }
+    }
+
+    fn matches(&self, ordering: Ordering) -> bool {
+        match self {
+            Comparator::Equal => ordering == Ordering::Equal,
+            Comparator::NotEqual => ordering != Ordering::Equal,
+            Comparator::Less => ordering == Ordering::Less,
+            Comparator::Greater => ordering == Ordering::Greater,
+            Comparator::LessOrEqual => ordering != Ordering::Greater,
+            Comparator::GreaterOrEqual => ordering != Ordering::Less,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Function {
+    File(String),
+    Readable(String),
+    Active(String),
+    Many(String),
+    ManyActive(String),
+    IsMaster(String),
+    Checksum(String, u32),
+    Version(String, String, Comparator),
+    ProductVersion(String, String, Comparator),
+    DescriptionContains(String, String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    Function(Function),
+    Not(Box<Condition>),
+    And(Vec<Condition>),
+    Or(Vec<Condition>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    Comma,
+    Text(String),
+    Word(String),
+}
+
+fn invalid_condition(message: String) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, Error> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '(' => tokens.push(Token::Open),
+            ')' => tokens.push(Token::Close),
+            ',' => tokens.push(Token::Comma),
+            '"' => {
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(c) => value.push(c),
+                        None => {
+                            return Err(invalid_condition(format!("Unclosed string in {text}")))
+                        }
+                    }
+                }
+                tokens.push(Token::Text(value));
+            }
+            c if c.is_whitespace() => {}
+            c => {
+                let mut word = c.to_string();
+                while let Some(c) = chars.peek() {
+                    if c.is_whitespace() || ['(', ')', ',', '"'].contains(c) {
+                        break;
+                    }
+                    word.push(*c);
+                    chars.next();
+                }
+                tokens.push(Token::Word(word));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), Error> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            token => Err(invalid_condition(format!(
+                "Expected {:?}, found {:?}",
+                expected, token
+            ))),
+        }
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Word(word)) if word == keyword)
+    }
+
+    fn expression(&mut self) -> Result<Condition, Error> {
+        let mut terms = vec![self.compound()?];
+        while self.is_keyword("or") {
+            self.next();
+            terms.push(self.compound()?);
+        }
+        Ok(match terms.len() {
+            1 => terms.remove(0),
+            _ => Condition::Or(terms),
+        })
+    }
+
+    fn compound(&mut self) -> Result<Condition, Error> {
+        let mut terms = vec![self.condition()?];
+        while self.is_keyword("and") {
+            self.next();
+            terms.push(self.condition()?);
+        }
+        Ok(match terms.len() {
+            1 => terms.remove(0),
+            _ => Condition::And(terms),
+        })
+    }
+
+    fn condition(&mut self) -> Result<Condition, Error> {
+        match self.next() {
+            Some(Token::Word(word)) if word == "not" => {
+                Ok(Condition::Not(Box::new(self.condition()?)))
+            }
+            Some(Token::Open) => {
+                let condition = self.expression()?;
+                self.expect(Token::Close)?;
+                Ok(condition)
+            }
+            Some(Token::Word(name)) => {
+                let arguments = self.arguments()?;
+                Ok(Condition::Function(build_function(&name, arguments)?))
+            }
+            token => Err(invalid_condition(format!(
+                "Expected a condition, found {:?}",
+                token
+            ))),
+        }
+    }
+
+    fn arguments(&mut self) -> Result<Vec<String>, Error> {
+        self.expect(Token::Open)?;
+        let mut arguments = Vec::new();
+        loop {
+            match self.next() {
+                Some(Token::Text(value)) | Some(Token::Word(value)) => arguments.push(value),
+                token => {
+                    return Err(invalid_condition(format!(
+                        "Expected an argument, found {:?}",
+                        token
+                    )))
+                }
+            }
+            match self.next() {
+                Some(Token::Comma) => continue,
+                Some(Token::Close) => return Ok(arguments),
+                token => {
+                    return Err(invalid_condition(format!(
+                        "Expected , or ), found {:?}",
+                        token
+                    )))
+                }
+            }
+        }
+    }
+}
+
+fn build_function(name: &str, arguments: Vec<String>) -> Result<Function, Error> {
+    let wrong_arguments =
+        || invalid_condition(format!("Wrong arguments for {name}: {:?}", arguments));
+    let comparator = |text: &str| Comparator::parse(text).ok_or_else(wrong_arguments);
+    let function = match (name, arguments.as_slice()) {
+        ("file", [path]) => Function::File(path.clone()),
+        ("readable", [path]) => Function::Readable(path.clone()),
+        ("active", [plugin]) => Function::Active(plugin.clone()),
+        ("many", [path]) => Function::Many(path.clone()),
+        ("many_active", [plugin]) => Function::ManyActive(plugin.clone()),
+        ("is_master", [plugin]) => Function::IsMaster(plugin.clone()),
+        ("checksum", [path, crc]) => Function::Checksum(
+            path.clone(),
+            u32::from_str_radix(crc.trim_start_matches("0x"), 16).map_err(|_| wrong_arguments())?,
+        ),
+        ("version", [path, version, op]) => {
+            Function::Version(path.clone(), version.clone(), comparator(op)?)
+        }
+        ("product_version", [path, version, op]) => {
+            Function::ProductVersion(path.clone(), version.clone(), comparator(op)?)
+        }
+        ("description_contains", [plugin, regex]) => {
+            Function::DescriptionContains(plugin.clone(), regex.clone())
+        }
+        _ => return Err(wrong_arguments()),
+    };
+    Ok(function)
+}
+
+pub fn parse_condition(text: &str) -> Result<Condition, Error> {
+    let mut parser = Parser {
+        tokens: tokenize(text)?,
+        position: 0,
+    };
+    let condition = parser.expression()?;
+    match parser.peek() {
+        None => Ok(condition),
+        Some(token) => Err(invalid_condition(format!(
+            "Unexpected {:?} in {}",
+            token, text
+        ))),
+    }
+}
+
+/**
+ * LOOT treats the file name part of a path as a regex when it contains any of `:\*?|`.
+ */
+fn split_regex_path(path: &str) -> Option<(&str, Regex)> {
+    let (folder, name) = match path.rsplit_once('/') {
+        Some((folder, name)) => (folder, name),
+        None => ("", path),
+    };
+    if !name.contains([':', '\\', '*', '?', '|']) {
+        return None;
+    }
+    match RegexBuilder::new(&format!("^(?:{})$", name))
+        .case_insensitive(true)
+        .build()
+    {
+        Ok(regex) => Some((folder, regex)),
+        Err(e) => {
+            tracing::error!("Invalid regex in condition path {}: {}", path, e);
+            None
+        }
+    }
+}
+
+fn count_files(env: &dyn Environment, path: &str) -> usize {
+    match split_regex_path(path) {
+        Some((folder, regex)) => env
+            .list_folder(folder)
+            .iter()
+            .filter(|name| regex.is_match(name))
+            .count(),
+        None => env.file_exists(path) as usize,
+    }
+}
+
+fn count_active(env: &dyn Environment, plugin: &str) -> usize {
+    match split_regex_path(plugin) {
+        Some((_, regex)) => env
+            .active_plugins()
+            .iter()
+            .filter(|name| regex.is_match(name))
+            .count(),
+        None => env.is_active(plugin) as usize,
+    }
+}
+
+fn version_parts(version: &str) -> Vec<String> {
+    version
+        .split(['.', '-', '_', ' '])
+        .filter(|part| !part.is_empty())
+        .map(|part| part.to_lowercase())
+        .collect()
+}
+
+/**
+ * Compares versions part by part, numerically where both parts are numbers. Missing parts
+ * count as 0, so 1.2 equals 1.2.0.
+ */
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let (a, b) = (version_parts(a), version_parts(b));
+    for index in 0..a.len().max(b.len()) {
+        let a = a.get(index).map(|p| p.as_str()).unwrap_or("0");
+        let b = b.get(index).map(|p| p.as_str()).unwrap_or("0");
+        let ordering = match (a.parse::<u64>(), b.parse::<u64>()) {
+            (Ok(a), Ok(b)) => a.cmp(&b),
+            _ => a.cmp(b),
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+/**
+ * A missing file has version 0. A file whose version can't be read never matches, rather
+ * than pretending to be version 0.
+ */
+fn version_matches(
+    env: &dyn Environment,
+    path: &str,
+    version: &str,
+    op: Comparator,
+    read_version: impl Fn(&str) -> Option<String>,
+) -> bool {
+    let actual = match env.file_exists(path) {
+        true => match read_version(path) {
+            Some(actual) => actual,
+            None => return false,
+        },
+        false => "0".to_string(),
+    };
+    op.matches(compare_versions(&actual, version))
+}
+
+impl Function {
+    pub fn eval(&self, env: &dyn Environment) -> bool {
+        match self {
+            Function::File(path) | Function::Readable(path) => count_files(env, path) > 0,
+            Function::Active(plugin) => count_active(env, plugin) > 0,
+            Function::Many(path) => count_files(env, path) > 1,
+            Function::ManyActive(plugin) => count_active(env, plugin) > 1,
+            Function::IsMaster(plugin) => env.is_master(plugin),
+            Function::Checksum(path, crc) => env.crc(path) == Some(*crc),
+            Function::Version(path, version, op) => {
+                version_matches(env, path, version, *op, |path| env.version(path))
+            }
+            Function::ProductVersion(path, version, op) => {
+                version_matches(env, path, version, *op, |path| env.product_version(path))
+            }
+            Function::DescriptionContains(plugin, regex) => {
+                let regex = RegexBuilder::new(regex).case_insensitive(true).build();
+                match (env.description(plugin), regex) {
+                    (Some(description), Ok(regex)) => regex.is_match(&description),
+                    _ => false,
+                }
+            }
+        }
+    }
+}
+
+impl Condition {
+    pub fn eval(&self, env: &dyn Environment) -> bool {
+        match self {
+            Condition::Function(function) => function.eval(env),
+            Condition::Not(condition) => !condition.eval(env),
+            Condition::And(conditions) => conditions.iter().all(|c| c.eval(env)),
+            Condition::Or(conditions) => conditions.iter().any(|c| c.eval(env)),
+        }
+    }
+}
+
+/**
+ * Evaluates a condition, treating one that can't be parsed as false so that its message
+ * isn't shown.
+ */
+pub fn evaluate_condition(text: &str, env: &dyn Environment) -> bool {
+    match parse_condition(text) {
+        Ok(condition) => condition.eval(env),
+        Err(e) => {
+            tracing::error!("Could not parse LOOT condition {}: {}", text, e);
+            false
+        }
+    }
+}
+
+/**
+ * Whether an optional condition of a metadata entry holds.
+ */
+pub fn applies(condition: &Option<String>, env: &dyn Environment) -> bool {
+    condition
+        .as_ref()
+        .is_none_or(|condition| evaluate_condition(condition, env))
+}
+
+/**
+ * Finds the version in a plugin's description, the way authors usually write it.
+ */
+pub fn extract_version(description: &str) -> Option<String> {
+    let regex =
+        Regex::new(r"(?i)\b(?:version|ver\.?|v)\s*[:.]?\s*(\d+(?:[.\-]\d+)*[a-z]?)\b").ok()?;
+    regex
+        .captures(description)
+        .map(|captures| captures[1].to_string())
+}
+
+/**
+ * The game's Data folder and the plugins active in the selected profile. Windows file
+ * names are case insensitive, so paths are looked up without case, and ghosted plugins
+ * count as present.
+ */
+pub struct InstallEnvironment {
+    data_path: Option<PathBuf>,
+    active: Vec<String>,
+    crcs: RefCell<HashMap<String, Option<u32>>>,
+    version_infos: RefCell<HashMap<String, Option<VersionInfo>>>,
+}
+
+fn is_executable(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("exe") || e.eq_ignore_ascii_case("dll"))
+}
+
+impl InstallEnvironment {
+    pub fn new(data_path: Option<&Path>, active: Vec<String>) -> InstallEnvironment {
+        InstallEnvironment {
+            data_path: data_path.map(|p| p.to_path_buf()),
+            active,
+            crcs: RefCell::new(HashMap::new()),
+            version_infos: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /**
+     * The version resource of an executable or DLL, read once per path.
+     */
+    fn version_info(&self, path: &str) -> Option<VersionInfo> {
+        let key = path.to_lowercase();
+        if let Some(info) = self.version_infos.borrow().get(&key) {
+            return info.clone();
+        }
+        let info = match self.resolve(path).map(|path| read_version_info(&path)) {
+            Some(Ok(info)) => Some(info),
+            Some(Err(e)) => {
+                tracing::info!("Could not read the version of {}: {}", path, e);
+                None
+            }
+            None => None,
+        };
+        self.version_infos.borrow_mut().insert(key, info.clone());
+        info
+    }
+
+    fn resolve(&self, path: &str) -> Option<PathBuf> {
+        let full_path = self.data_path.as_ref()?.join(path);
+        if full_path.exists() {
+            return Some(full_path);
+        }
+        let folder = full_path.parent()?;
+        let name = full_path.file_name()?.to_string_lossy().to_string();
+        let ghost = format!("{}.ghost", name);
+        fs::read_dir(folder)
+            .ok()?
+            .filter_map(|entry| entry.ok())
+            .find(|entry| {
+                let entry_name = entry.file_name().to_string_lossy().to_string();
+                entry_name.eq_ignore_ascii_case(&name) || entry_name.eq_ignore_ascii_case(&ghost)
+            })
+            .map(|entry| entry.path())
+    }
+}
+
+impl Environment for InstallEnvironment {
+    fn file_exists(&self, path: &str) -> bool {
+        self.resolve(path).is_some() || self.is_active(path)
+    }
+
+    fn list_folder(&self, folder: &str) -> Vec<String> {
+        let path = match &self.data_path {
+            Some(data_path) => data_path.join(folder),
+            None => return Vec::new(),
+        };
+        match fs::read_dir(path) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| {
+                    let name = entry.file_name().to_string_lossy().to_string();
+                    name.strip_suffix(".ghost")
+                        .map(String::from)
+                        .unwrap_or(name)
+                })
+                .collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    fn active_plugins(&self) -> Vec<String> {
+        self.active.clone()
+    }
+
+    fn is_master(&self, plugin: &str) -> bool {
+        self.resolve(plugin)
+            .and_then(|path| read_plugin_header(&path).ok())
+            .is_some_and(|header| header.is_master())
+    }
+
+    fn crc(&self, path: &str) -> Option<u32> {
+        let key = path.to_lowercase();
+        if let Some(crc) = self.crcs.borrow().get(&key) {
+            return *crc;
+        }
+        let crc = self.resolve(path).and_then(|path| file_crc(&path).ok());
+        self.crcs.borrow_mut().insert(key, crc);
+        crc
+    }
+
+    fn version(&self, path: &str) -> Option<String> {
+        match is_executable(path) {
+            true => self.version_info(path).map(|info| info.file_version),
+            false => extract_version(&self.description(path)?),
+        }
+    }
+
+    fn product_version(&self, path: &str) -> Option<String> {
+        match is_executable(path) {
+            true => self.version_info(path).map(|info| info.product_version),
+            false => None,
+        }
+    }
+
+    fn description(&self, plugin: &str) -> Option<String> {
+        read_plugin_header(&self.resolve(plugin)?).ok()?.description
+    }
+}
+
+#[cfg(test)]
+pub mod test_data {
+    use std::collections::HashMap;
+
+    use super::Environment;
+
+    /**
+     * An installation described in memory.
+     */
+    #[derive(Default)]
+    pub struct FakeEnvironment {
+        pub files: Vec<String>,
+        pub active: Vec<String>,
+        pub masters: Vec<String>,
+        pub crcs: HashMap<String, u32>,
+        pub versions: HashMap<String, String>,
+        pub product_versions: HashMap<String, String>,
+    }
+
+    impl Environment for FakeEnvironment {
+        fn file_exists(&self, path: &str) -> bool {
+            self.files.iter().any(|f| f.eq_ignore_ascii_case(path))
+        }
+
+        fn list_folder(&self, folder: &str) -> Vec<String> {
+            self.files
+                .iter()
+                .filter_map(|f| match f.rsplit_once('/') {
+                    Some((f_folder, name)) if f_folder == folder => Some(name.to_string()),
+                    None if folder.is_empty() => Some(f.clone()),
+                    _ => None,
+                })
+                .collect()
+        }
+
+        fn active_plugins(&self) -> Vec<String> {
+            self.active.clone()
+        }
+
+        fn is_master(&self, plugin: &str) -> bool {
+            self.masters.iter().any(|m| m.eq_ignore_ascii_case(plugin))
+        }
+
+        fn crc(&self, path: &str) -> Option<u32> {
+            self.crcs.get(path).copied()
+        }
+
+        fn version(&self, path: &str) -> Option<String> {
+            self.versions.get(path).cloned()
+        }
+
+        fn product_version(&self, path: &str) -> Option<String> {
+            self.product_versions.get(path).cloned()
+        }
+
+        fn description(&self, plugin: &str) -> Option<String> {
+            self.version(plugin).map(|v| format!("Version: {v}"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_data::FakeEnvironment;
+    use super::*;
+    use crate::loot::version_info::test_data::{dll, version_resource};
+
+    fn environment() -> FakeEnvironment {
+        FakeEnvironment {
+            files: vec![
+                "Skyrim.esm".to_string(),
+                "Cutting Room Floor.esp".to_string(),
+                "Disabled.esp".to_string(),
+                "SKSE/Plugins/po3_Tweaks.dll".to_string(),
+                "SKSE/Plugins/EngineFixes.dll".to_string(),
+                "skse64_loader.exe".to_string(),
+            ],
+            active: vec![
+                "Skyrim.esm".to_string(),
+                "Cutting Room Floor.esp".to_string(),
+            ],
+            masters: vec!["Skyrim.esm".to_string()],
+            crcs: HashMap::from([("Cutting Room Floor.esp".to_string(), 0x3D9F1C49)]),
+            versions: HashMap::from([
+                ("Cutting Room Floor.esp".to_string(), "3.1.2".to_string()),
+                ("skse64_loader.exe".to_string(), "0.2.0.17".to_string()),
+            ]),
+            product_versions: HashMap::from([(
+                "skse64_loader.exe".to_string(),
+                "2.0.17".to_string(),
+            )]),
+        }
+    }
+
+    fn check(condition: &str) -> bool {
+        parse_condition(condition).unwrap().eval(&environment())
+    }
+
+    #[test]
+    fn functions_check_the_installation() {
+        assert!(check(r#"file("Disabled.esp")"#));
+        assert!(!check(r#"active("Disabled.esp")"#));
+        assert!(check(r#"active("cutting room floor.esp")"#));
+        assert!(check(r#"is_master("Skyrim.esm")"#));
+        assert!(check(r#"checksum("Cutting Room Floor.esp", 3D9F1C49)"#));
+        assert!(!check(r#"checksum("Cutting Room Floor.esp", 12345678)"#));
+        assert!(check(r#"file("SKSE/Plugins/po3_.*\.dll")"#));
+        assert!(check(r#"many("SKSE/Plugins/.*\.dll")"#));
+        assert!(!check(r#"many_active(".*\.esp")"#));
+    }
+
+    #[test]
+    fn versions_are_compared_by_part() {
+        assert!(check(r#"version("Cutting Room Floor.esp", "3.1.10", <)"#));
+        assert!(check(r#"version("Cutting Room Floor.esp", "3.1.2.0", ==)"#));
+        assert!(check(r#"version("Missing.esp", "1.0", <)"#));
+        // The version of a DLL isn't known, so it never matches
+        assert!(!check(
+            r#"version("SKSE/Plugins/EngineFixes.dll", "6.0", <)"#
+        ));
+        assert_eq!(compare_versions("1.10", "1.9"), Ordering::Greater);
+    }
+
+    #[test]
+    fn product_versions_are_looked_up_on_their_own() {
+        assert!(check(r#"version("skse64_loader.exe", "0.2.0.17", ==)"#));
+        assert!(check(
+            r#"product_version("skse64_loader.exe", "2.0.17", ==)"#
+        ));
+        assert!(!check(
+            r#"product_version("skse64_loader.exe", "0.2.0.17", ==)"#
+        ));
+        // Plugins have no product version
+        assert!(!check(
+            r#"product_version("Cutting Room Floor.esp", "3.1.2", ==)"#
+        ));
+    }
+
+    #[test]
+    fn descriptions_are_matched_without_case() {
+        assert!(check(
+            r#"description_contains("Cutting Room Floor.esp", "VERSION: 3")"#
+        ));
+        assert!(!check(
+            r#"description_contains("Cutting Room Floor.esp", "beta")"#
+        ));
+    }
+
+    #[test]
+    fn executable_versions_come_from_their_resources() {
+        let data_path = std::env::temp_dir().join(format!("arcanaeum-exe-{}", std::process::id()));
+        fs::create_dir_all(&data_path).unwrap();
+        fs::write(
+            data_path.join("skse64_loader.exe"),
+            dll(&version_resource(Some("2.0.17"))),
+        )
+        .unwrap();
+        let env = InstallEnvironment::new(Some(&data_path), Vec::new());
+
+        assert_eq!(env.version("SKSE64_Loader.exe").as_deref(), Some("1.2.3.4"));
+        assert_eq!(
+            env.product_version("skse64_loader.exe").as_deref(),
+            Some("2.0.17")
+        );
+        fs::remove_dir_all(&data_path).unwrap();
+    }
+
+    #[test]
+    fn operators_nest_and_bind_like_loot() {
+        assert!(check(
+            r#"not file("Missing.esp") and (active("Disabled.esp") or active("Skyrim.esm"))"#
+        ));
+        assert!(check(
+            r#"file("Missing.esp") and active("Skyrim.esm") or file("Skyrim.esm")"#
+        ));
+        assert!(!check(r#"not (file("Skyrim.esm") or file("Missing.esp"))"#));
+    }
+
+    #[test]
+    fn malformed_conditions_are_rejected() {
+        assert!(parse_condition(r#"file("Skyrim.esm""#).is_err());
+        assert!(parse_condition(r#"unknown("Skyrim.esm")"#).is_err());
+        assert!(parse_condition(r#"file("a.esp") file("b.esp")"#).is_err());
+        assert!(!evaluate_condition(
+            r#"version("a.esp", "1", ~)"#,
+            &environment()
+        ));
+    }
+
+    #[test]
+    fn versions_are_found_in_descriptions() {
+        assert_eq!(
+            extract_version("Fixes bugs.\nVersion: 4.2.5b").as_deref(),
+            Some("4.2.5b")
+        );
+        assert_eq!(extract_version("v1.3 by someone").as_deref(), Some("1.3"));
+        assert_eq!(extract_version("No version here"), None);
+    }
+}
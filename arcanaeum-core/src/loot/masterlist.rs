@@ -0,0 +1,568 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MessageType {
+    Say,
+    Warn,
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LocalizedText {
+    pub text: String,
+    pub lang: String,
+}
+
+/**
+ * Message content is either plain text or a list of translations.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum MessageContent {
+    Text(String),
+    Localized(Vec<LocalizedText>),
+}
+
+impl MessageContent {
+    /**
+     * The English text, or the first translation when there is none.
+     */
+    pub fn text(&self) -> &str {
+        match self {
+            MessageContent::Text(text) => text,
+            MessageContent::Localized(texts) => texts
+                .iter()
+                .find(|t| t.lang == "en")
+                .or_else(|| texts.first())
+                .map(|t| t.text.as_str())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Message {
+    #[serde(rename = "type")]
+    pub kind: MessageType,
+    pub content: MessageContent,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub condition: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub subs: Vec<String>,
+}
+
+impl Message {
+    /**
+     * The text with its substitutions filled in. Older masterlists use `%1%`, newer ones
+     * `{0}`.
+     */
+    pub fn text(&self) -> String {
+        let mut text = self.content.text().to_string();
+        for (index, sub) in self.subs.iter().enumerate() {
+            text = text
+                .replace(&format!("{{{}}}", index), sub)
+                .replace(&format!("%{}%", index + 1), sub);
+        }
+        text
+    }
+}
+
+/**
+ * A file referenced by a plugin, written either as just its name or with a display name
+ * and a condition.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "FileRepr")]
+pub struct File {
+    pub name: String,
+    pub display: Option<String>,
+    pub condition: Option<String>,
+}
+
+impl File {
+    pub fn display_name(&self) -> &str {
+        self.display.as_deref().unwrap_or(&self.name)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum FileRepr {
+    Name(String),
+    Full {
+        name: String,
+        #[serde(default)]
+        display: Option<String>,
+        #[serde(default)]
+        condition: Option<String>,
+    },
+}
+
+impl From<FileRepr> for File {
+    fn from(repr: FileRepr) -> Self {
+        match repr {
+            FileRepr::Name(name) => File {
+                name,
+                display: None,
+                condition: None,
+            },
+            FileRepr::Full {
+                name,
+                display,
+                condition,
+            } => File {
+                name,
+                display,
+                condition,
+            },
+        }
+    }
+}
+
+/**
+ * A Bash tag suggestion. Tags written with a leading `-` are suggested for removal.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "TagRepr")]
+pub struct Tag {
+    pub name: String,
+    pub add: bool,
+    pub condition: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TagRepr {
+    Name(String),
+    Full {
+        name: String,
+        #[serde(default)]
+        condition: Option<String>,
+    },
+}
+
+impl From<TagRepr> for Tag {
+    fn from(repr: TagRepr) -> Self {
+        let (name, condition) = match repr {
+            TagRepr::Name(name) => (name, None),
+            TagRepr::Full { name, condition } => (name, condition),
+        };
+        match name.strip_prefix('-') {
+            Some(name) => Tag {
+                name: name.to_string(),
+                add: false,
+                condition,
+            },
+            None => Tag {
+                name,
+                add: true,
+                condition,
+            },
+        }
+    }
+}
+
+/**
+ * Where a plugin can be downloaded, either a bare URL or a link with a name.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "LocationRepr")]
+pub struct Location {
+    pub link: String,
+    pub name: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum LocationRepr {
+    Link(String),
+    Full {
+        link: String,
+        #[serde(default)]
+        name: Option<String>,
+    },
+}
+
+impl From<LocationRepr> for Location {
+    fn from(repr: LocationRepr) -> Self {
+        match repr {
+            LocationRepr::Link(link) => Location { link, name: None },
+            LocationRepr::Full { link, name } => Location { link, name },
+        }
+    }
+}
+
+/**
+ * Whether a version of a plugin, identified by its CRC, is dirty or has been checked
+ * clean, and with which utility.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CleaningData {
+    pub crc: u32,
+    pub util: String,
+    #[serde(default)]
+    pub itm: u32,
+    #[serde(default)]
+    pub udr: u32,
+    #[serde(default)]
+    pub nav: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<MessageContent>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Group {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub after: Vec<String>,
+}
+
+/**
+ * A plugin entry. The name is a regex when it contains any of `:\*?|`.
+ */
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PluginMetadata {
+    pub name: String,
+    pub url: Vec<Location>,
+    pub group: Option<String>,
+    pub after: Vec<File>,
+    pub req: Vec<File>,
+    pub inc: Vec<File>,
+    pub msg: Vec<Message>,
+    pub tag: Vec<Tag>,
+    pub dirty: Vec<CleaningData>,
+    pub clean: Vec<CleaningData>,
+}
+
+impl PluginMetadata {
+    pub fn is_regex(&self) -> bool {
+        self.name.contains([':', '\\', '*', '?', '|'])
+    }
+
+    /**
+     * Adds the metadata of another matching entry, leaving out what is already there. The
+     * group of the first entry wins.
+     */
+    pub fn merge(&mut self, other: &PluginMetadata) {
+        if self.group.is_none() {
+            self.group = other.group.clone();
+        }
+        extend_unique(&mut self.url, &other.url);
+        extend_unique(&mut self.after, &other.after);
+        extend_unique(&mut self.req, &other.req);
+        extend_unique(&mut self.inc, &other.inc);
+        extend_unique(&mut self.msg, &other.msg);
+        extend_unique(&mut self.tag, &other.tag);
+        extend_unique(&mut self.dirty, &other.dirty);
+        extend_unique(&mut self.clean, &other.clean);
+    }
+}
+
+fn extend_unique<T: Clone + PartialEq>(items: &mut Vec<T>, other: &[T]) {
+    for item in other {
+        if !items.contains(item) {
+            items.push(item.clone());
+        }
+    }
+}
+
+/**
+ * A LOOT masterlist. Other top level keys, like the `common` list that only holds YAML
+ * anchors, are ignored once their aliases have been resolved.
+ */
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Masterlist {
+    pub bash_tags: Vec<String>,
+    pub globals: Vec<Message>,
+    pub groups: Vec<Group>,
+    pub plugins: Vec<PluginMetadata>,
+}
+
+impl Masterlist {
+    /**
+     * Merges a userlist over the masterlist the way LOOT does: the userlist's group for a
+     * plugin replaces the masterlist's, everything else is added to it, and plugins or
+     * groups only in the userlist are added as they are.
+     */
+    pub fn merge_userlist(&mut self, userlist: &Masterlist) {
+        extend_unique(&mut self.bash_tags, &userlist.bash_tags);
+        extend_unique(&mut self.globals, &userlist.globals);
+        for group in &userlist.groups {
+            match self.groups.iter_mut().find(|g| g.name == group.name) {
+                Some(existing) => {
+                    extend_unique(&mut existing.after, &group.after);
+                    if group.description.is_some() {
+                        existing.description = group.description.clone();
+                    }
+                }
+                None => self.groups.push(group.clone()),
+            }
+        }
+        for plugin in &userlist.plugins {
+            match self
+                .plugins
+                .iter_mut()
+                .find(|p| p.name.eq_ignore_ascii_case(&plugin.name))
+            {
+                Some(existing) => {
+                    let group = plugin.group.clone().or_else(|| existing.group.take());
+                    existing.merge(plugin);
+                    existing.group = group;
+                }
+                None => self.plugins.push(plugin.clone()),
+            }
+        }
+    }
+}
+
+pub fn parse_masterlist(contents: &str) -> Result<Masterlist, Error> {
+    serde_yaml::from_str(contents).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+}
+
+pub fn read_masterlist(path: &Path) -> Result<Masterlist, Error> {
+    parse_masterlist(&fs::read_to_string(path)?)
+}
+
+/**
+ * A masterlist with its plugin entries indexed, for looking up plugins by name.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct MasterlistIndex {
+    pub masterlist: Masterlist,
+    names: HashMap<String, usize>,
+    patterns: Vec<(Regex, usize)>,
+}
+
+impl MasterlistIndex {
+    pub fn new(masterlist: Masterlist) -> MasterlistIndex {
+        let mut names = HashMap::new();
+        let mut patterns = Vec::new();
+        for (index, plugin) in masterlist.plugins.iter().enumerate() {
+            if !plugin.is_regex() {
+                names.entry(plugin.name.to_lowercase()).or_insert(index);
+                continue;
+            }
+            match RegexBuilder::new(&format!("^(?:{})$", plugin.name))
+                .case_insensitive(true)
+                .build()
+            {
+                Ok(regex) => patterns.push((regex, index)),
+                Err(e) => tracing::error!("Invalid plugin regex {}: {}", plugin.name, e),
+            }
+        }
+        MasterlistIndex {
+            masterlist,
+            names,
+            patterns,
+        }
+    }
+
+    /**
+     * The metadata for a plugin: its own entry merged with every regex entry matching it.
+     */
+    pub fn get(&self, plugin: &str) -> Option<PluginMetadata> {
+        let mut entries = self
+            .names
+            .get(&plugin.to_lowercase())
+            .into_iter()
+            .copied()
+            .chain(
+                self.patterns
+                    .iter()
+                    .filter(|(regex, _)| regex.is_match(plugin))
+                    .map(|(_, index)| *index),
+            )
+            .map(|index| &self.masterlist.plugins[index]);
+        let mut metadata = entries.next()?.clone();
+        metadata.name = plugin.to_string();
+        for entry in entries {
+            metadata.merge(entry);
+        }
+        Some(metadata)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MASTERLIST: &str = r#"
+common:
+  - &useUSSEP
+    type: say
+    content: 'Use the Unofficial Skyrim Special Edition Patch.'
+    condition: 'not active("Unofficial Skyrim Special Edition Patch.esp")'
+  - &obsolete
+    type: warn
+    content:
+      - lang: de
+        text: 'Veraltet, benutze stattdessen {0}.'
+      - lang: en
+        text: 'Obsolete, use {0} instead.'
+    subs: [ 'New Patch.esp' ]
+
+bash_tags:
+  - Delev
+  - Relev
+
+globals:
+  - type: error
+    content: 'You have not sorted your load order.'
+
+groups:
+  - name: default
+  - name: Fixes
+    description: 'Bug fixes'
+    after: [ default ]
+
+plugins:
+  - name: 'Old Patch.esp'
+    msg: [ *obsolete ]
+  - name: 'Cutting Room Floor.esp'
+    url:
+      - 'https://www.nexusmods.com/skyrimspecialedition/mods/276'
+      - link: 'https://www.nexusmods.com/skyrimspecialedition/mods/20141'
+        name: 'Cutting Room Floor Patches'
+    group: Fixes
+    req: [ 'Unofficial Skyrim Special Edition Patch.esp' ]
+    inc:
+      - name: 'Old CRF.esp'
+        display: 'the old Cutting Room Floor'
+    after: [ 'Alternate Start.esp' ]
+    msg: [ *useUSSEP ]
+    tag: [ Delev, -Relev, { name: C.Water, condition: 'file("Water.esp")' } ]
+    dirty:
+      - crc: 0x4C1A2E3F
+        util: 'SSEEdit v4.0.3'
+        itm: 12
+        udr: 3
+    clean:
+      - crc: 0x1234ABCD
+        util: 'SSEEdit v4.0.4'
+  - name: 'ccBGSSSE\d+-.*\.es[lm]'
+    msg:
+      - type: say
+        content: 'A Creation Club plugin.'
+"#;
+
+    #[test]
+    fn masterlist_reads_the_full_schema() {
+        let masterlist = parse_masterlist(MASTERLIST).unwrap();
+        assert_eq!(masterlist.bash_tags, vec!["Delev", "Relev"]);
+        assert_eq!(masterlist.globals[0].kind, MessageType::Error);
+        assert_eq!(masterlist.groups[1].after, vec!["default"]);
+
+        let crf = &masterlist.plugins[1];
+        assert_eq!(
+            crf.url[1].name.as_deref(),
+            Some("Cutting Room Floor Patches")
+        );
+        assert_eq!(crf.group.as_deref(), Some("Fixes"));
+        assert_eq!(
+            crf.req[0].name,
+            "Unofficial Skyrim Special Edition Patch.esp"
+        );
+        assert_eq!(crf.inc[0].display_name(), "the old Cutting Room Floor");
+        assert_eq!(crf.after[0].display_name(), "Alternate Start.esp");
+        assert_eq!(crf.msg[0].kind, MessageType::Say);
+        assert!(crf.msg[0].condition.is_some());
+        assert_eq!(
+            crf.tag,
+            vec![
+                Tag {
+                    name: "Delev".to_string(),
+                    add: true,
+                    condition: None
+                },
+                Tag {
+                    name: "Relev".to_string(),
+                    add: false,
+                    condition: None
+                },
+                Tag {
+                    name: "C.Water".to_string(),
+                    add: true,
+                    condition: Some("file(\"Water.esp\")".to_string())
+                },
+            ]
+        );
+        assert_eq!(crf.dirty[0].crc, 0x4C1A2E3F);
+        assert_eq!((crf.dirty[0].itm, crf.dirty[0].udr), (12, 3));
+        assert_eq!(crf.clean[0].crc, 0x1234ABCD);
+    }
+
+    #[test]
+    fn messages_are_translated_and_substituted() {
+        let masterlist = parse_masterlist(MASTERLIST).unwrap();
+        let message = &masterlist.plugins[0].msg[0];
+        assert_eq!(message.kind, MessageType::Warn);
+        assert_eq!(message.text(), "Obsolete, use New Patch.esp instead.");
+    }
+
+    #[test]
+    fn userlist_is_merged_over_the_masterlist() {
+        let mut masterlist = parse_masterlist(MASTERLIST).unwrap();
+        let userlist = parse_masterlist(
+            r#"
+groups:
+  - name: Fixes
+    after: [ Early Loaders ]
+  - name: Early Loaders
+plugins:
+  - name: 'cutting room floor.esp'
+    group: Early Loaders
+    url: [ 'https://example.com/crf', 'https://www.nexusmods.com/skyrimspecialedition/mods/276' ]
+    after: [ 'Our Patch.esp' ]
+    msg:
+      - type: warn
+        content: 'Load our patch after this.'
+  - name: 'Our Patch.esp'
+    group: Fixes
+"#,
+        )
+        .unwrap();
+        masterlist.merge_userlist(&userlist);
+
+        let fixes = masterlist
+            .groups
+            .iter()
+            .find(|g| g.name == "Fixes")
+            .unwrap();
+        assert_eq!(fixes.after, vec!["default", "Early Loaders"]);
+        assert_eq!(masterlist.groups.len(), 3);
+
+        let index = MasterlistIndex::new(masterlist);
+        let crf = index.get("Cutting Room Floor.esp").unwrap();
+        assert_eq!(crf.group.as_deref(), Some("Early Loaders"));
+        assert_eq!(crf.url.len(), 3);
+        let after: Vec<&str> = crf.after.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(after, vec!["Alternate Start.esp", "Our Patch.esp"]);
+        assert_eq!(crf.msg.last().unwrap().text(), "Load our patch after this.");
+        assert!(index.get("Our Patch.esp").is_some());
+    }
+
+    #[test]
+    fn regex_entries_are_merged_into_matching_plugins() {
+        let index = MasterlistIndex::new(parse_masterlist(MASTERLIST).unwrap());
+        assert!(index.masterlist.plugins[2].is_regex());
+
+        let fish = index.get("ccBGSSSE001-Fish.esm").unwrap();
+        assert_eq!(fish.name, "ccBGSSSE001-Fish.esm");
+        assert_eq!(fish.msg[0].text(), "A Creation Club plugin.");
+
+        let crf = index.get("cutting room floor.esp").unwrap();
+        assert_eq!(crf.url.len(), 2);
+        assert!(index.get("Unknown.esp").is_none());
+    }
+}
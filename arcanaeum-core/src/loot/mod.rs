@@ -0,0 +1,74 @@
+//! Reading LOOT's plugin metadata: the masterlist and userlists, and the warnings they have
+//! about the plugins in a load order.
+
+use std::env;
+use std::fs::File;
+use std::io::{Error, Read};
+use std::path::{Path, PathBuf};
+
+use crate::mod_manager::get_game_folder_name;
+use crate::mod_search::vortex_scanner::{get_masterlist_path, get_vortex_path};
+
+use masterlist::{read_masterlist, Masterlist};
+
+pub mod condition;
+pub mod masterlist;
+pub mod sorting;
+pub mod sources;
+pub mod version_info;
+pub mod warnings;
+
+/**
+ * The CRC-32 of a whole file, which LOOT uses to tell versions of a plugin apart.
+ */
+pub fn file_crc(path: &Path) -> Result<u32, Error> {
+    let mut file = File::open(path)?;
+    let mut hasher = crc32fast::Hasher::new();
+    let mut buf = vec![0; 64 * 1024];
+    loop {
+        let read = file.read(&mut buf)?;
+        if read == 0 {
+            return Ok(hasher.finalize());
+        }
+        hasher.update(&buf[..read]);
+    }
+}
+
+/**
+ * Where userlists for a game can be: LOOT's own, in the current and the older folder
+ * layout, and the one Vortex keeps next to its masterlist.
+ */
+pub fn get_userlist_paths(game: &str) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    if let Ok(local_app_data) = env::var("LOCALAPPDATA") {
+        let loot_path = PathBuf::from(local_app_data).join("LOOT");
+        let folder = get_game_folder_name(game);
+        paths.push(loot_path.join("games").join(folder).join("userlist.yaml"));
+        paths.push(loot_path.join(folder).join("userlist.yaml"));
+    }
+    if let Ok(vortex_path) = get_vortex_path() {
+        paths.push(vortex_path.join(game).join("userlist.yaml"));
+    }
+    paths
+}
+
+/**
+ * Reads a masterlist and merges the game's userlists over it.
+ */
+pub fn read_masterlist_with_userlists(path: &Path, game: &str) -> Result<Masterlist, Error> {
+    let mut masterlist = read_masterlist(path)?;
+    for userlist_path in get_userlist_paths(game).iter().filter(|p| p.exists()) {
+        match read_masterlist(userlist_path) {
+            Ok(userlist) => masterlist.merge_userlist(&userlist),
+            Err(e) => tracing::error!("Could not read userlist {:?}: {}", userlist_path, e),
+        }
+    }
+    Ok(masterlist)
+}
+
+/**
+ * The masterlist Vortex downloaded for the game, with any userlists merged over it.
+ */
+pub fn read_game_masterlist(game: &str) -> Result<Masterlist, Error> {
+    read_masterlist_with_userlists(&get_masterlist_path(game)?, game)
+}
@@ -0,0 +1,545 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::fmt::{self, Display};
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::load_order::profile::{ProfilePlugin, IMPLICIT_MASTERS};
+use crate::plugin_file::read_plugin_header;
+
+use super::condition::{applies, Environment};
+use super::masterlist::{Group, MasterlistIndex};
+
+const DEFAULT_GROUP: &str = "default";
+
+/**
+ * What the sorter needs to know about a plugin besides its metadata.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SortPlugin {
+    pub name: String,
+    pub is_master: bool,
+    pub masters: Vec<String>,
+}
+
+/**
+ * Why one plugin has to load before another.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EdgeKind {
+    Hardcoded,
+    Master,
+    Requirement,
+    LoadAfter,
+    MasterFlag,
+    Group,
+    PluginGroup,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Edge {
+    pub from: String,
+    pub to: String,
+    pub kind: EdgeKind,
+}
+
+impl Display for Edge {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (from, to) = (&self.from, &self.to);
+        match self.kind {
+            EdgeKind::Hardcoded => write!(f, "{from} is always loaded before {to}"),
+            EdgeKind::Master => write!(f, "{from} is a master of {to}"),
+            EdgeKind::Requirement => write!(f, "{to} requires {from}"),
+            EdgeKind::LoadAfter => write!(f, "{to} loads after {from}"),
+            EdgeKind::MasterFlag => write!(f, "{from} is a master file and {to} is not"),
+            EdgeKind::Group => write!(f, "group {to} loads after group {from}"),
+            EdgeKind::PluginGroup => {
+                write!(f, "the group of {to} loads after the group of {from}")
+            }
+        }
+    }
+}
+
+/**
+ * Rules that contradict each other, so no order can satisfy them all.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Cycle {
+    pub edges: Vec<Edge>,
+}
+
+impl Display for Cycle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let edges: Vec<String> = self.edges.iter().map(|e| e.to_string()).collect();
+        write!(f, "Cycle: {}", edges.join(", "))
+    }
+}
+
+/**
+ * A graph over indexes, remembering why each edge was added.
+ */
+struct Graph {
+    names: Vec<String>,
+    incoming: Vec<Vec<(usize, EdgeKind)>>,
+    outgoing: Vec<Vec<usize>>,
+    edges: HashSet<(usize, usize)>,
+}
+
+impl Graph {
+    fn new(names: Vec<String>) -> Graph {
+        let count = names.len();
+        Graph {
+            names,
+            incoming: vec![Vec::new(); count],
+            outgoing: vec![Vec::new(); count],
+            edges: HashSet::new(),
+        }
+    }
+
+    /**
+     * Adds an edge unless there already is one, so the first reason given is kept.
+     */
+    fn add(&mut self, from: usize, to: usize, kind: EdgeKind) {
+        if from != to && self.edges.insert((from, to)) {
+            self.incoming[to].push((from, kind));
+            self.outgoing[from].push(to);
+        }
+    }
+
+    /**
+     * The nodes that can be reached from a node by following its edges.
+     */
+    fn reachable_from(&self, node: usize) -> Vec<bool> {
+        let mut reached = vec![false; self.names.len()];
+        let mut stack = vec![node];
+        while let Some(node) = stack.pop() {
+            for next in &self.outgoing[node] {
+                if !reached[*next] {
+                    reached[*next] = true;
+                    stack.push(*next);
+                }
+            }
+        }
+        reached
+    }
+
+    /**
+     * Topologically sorts the graph, taking the ready node with the lowest priority first.
+     * When nodes are left over they are part of a cycle, which is returned instead.
+     */
+    fn sort<P: Ord>(&self, priority: impl Fn(usize) -> P) -> Result<Vec<usize>, Cycle> {
+        let mut in_degree: Vec<usize> = self.incoming.iter().map(|i| i.len()).collect();
+        let mut ready: BinaryHeap<Reverse<(P, usize)>> = (0..self.names.len())
+            .filter(|node| in_degree[*node] == 0)
+            .map(|node| Reverse((priority(node), node)))
+            .collect();
+        let mut sorted = Vec::new();
+        while let Some(Reverse((_, node))) = ready.pop() {
+            sorted.push(node);
+            for next in &self.outgoing[node] {
+                in_degree[*next] -= 1;
+                if in_degree[*next] == 0 {
+                    ready.push(Reverse((priority(*next), *next)));
+                }
+            }
+        }
+        match sorted.len() == self.names.len() {
+            true => Ok(sorted),
+            false => Err(self.find_cycle(&in_degree)),
+        }
+    }
+
+    /**
+     * Every node left with incoming edges has a predecessor that is also left, so walking
+     * back from any of them ends up going round a cycle.
+     */
+    fn find_cycle(&self, in_degree: &[usize]) -> Cycle {
+        let mut node = (0..in_degree.len())
+            .find(|node| in_degree[*node] > 0)
+            .unwrap_or_default();
+        let mut path: Vec<(usize, usize, EdgeKind)> = Vec::new();
+        let mut visited = HashMap::new();
+        while !visited.contains_key(&node) {
+            visited.insert(node, path.len());
+            let (from, kind) = match self.incoming[node]
+                .iter()
+                .find(|(from, _)| in_degree[*from] > 0)
+            {
+                Some(edge) => *edge,
+                None => break,
+            };
+            path.push((from, node, kind));
+            node = from;
+        }
+        let start = visited.get(&node).copied().unwrap_or_default();
+        let mut edges: Vec<Edge> = path[start..]
+            .iter()
+            .map(|(from, to, kind)| Edge {
+                from: self.names[*from].clone(),
+                to: self.names[*to].clone(),
+                kind: *kind,
+            })
+            .collect();
+        edges.reverse();
+        Cycle { edges }
+    }
+}
+
+/**
+ * Where a group loads relative to the others.
+ */
+struct GroupPosition {
+    index: usize,
+    /**
+     * How many groups it loads after in a row, to order plugins whose group edges were
+     * left out.
+     */
+    level: usize,
+    /**
+     * The indexes of every group it loads after, directly or through other groups.
+     */
+    after: HashSet<usize>,
+}
+
+fn get_group_positions(groups: &[Group]) -> Result<HashMap<String, GroupPosition>, Cycle> {
+    let mut graph = Graph::new(groups.iter().map(|g| g.name.clone()).collect());
+    let positions: HashMap<&str, usize> = groups
+        .iter()
+        .enumerate()
+        .map(|(index, group)| (group.name.as_str(), index))
+        .collect();
+    for (index, group) in groups.iter().enumerate() {
+        for after in &group.after {
+            if let Some(after) = positions.get(after.as_str()) {
+                graph.add(*after, index, EdgeKind::Group);
+            }
+        }
+    }
+    let order = graph.sort(|node| node)?;
+    let mut levels = vec![0; groups.len()];
+    let mut after: Vec<HashSet<usize>> = vec![HashSet::new(); groups.len()];
+    for node in order {
+        for next in &graph.outgoing[node] {
+            levels[*next] = levels[*next].max(levels[node] + 1);
+            let inherited = after[node].clone();
+            after[*next].extend(inherited);
+            after[*next].insert(node);
+        }
+    }
+    Ok(groups
+        .iter()
+        .zip(levels)
+        .zip(after)
+        .enumerate()
+        .map(|(index, ((group, level), after))| {
+            let position = GroupPosition {
+                index,
+                level,
+                after,
+            };
+            (group.name.clone(), position)
+        })
+        .collect())
+}
+
+/**
+ * Sorts plugins the way LOOT does. The hardcoded masters, masters, requirements, load
+ * after rules and master flags must all hold. Plugins then load after the plugins of the
+ * groups their group loads after, unless that contradicts those rules, and otherwise keep
+ * their current order, which is the order they are given in.
+ */
+pub fn sort_plugins(
+    plugins: &[SortPlugin],
+    index: &MasterlistIndex,
+    env: &dyn Environment,
+) -> Result<Vec<String>, Cycle> {
+    let group_positions = get_group_positions(&index.masterlist.groups)?;
+    let positions: HashMap<String, usize> = plugins
+        .iter()
+        .enumerate()
+        .map(|(position, plugin)| (plugin.name.to_lowercase(), position))
+        .collect();
+    let find = |name: &str| positions.get(&name.to_lowercase()).copied();
+    let mut graph = Graph::new(plugins.iter().map(|p| p.name.clone()).collect());
+
+    let hardcoded: Vec<usize> = IMPLICIT_MASTERS.iter().filter_map(|m| find(m)).collect();
+    for (rank, master) in hardcoded.iter().enumerate() {
+        for (position, _) in plugins.iter().enumerate() {
+            if !hardcoded[..=rank].contains(&position) {
+                graph.add(*master, position, EdgeKind::Hardcoded);
+            }
+        }
+    }
+
+    let mut plugin_groups = Vec::new();
+    for (position, plugin) in plugins.iter().enumerate() {
+        for master in plugin.masters.iter().filter_map(|m| find(m)) {
+            graph.add(master, position, EdgeKind::Master);
+        }
+        let metadata = index.get(&plugin.name).unwrap_or_default();
+        let rules = [
+            (&metadata.req, EdgeKind::Requirement),
+            (&metadata.after, EdgeKind::LoadAfter),
+        ];
+        for (files, kind) in rules {
+            for file in files.iter().filter(|file| applies(&file.condition, env)) {
+                if let Some(before) = find(&file.name) {
+                    graph.add(before, position, kind);
+                }
+            }
+        }
+        let group = metadata.group.as_deref().unwrap_or(DEFAULT_GROUP);
+        plugin_groups.push(group_positions.get(group));
+    }
+
+    for (master, _) in plugins.iter().enumerate().filter(|(_, p)| p.is_master) {
+        for (position, _) in plugins.iter().enumerate().filter(|(_, p)| !p.is_master) {
+            graph.add(master, position, EdgeKind::MasterFlag);
+        }
+    }
+
+    // Like LOOT, a group edge is left out when the rules above already put the plugins the
+    // other way round
+    for (position, group) in plugin_groups.iter().enumerate() {
+        let after = match group {
+            Some(group) if !group.after.is_empty() => &group.after,
+            _ => continue,
+        };
+        let reachable = graph.reachable_from(position);
+        for (before, before_group) in plugin_groups.iter().enumerate() {
+            if before_group.is_some_and(|g| after.contains(&g.index)) && !reachable[before] {
+                graph.add(before, position, EdgeKind::PluginGroup);
+            }
+        }
+    }
+
+    let sorted = graph.sort(|node| (plugin_groups[node].map_or(0, |g| g.level), node))?;
+    Ok(sorted
+        .into_iter()
+        .map(|node| plugins[node].name.clone())
+        .collect())
+}
+
+/**
+ * Reads the master flag and masters of plugins from their headers, finding their files with
+ * `find_plugin`. Plugins that can't be read are treated as having no masters.
+ */
+pub fn read_sort_plugins(
+    names: &[String],
+    find_plugin: &dyn Fn(&str) -> Option<PathBuf>,
+) -> Vec<SortPlugin> {
+    names
+        .iter()
+        .map(
+            |name| match find_plugin(name).map(|path| read_plugin_header(&path)) {
+                Some(Ok(header)) => SortPlugin {
+                    name: name.clone(),
+                    is_master: header.is_master(),
+                    masters: header.masters,
+                },
+                _ => SortPlugin {
+                    name: name.clone(),
+                    is_master: [".esm", ".esl"]
+                        .iter()
+                        .any(|e| name.to_lowercase().ends_with(e)),
+                    masters: Vec::new(),
+                },
+            },
+        )
+        .collect()
+}
+
+/**
+ * Sorts a profile's load order, keeping whether each plugin is enabled.
+ */
+pub fn sort_load_order(
+    load_order: &[ProfilePlugin],
+    find_plugin: &dyn Fn(&str) -> Option<PathBuf>,
+    index: &MasterlistIndex,
+    env: &dyn Environment,
+) -> Result<Vec<ProfilePlugin>, Cycle> {
+    let names: Vec<String> = load_order.iter().map(|p| p.name.clone()).collect();
+    let sorted = sort_plugins(&read_sort_plugins(&names, find_plugin), index, env)?;
+    let enabled: HashMap<String, bool> = load_order
+        .iter()
+        .map(|p| (p.name.to_lowercase(), p.enabled))
+        .collect();
+    Ok(sorted
+        .into_iter()
+        .map(|name| ProfilePlugin {
+            enabled: enabled.get(&name.to_lowercase()).copied().unwrap_or(true),
+            name,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loot::condition::test_data::FakeEnvironment;
+    use crate::loot::masterlist::parse_masterlist;
+
+    fn plugin(name: &str, masters: &[&str]) -> SortPlugin {
+        SortPlugin {
+            name: name.to_string(),
+            is_master: name.ends_with(".esm"),
+            masters: masters.iter().map(|m| m.to_string()).collect(),
+        }
+    }
+
+    fn index(masterlist: &str) -> MasterlistIndex {
+        MasterlistIndex::new(parse_masterlist(masterlist).unwrap())
+    }
+
+    const MASTERLIST: &str = r#"
+groups:
+  - name: Early Loaders
+  - name: default
+    after: [ Early Loaders ]
+  - name: Late Loaders
+    after: [ default ]
+plugins:
+  - name: 'Alternate Start.esp'
+    group: Early Loaders
+  - name: 'Bashed Patch, 0.esp'
+    group: Late Loaders
+  - name: 'Patch.esp'
+    after: [ 'Cutting Room Floor.esp' ]
+    req: [ 'Lore.esp' ]
+"#;
+
+    #[test]
+    fn rules_and_groups_are_applied() {
+        let plugins = vec![
+            plugin("Bashed Patch, 0.esp", &[]),
+            plugin("Patch.esp", &[]),
+            plugin("Update.esm", &["Skyrim.esm"]),
+            plugin("Cutting Room Floor.esp", &["Skyrim.esm"]),
+            plugin("Lore.esp", &[]),
+            plugin("Alternate Start.esp", &["Skyrim.esm"]),
+            plugin("Unofficial Patch.esm", &["Skyrim.esm"]),
+            plugin("Skyrim.esm", &[]),
+        ];
+        let sorted = sort_plugins(&plugins, &index(MASTERLIST), &FakeEnvironment::default());
+        assert_eq!(
+            sorted.unwrap(),
+            vec![
+                "Skyrim.esm",
+                "Update.esm",
+                "Unofficial Patch.esm",
+                "Alternate Start.esp",
+                "Cutting Room Floor.esp",
+                "Lore.esp",
+                "Patch.esp",
+                "Bashed Patch, 0.esp",
+            ]
+        );
+    }
+
+    #[test]
+    fn groups_order_plugins_their_rules_do_not() {
+        let masterlist = r#"
+groups:
+  - name: default
+  - name: Late Loaders
+    after: [ default ]
+plugins:
+  - name: 'Late A.esp'
+    group: Late Loaders
+  - name: 'Late B.esp'
+    group: Late Loaders
+  - name: 'Patch.esp'
+    after: [ 'Late B.esp' ]
+"#;
+        let plugins = vec![
+            plugin("Late A.esp", &[]),
+            plugin("Late B.esp", &[]),
+            plugin("Patch.esp", &[]),
+        ];
+        // Only using groups to pick between plugins that are ready would load Late A.esp
+        // first, as nothing else has to load before it
+        let sorted = sort_plugins(&plugins, &index(masterlist), &FakeEnvironment::default());
+        assert_eq!(
+            sorted.unwrap(),
+            vec!["Late B.esp", "Patch.esp", "Late A.esp"]
+        );
+    }
+
+    #[test]
+    fn cycles_are_explained() {
+        let masterlist = r#"
+plugins:
+  - name: 'Lore.esp'
+    after: [ 'Patch.esp' ]
+"#;
+        let plugins = vec![
+            plugin("Skyrim.esm", &[]),
+            plugin("Lore.esp", &[]),
+            plugin("Patch.esp", &["Lore.esp"]),
+        ];
+        let cycle =
+            sort_plugins(&plugins, &index(masterlist), &FakeEnvironment::default()).unwrap_err();
+        assert_eq!(cycle.edges.len(), 2);
+        let explanation = cycle.to_string();
+        assert!(explanation.contains("Lore.esp is a master of Patch.esp"));
+        assert!(explanation.contains("Lore.esp loads after Patch.esp"));
+    }
+
+    #[test]
+    fn group_cycles_are_reported() {
+        let masterlist = r#"
+groups:
+  - name: A
+    after: [ B ]
+  - name: B
+    after: [ A ]
+"#;
+        let cycle = sort_plugins(&[], &index(masterlist), &FakeEnvironment::default()).unwrap_err();
+        assert_eq!(cycle.edges[0].kind, EdgeKind::Group);
+    }
+
+    #[test]
+    fn conditional_rules_only_apply_when_they_hold() {
+        let masterlist = r#"
+plugins:
+  - name: 'A.esp'
+    after:
+      - name: 'B.esp'
+        condition: 'active("C.esp")'
+"#;
+        let plugins = vec![plugin("A.esp", &[]), plugin("B.esp", &[])];
+        let sorted = sort_plugins(&plugins, &index(masterlist), &FakeEnvironment::default());
+        assert_eq!(sorted.unwrap(), vec!["A.esp", "B.esp"]);
+
+        let env = FakeEnvironment {
+            active: vec!["C.esp".to_string()],
+            ..Default::default()
+        };
+        let sorted = sort_plugins(&plugins, &index(masterlist), &env);
+        assert_eq!(sorted.unwrap(), vec!["B.esp", "A.esp"]);
+    }
+
+    #[test]
+    fn enabled_state_is_kept() {
+        let load_order = vec![
+            ProfilePlugin {
+                name: "Patch.esp".to_string(),
+                enabled: false,
+            },
+            ProfilePlugin {
+                name: "Skyrim.esm".to_string(),
+                enabled: true,
+            },
+        ];
+        let sorted = sort_load_order(
+            &load_order,
+            &|_| None,
+            &MasterlistIndex::default(),
+            &FakeEnvironment::default(),
+        )
+        .unwrap();
+        assert_eq!(sorted[0].name, "Skyrim.esm");
+        assert!(!sorted[1].enabled);
+    }
+}
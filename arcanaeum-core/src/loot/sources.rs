@@ -0,0 +1,282 @@
+use std::collections::HashMap;
+use std::env;
+use std::fmt::{self, Display};
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+use crate::mod_manager::get_game_folder_name;
+use crate::mod_search::mo2_scanner::find_install_paths;
+use crate::mod_search::vortex_scanner::get_masterlist_path;
+
+use super::masterlist::MasterlistIndex;
+use super::{get_userlist_paths, read_masterlist_with_userlists};
+
+const MASTERLIST_FILE: &str = "masterlist.yaml";
+
+/**
+ * Where to read the masterlist from. `Auto` takes the most recently updated one found.
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MasterlistSource {
+    #[default]
+    Auto,
+    Vortex,
+    Loot,
+    ModOrganizer2,
+    /**
+     * A masterlist.yaml, or a folder holding one such as a clone of LOOT's masterlist
+     * repository.
+     */
+    Custom(PathBuf),
+}
+
+impl MasterlistSource {
+    pub const FOUND: [MasterlistSource; 4] = [
+        MasterlistSource::Auto,
+        MasterlistSource::Vortex,
+        MasterlistSource::Loot,
+        MasterlistSource::ModOrganizer2,
+    ];
+}
+
+impl Display for MasterlistSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MasterlistSource::Auto => write!(f, "Newest found"),
+            MasterlistSource::Vortex => write!(f, "Vortex"),
+            MasterlistSource::Loot => write!(f, "LOOT"),
+            MasterlistSource::ModOrganizer2 => write!(f, "Mod Organizer 2"),
+            MasterlistSource::Custom(path) => write!(f, "{}", path.to_string_lossy()),
+        }
+    }
+}
+
+/**
+ * LOOT keeps its data in %LOCALAPPDATA%/LOOT, under games/ since 0.18 and directly in it
+ * before.
+ */
+fn get_loot_masterlist_paths(game: &str) -> Vec<PathBuf> {
+    let local_app_data = match env::var("LOCALAPPDATA") {
+        Ok(local_app_data) => PathBuf::from(local_app_data),
+        Err(_) => return Vec::new(),
+    };
+    let loot_path = local_app_data.join("LOOT");
+    let folder = get_game_folder_name(game);
+    vec![
+        loot_path.join("games").join(folder).join(MASTERLIST_FILE),
+        loot_path.join(folder).join(MASTERLIST_FILE),
+    ]
+}
+
+/**
+ * MO2's LOOT integration keeps the masterlist in a loot folder next to the executable.
+ */
+fn get_mo2_masterlist_paths(game: &str) -> Vec<PathBuf> {
+    let folder = get_game_folder_name(game);
+    find_install_paths()
+        .into_iter()
+        .map(|path| path.join("loot").join(folder).join(MASTERLIST_FILE))
+        .collect()
+}
+
+fn get_custom_masterlist_path(path: &Path) -> PathBuf {
+    match path.is_dir() {
+        true => path.join(MASTERLIST_FILE),
+        false => path.to_path_buf(),
+    }
+}
+
+/**
+ * The places a source may have a masterlist for the game, whether or not they exist.
+ */
+pub fn get_candidate_paths(game: &str, source: &MasterlistSource) -> Vec<PathBuf> {
+    match source {
+        MasterlistSource::Auto => [
+            MasterlistSource::Loot,
+            MasterlistSource::Vortex,
+            MasterlistSource::ModOrganizer2,
+        ]
+        .iter()
+        .flat_map(|source| get_candidate_paths(game, source))
+        .collect(),
+        MasterlistSource::Vortex => get_masterlist_path(game).into_iter().collect(),
+        MasterlistSource::Loot => get_loot_masterlist_paths(game),
+        MasterlistSource::ModOrganizer2 => get_mo2_masterlist_paths(game),
+        MasterlistSource::Custom(path) => vec![get_custom_masterlist_path(path)],
+    }
+}
+
+fn get_modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/**
+ * The most recently updated of the paths that exist.
+ */
+pub fn find_newest(paths: &[PathBuf]) -> Option<PathBuf> {
+    paths
+        .iter()
+        .filter_map(|path| Some((get_modified(path)?, path)))
+        .max_by_key(|(modified, _)| *modified)
+        .map(|(_, path)| path.clone())
+}
+
+pub fn find_masterlist(game: &str, source: &MasterlistSource) -> Result<PathBuf, Error> {
+    let candidates = get_candidate_paths(game, source);
+    find_newest(&candidates).ok_or_else(|| {
+        let looked_in: Vec<String> = candidates
+            .iter()
+            .map(|p| p.to_string_lossy().to_string())
+            .collect();
+        Error::new(
+            ErrorKind::NotFound,
+            format!(
+                "No masterlist for {} from {}, looked in: {}",
+                game,
+                source,
+                looked_in.join(", ")
+            ),
+        )
+    })
+}
+
+/**
+ * The files a parsed masterlist was read from and when they were last modified.
+ */
+#[derive(Debug, Clone, PartialEq)]
+struct Stamp {
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+}
+
+impl Stamp {
+    fn new(path: &Path, game: &str) -> Stamp {
+        let files = std::iter::once(path.to_path_buf())
+            .chain(get_userlist_paths(game))
+            .map(|path| {
+                let modified = get_modified(&path);
+                (path, modified)
+            })
+            .collect();
+        Stamp { files }
+    }
+}
+
+struct CachedMasterlist {
+    stamp: Stamp,
+    index: Arc<MasterlistIndex>,
+}
+
+/**
+ * Parsed masterlists by game and source, read again only when one of their files changed.
+ */
+#[derive(Default)]
+pub struct MasterlistCache {
+    entries: Mutex<HashMap<(String, MasterlistSource), CachedMasterlist>>,
+}
+
+impl MasterlistCache {
+    pub fn get(
+        &self,
+        game: &str,
+        source: &MasterlistSource,
+    ) -> Result<Arc<MasterlistIndex>, Error> {
+        let path = find_masterlist(game, source)?;
+        let stamp = Stamp::new(&path, game);
+        let key = (game.to_string(), source.clone());
+        if let Some(cached) = self.entries.lock().unwrap().get(&key) {
+            if cached.stamp == stamp {
+                return Ok(cached.index.clone());
+            }
+        }
+
+        tracing::info!("Reading masterlist {:?}", path);
+        let masterlist = read_masterlist_with_userlists(&path, game)?;
+        let index = Arc::new(MasterlistIndex::new(masterlist));
+        let cached = CachedMasterlist {
+            stamp,
+            index: index.clone(),
+        };
+        self.entries.lock().unwrap().insert(key, cached);
+        Ok(index)
+    }
+}
+
+/**
+ * The cache shared by everything that needs a masterlist.
+ */
+pub fn masterlist_cache() -> &'static MasterlistCache {
+    static CACHE: OnceLock<MasterlistCache> = OnceLock::new();
+    CACHE.get_or_init(MasterlistCache::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn temp_folder(name: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("arcanaeum-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&path).unwrap();
+        path
+    }
+
+    fn write_masterlist(path: &Path, plugin: &str) {
+        fs::write(path, format!("plugins:\n  - name: '{}'\n", plugin)).unwrap();
+    }
+
+    #[test]
+    fn custom_folders_hold_a_masterlist() {
+        let folder = temp_folder("custom-source");
+        let source = MasterlistSource::Custom(folder.clone());
+        assert!(find_masterlist("skyrimse", &source).is_err());
+
+        write_masterlist(&folder.join(MASTERLIST_FILE), "A.esp");
+        assert_eq!(
+            find_masterlist("skyrimse", &source).unwrap(),
+            folder.join(MASTERLIST_FILE)
+        );
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn newest_masterlist_wins() {
+        let folder = temp_folder("newest");
+        let (old, new) = (folder.join("old.yaml"), folder.join("new.yaml"));
+        write_masterlist(&old, "A.esp");
+        write_masterlist(&new, "A.esp");
+        let file = fs::File::options().write(true).open(&old).unwrap();
+        file.set_modified(SystemTime::now() - Duration::from_secs(3600))
+            .unwrap();
+
+        let paths = vec![folder.join("missing.yaml"), old, new.clone()];
+        assert_eq!(find_newest(&paths), Some(new));
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn cache_is_invalidated_when_the_file_changes() {
+        let folder = temp_folder("cache");
+        let path = folder.join(MASTERLIST_FILE);
+        write_masterlist(&path, "A.esp");
+        let source = MasterlistSource::Custom(path.clone());
+        let cache = MasterlistCache::default();
+
+        let first = cache.get("skyrimse", &source).unwrap();
+        let second = cache.get("skyrimse", &source).unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+
+        write_masterlist(&path, "B.esp");
+        let file = fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(60))
+            .unwrap();
+        let third = cache.get("skyrimse", &source).unwrap();
+        assert!(third.get("B.esp").is_some());
+        assert!(third.get("A.esp").is_none());
+        fs::remove_dir_all(folder).unwrap();
+    }
+}
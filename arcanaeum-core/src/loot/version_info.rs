@@ -0,0 +1,414 @@
+//! Reads the version resource of Windows executables and DLLs, which LOOT's version
+//! conditions check for files like SKSE and its plugins.
+
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+
+const RT_VERSION: u32 = 16;
+const FIXED_FILE_INFO_SIGNATURE: u32 = 0xFEEF04BD;
+const RESOURCE_DIRECTORY_INDEX: usize = 2;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VersionInfo {
+    /**
+     * The numeric file version, as four parts.
+     */
+    pub file_version: String,
+
+    /**
+     * The ProductVersion string, or the numeric product version when there is none.
+     * Authors often put things like "2.0.0-beta" here that the file version can't hold.
+     */
+    pub product_version: String,
+}
+
+fn invalid_data(message: String) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+fn bytes_at(buf: &[u8], offset: usize, length: usize) -> Result<&[u8], Error> {
+    offset
+        .checked_add(length)
+        .and_then(|end| buf.get(offset..end))
+        .ok_or_else(|| {
+            invalid_data(format!(
+                "{} bytes at {} are past the end of the file",
+                length, offset
+            ))
+        })
+}
+
+fn u16_at(buf: &[u8], offset: usize) -> Result<u16, Error> {
+    let bytes = bytes_at(buf, offset, 2)?;
+    Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn u32_at(buf: &[u8], offset: usize) -> Result<u32, Error> {
+    let bytes = bytes_at(buf, offset, 4)?;
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn align_4(offset: usize) -> usize {
+    offset.saturating_add(3) & !3
+}
+
+/**
+ * A section of the file, for turning the addresses the headers use into file offsets.
+ */
+struct Section {
+    virtual_address: u32,
+    virtual_size: u32,
+    raw_offset: u32,
+}
+
+fn file_offset(sections: &[Section], rva: u32) -> Result<usize, Error> {
+    sections
+        .iter()
+        .find(|section| {
+            rva >= section.virtual_address && rva - section.virtual_address < section.virtual_size
+        })
+        .map(|section| (rva - section.virtual_address) as usize + section.raw_offset as usize)
+        .ok_or_else(|| invalid_data(format!("Address {:#x} is in no section", rva)))
+}
+
+/**
+ * Finds an entry of a resource directory, by id or the first one, returning its offset
+ * field.
+ */
+fn find_resource_entry(resources: &[u8], directory: usize, id: Option<u32>) -> Result<u32, Error> {
+    let named = u16_at(resources, directory + 12)? as usize;
+    let ids = u16_at(resources, directory + 14)? as usize;
+    for index in 0..named + ids {
+        let entry = directory + 16 + index * 8;
+        let matches = match id {
+            Some(id) => index >= named && u32_at(resources, entry)? == id,
+            None => true,
+        };
+        if matches {
+            return u32_at(resources, entry + 4);
+        }
+    }
+    Err(invalid_data("The file has no version resource".to_string()))
+}
+
+/**
+ * Walks the resource tree from the version type down to the data of its first name and
+ * language.
+ */
+fn find_version_resource<'a>(
+    file: &'a [u8],
+    sections: &[Section],
+    resource_rva: u32,
+) -> Result<&'a [u8], Error> {
+    let resources = file
+        .get(file_offset(sections, resource_rva)?..)
+        .unwrap_or_default();
+    let mut entry = find_resource_entry(resources, 0, Some(RT_VERSION))?;
+    for _ in 0..2 {
+        if entry & 0x8000_0000 == 0 {
+            return Err(invalid_data(
+                "Unexpected version resource layout".to_string(),
+            ));
+        }
+        entry = find_resource_entry(resources, (entry & 0x7FFF_FFFF) as usize, None)?;
+    }
+    let data_entry = entry as usize;
+    let data_rva = u32_at(resources, data_entry)?;
+    let size = u32_at(resources, data_entry + 4)? as usize;
+    bytes_at(file, file_offset(sections, data_rva)?, size)
+}
+
+/**
+ * A node of the version resource: a key, a value, and child nodes.
+ */
+struct Block<'a> {
+    key: String,
+    value: &'a [u8],
+    children: &'a [u8],
+}
+
+fn read_utf16(bytes: &[u8]) -> String {
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+        .take_while(|unit| *unit != 0)
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+fn read_block(buf: &[u8]) -> Result<Block<'_>, Error> {
+    let length = u16_at(buf, 0)? as usize;
+    if length < 6 {
+        return Err(invalid_data(format!(
+            "Version resource block of {} bytes",
+            length
+        )));
+    }
+    let block = bytes_at(buf, 0, length)?;
+    let value_length = u16_at(block, 2)? as usize;
+    let is_text = u16_at(block, 4)? == 1;
+
+    let key = read_utf16(&block[6..]);
+    let value_start = align_4(6 + (key.encode_utf16().count() + 1) * 2).min(length);
+    // Text values count their length in UTF-16 units
+    let value_size = value_length * if is_text { 2 } else { 1 };
+    let value_end = value_start.saturating_add(value_size).min(length);
+    Ok(Block {
+        key,
+        value: &block[value_start..value_end],
+        children: &block[align_4(value_end).min(length)..],
+    })
+}
+
+fn read_children(children: &[u8]) -> Result<Vec<Block<'_>>, Error> {
+    let mut blocks = Vec::new();
+    let mut offset = 0;
+    while offset + 6 <= children.len() {
+        let length = u16_at(children, offset)? as usize;
+        blocks.push(read_block(&children[offset..])?);
+        offset += align_4(length);
+    }
+    Ok(blocks)
+}
+
+fn format_version(most_significant: u32, least_significant: u32) -> String {
+    format!(
+        "{}.{}.{}.{}",
+        most_significant >> 16,
+        most_significant & 0xFFFF,
+        least_significant >> 16,
+        least_significant & 0xFFFF
+    )
+}
+
+fn find_product_version_string(root: &Block) -> Result<Option<String>, Error> {
+    for string_file_info in read_children(root.children)?
+        .iter()
+        .filter(|block| block.key == "StringFileInfo")
+    {
+        for table in read_children(string_file_info.children)? {
+            for string in read_children(table.children)? {
+                let value = read_utf16(string.value);
+                if string.key == "ProductVersion" && !value.trim().is_empty() {
+                    return Ok(Some(value.trim().to_string()));
+                }
+            }
+        }
+    }
+    Ok(None)
+}
+
+/**
+ * Reads the VS_VERSIONINFO structure of a version resource.
+ */
+pub fn parse_version_resource(buf: &[u8]) -> Result<VersionInfo, Error> {
+    let root = read_block(buf)?;
+    if root.key != "VS_VERSION_INFO" || u32_at(root.value, 0)? != FIXED_FILE_INFO_SIGNATURE {
+        return Err(invalid_data("Not a version resource".to_string()));
+    }
+    let file_version = format_version(u32_at(root.value, 8)?, u32_at(root.value, 12)?);
+    let product_version = match find_product_version_string(&root)? {
+        Some(product_version) => product_version,
+        None => format_version(u32_at(root.value, 16)?, u32_at(root.value, 20)?),
+    };
+    Ok(VersionInfo {
+        file_version,
+        product_version,
+    })
+}
+
+/**
+ * Reads the version resource of a PE file, whether 32 or 64 bit.
+ */
+pub fn parse_version_info(file: &[u8]) -> Result<VersionInfo, Error> {
+    if bytes_at(file, 0, 2)? != b"MZ" {
+        return Err(invalid_data("Not an executable".to_string()));
+    }
+    let pe_header = u32_at(file, 0x3C)? as usize;
+    if bytes_at(file, pe_header, 4)? != b"PE\0\0" {
+        return Err(invalid_data("Not a PE executable".to_string()));
+    }
+    let coff_header = pe_header + 4;
+    let section_count = u16_at(file, coff_header + 2)? as usize;
+    let optional_header_size = u16_at(file, coff_header + 16)? as usize;
+    let optional_header = coff_header + 20;
+
+    let (directory_count_offset, directories_offset) = match u16_at(file, optional_header)? {
+        0x10B => (92, 96),
+        0x20B => (108, 112),
+        magic => {
+            return Err(invalid_data(format!(
+                "Unknown optional header magic {:#x}",
+                magic
+            )))
+        }
+    };
+    let directory_count = u32_at(file, optional_header + directory_count_offset)? as usize;
+    if directory_count <= RESOURCE_DIRECTORY_INDEX {
+        return Err(invalid_data("The file has no resources".to_string()));
+    }
+    let resource_rva = u32_at(
+        file,
+        optional_header + directories_offset + RESOURCE_DIRECTORY_INDEX * 8,
+    )?;
+    if resource_rva == 0 {
+        return Err(invalid_data("The file has no resources".to_string()));
+    }
+
+    let section_table = optional_header + optional_header_size;
+    let sections = (0..section_count)
+        .map(|index| {
+            let section = section_table + index * 40;
+            Ok(Section {
+                virtual_address: u32_at(file, section + 12)?,
+                virtual_size: u32_at(file, section + 8)?.max(u32_at(file, section + 16)?),
+                raw_offset: u32_at(file, section + 20)?,
+            })
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    parse_version_resource(find_version_resource(file, &sections, resource_rva)?)
+}
+
+pub fn read_version_info(path: &Path) -> Result<VersionInfo, Error> {
+    parse_version_info(&fs::read(path)?)
+}
+
+#[cfg(test)]
+pub mod test_data {
+    fn utf16(text: &str) -> Vec<u8> {
+        text.encode_utf16()
+            .chain([0])
+            .flat_map(|unit| unit.to_le_bytes())
+            .collect()
+    }
+
+    fn pad(buf: &mut Vec<u8>) {
+        buf.resize((buf.len() + 3) & !3, 0);
+    }
+
+    fn block(
+        key: &str,
+        value: &[u8],
+        value_length: usize,
+        is_text: bool,
+        children: &[Vec<u8>],
+    ) -> Vec<u8> {
+        let mut buf = vec![0; 6];
+        buf[2..4].copy_from_slice(&(value_length as u16).to_le_bytes());
+        buf[4..6].copy_from_slice(&(is_text as u16).to_le_bytes());
+        buf.extend(utf16(key));
+        pad(&mut buf);
+        buf.extend_from_slice(value);
+        for child in children {
+            pad(&mut buf);
+            buf.extend_from_slice(child);
+        }
+        let length = buf.len() as u16;
+        buf[0..2].copy_from_slice(&length.to_le_bytes());
+        buf
+    }
+
+    /**
+     * A VS_VERSIONINFO with file version 1.2.3.4, numeric product version 1.2.0.0 and,
+     * when given, a ProductVersion string.
+     */
+    pub fn version_resource(product_version: Option<&str>) -> Vec<u8> {
+        let mut fixed = Vec::new();
+        for value in [
+            0xFEEF04BD, 0x10000, 0x10002, 0x30004, 0x10002, 0, 0x3F, 0, 4, 2, 0, 0, 0,
+        ] {
+            fixed.extend_from_slice(&u32::to_le_bytes(value));
+        }
+        let strings: Vec<Vec<u8>> = product_version
+            .map(|version| {
+                let value = utf16(version);
+                let string = block("ProductVersion", &value, value.len() / 2, true, &[]);
+                let table = block("040904b0", &[], 0, true, &[string]);
+                vec![block("StringFileInfo", &[], 0, true, &[table])]
+            })
+            .unwrap_or_default();
+        block("VS_VERSION_INFO", &fixed, fixed.len(), false, &strings)
+    }
+
+    /**
+     * A 64 bit DLL with nothing but a resource section holding the version resource.
+     */
+    pub fn dll(resource: &[u8]) -> Vec<u8> {
+        const SECTION_RVA: u32 = 0x1000;
+        const SECTION_OFFSET: usize = 0x200;
+
+        let mut resources = Vec::new();
+        let directory = |entry_id: u32, offset: u32| {
+            let mut buf = vec![0; 14];
+            buf.extend_from_slice(&1u16.to_le_bytes());
+            buf.extend_from_slice(&entry_id.to_le_bytes());
+            buf.extend_from_slice(&offset.to_le_bytes());
+            buf
+        };
+        resources.extend(directory(16, 0x8000_0000 | 24));
+        resources.extend(directory(1, 0x8000_0000 | 48));
+        resources.extend(directory(0x409, 72));
+        resources.extend_from_slice(&(SECTION_RVA + 88).to_le_bytes());
+        resources.extend_from_slice(&(resource.len() as u32).to_le_bytes());
+        resources.extend_from_slice(&[0; 8]);
+        resources.extend_from_slice(resource);
+
+        let mut file = vec![0; SECTION_OFFSET];
+        file[0..2].copy_from_slice(b"MZ");
+        file[0x3C..0x40].copy_from_slice(&0x40u32.to_le_bytes());
+        file[0x40..0x44].copy_from_slice(b"PE\0\0");
+        let coff_header = 0x44;
+        file[coff_header..coff_header + 2].copy_from_slice(&0x8664u16.to_le_bytes());
+        file[coff_header + 2..coff_header + 4].copy_from_slice(&1u16.to_le_bytes());
+        file[coff_header + 16..coff_header + 18].copy_from_slice(&240u16.to_le_bytes());
+        let optional_header = coff_header + 20;
+        file[optional_header..optional_header + 2].copy_from_slice(&0x20Bu16.to_le_bytes());
+        file[optional_header + 108..optional_header + 112].copy_from_slice(&16u32.to_le_bytes());
+        let resource_directory = optional_header + 112 + 16;
+        file[resource_directory..resource_directory + 4]
+            .copy_from_slice(&SECTION_RVA.to_le_bytes());
+        file[resource_directory + 4..resource_directory + 8]
+            .copy_from_slice(&(resources.len() as u32).to_le_bytes());
+        let section = optional_header + 240;
+        file[section..section + 5].copy_from_slice(b".rsrc");
+        file[section + 8..section + 12].copy_from_slice(&(resources.len() as u32).to_le_bytes());
+        file[section + 12..section + 16].copy_from_slice(&SECTION_RVA.to_le_bytes());
+        file[section + 16..section + 20].copy_from_slice(&(resources.len() as u32).to_le_bytes());
+        file[section + 20..section + 24].copy_from_slice(&(SECTION_OFFSET as u32).to_le_bytes());
+
+        file.extend(resources);
+        file
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_data::{dll, version_resource};
+    use super::*;
+
+    #[test]
+    fn versions_are_read_from_the_resource() {
+        let info = parse_version_info(&dll(&version_resource(Some("2.0.0-beta")))).unwrap();
+        assert_eq!(info.file_version, "1.2.3.4");
+        assert_eq!(info.product_version, "2.0.0-beta");
+    }
+
+    #[test]
+    fn product_version_falls_back_to_the_number() {
+        let info = parse_version_info(&dll(&version_resource(None))).unwrap();
+        assert_eq!(info.product_version, "1.2.0.0");
+    }
+
+    #[test]
+    fn broken_files_are_errors() {
+        assert!(parse_version_info(b"MZ").is_err());
+        let mut file = dll(&version_resource(None));
+        file.truncate(0x220);
+        assert!(parse_version_info(&file).is_err());
+        let mut file = dll(&version_resource(None));
+        file[0x3C..0x40].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(parse_version_info(&file).is_err());
+    }
+}
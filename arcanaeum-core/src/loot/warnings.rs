@@ -0,0 +1,234 @@
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+
+use serde::{Deserialize, Serialize};
+
+use super::condition::{applies, Environment};
+use super::masterlist::{CleaningData, MasterlistIndex, MessageType, PluginMetadata};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LootWarning {
+    pub level: MessageType,
+    pub text: String,
+}
+
+impl Display for LootWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
+
+fn warning(level: MessageType, text: String) -> LootWarning {
+    LootWarning { level, text }
+}
+
+fn describe_dirty(dirty: &CleaningData) -> String {
+    let counts: Vec<String> = [
+        (dirty.itm, "ITM record"),
+        (dirty.udr, "deleted reference"),
+        (dirty.nav, "deleted navmesh"),
+    ]
+    .iter()
+    .filter(|(count, _)| *count > 0)
+    .map(|(count, kind)| match count {
+        1 => format!("1 {}", kind),
+        _ => format!("{} {}s", count, kind),
+    })
+    .collect();
+    let mut text = match counts.is_empty() {
+        true => format!("Dirty, clean it with {}.", dirty.util),
+        false => format!(
+            "Contains {}, clean it with {}.",
+            counts.join(", "),
+            dirty.util
+        ),
+    };
+    if let Some(detail) = &dirty.detail {
+        text.push(' ');
+        text.push_str(detail.text());
+    }
+    text
+}
+
+fn is_plugin(name: &str) -> bool {
+    let name = name.to_lowercase();
+    [".esp", ".esm", ".esl"].iter().any(|e| name.ends_with(e))
+}
+
+/**
+ * The warnings and errors LOOT shows for a plugin, leaving out the ones whose conditions
+ * don't hold for the installation. A plugin is only incompatible with another plugin when
+ * both are active.
+ */
+pub fn get_plugin_warnings(metadata: &PluginMetadata, env: &dyn Environment) -> Vec<LootWarning> {
+    let mut warnings: Vec<LootWarning> = metadata
+        .msg
+        .iter()
+        .filter(|message| message.kind != MessageType::Say && applies(&message.condition, env))
+        .map(|message| warning(message.kind, message.text()))
+        .collect();
+
+    for file in metadata
+        .req
+        .iter()
+        .filter(|file| applies(&file.condition, env))
+    {
+        if !env.file_exists(&file.name) {
+            let text = format!("Requires {}, which is missing.", file.display_name());
+            warnings.push(warning(MessageType::Error, text));
+        }
+    }
+    for file in metadata
+        .inc
+        .iter()
+        .filter(|file| applies(&file.condition, env))
+    {
+        let present = match is_plugin(&file.name) {
+            true => env.is_active(&file.name),
+            false => env.file_exists(&file.name),
+        };
+        if present {
+            let text = format!("Incompatible with {}.", file.display_name());
+            warnings.push(warning(MessageType::Error, text));
+        }
+    }
+    if !metadata.dirty.is_empty() {
+        let crc = env.crc(&metadata.name);
+        if let Some(dirty) = metadata.dirty.iter().find(|d| Some(d.crc) == crc) {
+            warnings.push(warning(MessageType::Warn, describe_dirty(dirty)));
+        }
+    }
+    warnings
+}
+
+/**
+ * LOOT's warnings for every plugin in a save, keyed by lowercased plugin name.
+ */
+pub fn get_save_warnings(
+    index: &MasterlistIndex,
+    plugins: &[String],
+    env: &dyn Environment,
+) -> HashMap<String, Vec<LootWarning>> {
+    plugins
+        .iter()
+        .filter_map(|plugin| {
+            let metadata = index.get(plugin)?;
+            let warnings = get_plugin_warnings(&metadata, env);
+            match warnings.is_empty() {
+                true => None,
+                false => Some((plugin.to_lowercase(), warnings)),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loot::condition::test_data::FakeEnvironment;
+    use crate::loot::masterlist::parse_masterlist;
+
+    const MASTERLIST: &str = r#"
+plugins:
+  - name: 'Cutting Room Floor.esp'
+    req:
+      - name: 'Unofficial Skyrim Special Edition Patch.esp'
+        display: 'USSEP'
+    inc: [ 'Old CRF.esp' ]
+    msg:
+      - type: say
+        content: 'Thanks for playing.'
+      - type: warn
+        content: 'Check the patch hub.'
+      - type: error
+        content: 'Install the Lanterns patch.'
+        condition: 'active("Lanterns.esp") and not file("CRF - Lanterns Patch.esp")'
+    dirty:
+      - crc: 0x4C1A2E3F
+        util: 'SSEEdit v4.0.3'
+        itm: 12
+        udr: 1
+"#;
+
+    fn metadata() -> PluginMetadata {
+        MasterlistIndex::new(parse_masterlist(MASTERLIST).unwrap())
+            .get("Cutting Room Floor.esp")
+            .unwrap()
+    }
+
+    fn environment(active: &[&str]) -> FakeEnvironment {
+        let active: Vec<String> = active.iter().map(|p| p.to_string()).collect();
+        FakeEnvironment {
+            files: active.clone(),
+            active,
+            ..Default::default()
+        }
+    }
+
+    fn texts(warnings: &[LootWarning]) -> Vec<&str> {
+        warnings.iter().map(|w| w.text.as_str()).collect()
+    }
+
+    #[test]
+    fn missing_requirements_and_incompatibilities_are_errors() {
+        let env = environment(&["Cutting Room Floor.esp", "Old CRF.esp"]);
+        let warnings = get_plugin_warnings(&metadata(), &env);
+        assert_eq!(
+            texts(&warnings),
+            vec![
+                "Check the patch hub.",
+                "Requires USSEP, which is missing.",
+                "Incompatible with Old CRF.esp."
+            ]
+        );
+        assert_eq!(warnings[1].level, MessageType::Error);
+    }
+
+    #[test]
+    fn messages_are_only_shown_when_their_condition_holds() {
+        let env = environment(&[
+            "Cutting Room Floor.esp",
+            "Unofficial Skyrim Special Edition Patch.esp",
+            "Lanterns.esp",
+        ]);
+        assert_eq!(
+            texts(&get_plugin_warnings(&metadata(), &env)),
+            vec!["Check the patch hub.", "Install the Lanterns patch."]
+        );
+
+        let mut env = env;
+        env.files.push("CRF - Lanterns Patch.esp".to_string());
+        assert_eq!(
+            texts(&get_plugin_warnings(&metadata(), &env)),
+            vec!["Check the patch hub."]
+        );
+    }
+
+    #[test]
+    fn dirty_versions_are_reported() {
+        let mut env = environment(&["Unofficial Skyrim Special Edition Patch.esp"]);
+        env.crcs
+            .insert("Cutting Room Floor.esp".to_string(), 0x4C1A2E3F);
+        let warnings = get_plugin_warnings(&metadata(), &env);
+        assert_eq!(
+            warnings.last().unwrap().text,
+            "Contains 12 ITM records, 1 deleted reference, clean it with SSEEdit v4.0.3."
+        );
+        env.crcs
+            .insert("Cutting Room Floor.esp".to_string(), 0x1234);
+        let clean = get_plugin_warnings(&metadata(), &env);
+        assert!(!clean.iter().any(|w| w.text.contains("ITM")));
+    }
+
+    #[test]
+    fn save_warnings_are_keyed_by_lowercased_name() {
+        let index = MasterlistIndex::new(parse_masterlist(MASTERLIST).unwrap());
+        let plugins = vec![
+            "Skyrim.esm".to_string(),
+            "Cutting Room Floor.esp".to_string(),
+        ];
+        let warnings = get_save_warnings(&index, &plugins, &environment(&["Skyrim.esm"]));
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings["cutting room floor.esp"].len(), 2);
+    }
+}
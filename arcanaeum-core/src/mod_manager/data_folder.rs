@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::PathBuf;
+
+use crate::load_order::profile::{read_profile_load_order, ProfilePlugin};
+
+use super::{get_game_folder_name, get_game_save_folder, ModManager, ModManagerKind, Profile};
+
+const PROFILE_NAME: &str = "Default";
+
+/**
+ * Mods installed by hand. The game keeps its own plugins.txt in %LOCALAPPDATA%, so there is
+ * a single profile.
+ */
+pub struct DataFolderManager {
+    game: String,
+    data_path: Option<PathBuf>,
+}
+
+impl DataFolderManager {
+    pub fn new(game: &str, data_path: Option<PathBuf>) -> DataFolderManager {
+        DataFolderManager {
+            game: game.to_string(),
+            data_path,
+        }
+    }
+}
+
+impl ModManager for DataFolderManager {
+    fn kind(&self) -> ModManagerKind {
+        ModManagerKind::DataFolder
+    }
+
+    fn game(&self) -> &str {
+        &self.game
+    }
+
+    fn profiles(&self) -> Result<Vec<Profile>, Error> {
+        Ok(vec![Profile::named(PROFILE_NAME)])
+    }
+
+    fn profile_path(&self, _profile: &str) -> Result<PathBuf, Error> {
+        let local_app_data = env::var("LOCALAPPDATA")
+            .map_err(|_| Error::new(ErrorKind::NotFound, "LOCALAPPDATA is not set"))?;
+        Ok(PathBuf::from(local_app_data).join(get_game_folder_name(&self.game)))
+    }
+
+    fn load_order(&self, profile: &str) -> Result<Vec<ProfilePlugin>, Error> {
+        read_profile_load_order(&self.profile_path(profile)?, &self.game)
+    }
+
+    /**
+     * Everything in the Data folder was put there by hand.
+     */
+    fn plugin_owners(&self, _profile: &str) -> HashMap<String, String> {
+        let entries = match self.data_path.as_ref().map(fs::read_dir) {
+            Some(Ok(entries)) => entries,
+            _ => return HashMap::new(),
+        };
+        entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.file_name().to_string_lossy().to_lowercase())
+            .filter(|name| {
+                name.ends_with(".esp") || name.ends_with(".esm") || name.ends_with(".esl")
+            })
+            .map(|name| (name, "Data folder".to_string()))
+            .collect()
+    }
+
+    fn save_folders(&self) -> Vec<(String, PathBuf)> {
+        get_game_save_folder(&self.game)
+            .map(|path| vec![(get_game_folder_name(&self.game).to_string(), path)])
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,275 @@
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+
+use crate::load_order::profile::ProfilePlugin;
+use crate::mod_search::mo2_scanner::{
+    find_instances_for_game, find_mod_download, get_plugin_owners_from_instance,
+    get_profile_save_folders, get_profiles, read_categories, read_download_index, read_mod_meta,
+    read_profile, Mo2Download, Mo2Instance, Mo2ModMeta,
+};
+use crate::plugin_file::find_plugin_file;
+
+use super::{ModDetails, ModManager, ModManagerKind, Profile};
+
+pub struct Mo2Manager {
+    instance: Mo2Instance,
+    /**
+     * The mod folder providing each plugin, by lowercased plugin name. The selected
+     * profile wins over the others.
+     */
+    plugin_mods: HashMap<String, String>,
+    /**
+     * What the meta.ini and download of each of those mods say, by mod folder name. They
+     * are read once as the details are looked up for every plugin on every frame.
+     */
+    mod_details: HashMap<String, ModDetails>,
+    /**
+     * The names of the categories in each of those mods' meta.ini, by mod folder name.
+     */
+    mod_categories: HashMap<String, Vec<String>>,
+}
+
+/**
+ * Combines a mod's meta.ini with the .meta of the archive it was installed from. The
+ * download knows the Nexus page even when the mod was renamed after installing.
+ */
+fn get_mod_details(
+    mod_name: &str,
+    meta: &Mo2ModMeta,
+    download: Option<&Mo2Download>,
+) -> ModDetails {
+    let meta_download = Mo2Download {
+        game_name: meta.game_name.clone(),
+        mod_id: meta.mod_id,
+        url: meta.url.clone(),
+        ..Default::default()
+    };
+    ModDetails {
+        name: mod_name.to_string(),
+        version: download
+            .and_then(|d| d.version.clone())
+            .or_else(|| meta.version.clone()),
+        url: download
+            .and_then(|d| d.nexus_url())
+            .or_else(|| meta_download.nexus_url()),
+    }
+}
+
+impl Mo2Manager {
+    pub fn new(instance: Mo2Instance) -> Mo2Manager {
+        let downloads = read_download_index(&instance.downloads_path);
+        let categories = read_categories(&instance);
+        let mut profiles = get_profiles(&instance).unwrap_or_default();
+        // Read the selected profile last so its mods win
+        profiles.sort_by_key(|name| instance.selected_profile.as_deref() == Some(name.as_str()));
+        let mut plugin_mods = HashMap::new();
+        for name in profiles {
+            match read_profile(&instance, &name) {
+                Ok(profile) => {
+                    plugin_mods.extend(get_plugin_owners_from_instance(&instance, &profile))
+                }
+                Err(e) => tracing::error!("Could not read MO2 profile {}: {}", name, e),
+            }
+        }
+        let mut mod_details = HashMap::new();
+        let mut mod_categories = HashMap::new();
+        for mod_name in plugin_mods.values() {
+            if mod_details.contains_key(mod_name) {
+                continue;
+            }
+            match read_mod_meta(&instance, mod_name) {
+                Ok(meta) => {
+                    let download = find_mod_download(&downloads, &meta);
+                    let details = get_mod_details(mod_name, &meta, download);
+                    mod_details.insert(mod_name.clone(), details);
+                    let names = meta
+                        .categories
+                        .iter()
+                        .filter_map(|id| categories.get(id).cloned())
+                        .collect();
+                    mod_categories.insert(mod_name.clone(), names);
+                }
+                Err(e) => tracing::info!("Could not read meta.ini of {}: {}", mod_name, e),
+            }
+        }
+        Mo2Manager {
+            instance,
+            plugin_mods,
+            mod_details,
+            mod_categories,
+        }
+    }
+
+    /**
+     * Picks the first instance managing the game when none was chosen in the settings.
+     */
+    pub fn find_instance(game: &str) -> Result<Mo2Instance, Error> {
+        find_instances_for_game(game)
+            .into_iter()
+            .next()
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::NotFound,
+                    format!("No Mod Organizer 2 instance found for {game}"),
+                )
+            })
+    }
+}
+
+impl ModManager for Mo2Manager {
+    fn kind(&self) -> ModManagerKind {
+        ModManagerKind::ModOrganizer2
+    }
+
+    fn game(&self) -> &str {
+        self.instance.game().unwrap_or("skyrimse")
+    }
+
+    fn profiles(&self) -> Result<Vec<Profile>, Error> {
+        let names = get_profiles(&self.instance)?;
+        Ok(names.iter().map(|name| Profile::named(name)).collect())
+    }
+
+    fn profile_path(&self, profile: &str) -> Result<PathBuf, Error> {
+        Ok(self.instance.profiles_path.join(profile))
+    }
+
+    fn active_profile(&self) -> Option<String> {
+        self.instance.selected_profile.clone()
+    }
+
+    fn load_order(&self, profile: &str) -> Result<Vec<ProfilePlugin>, Error> {
+        Ok(read_profile(&self.instance, profile)?.plugins)
+    }
+
+    fn plugin_owners(&self, profile: &str) -> HashMap<String, String> {
+        match read_profile(&self.instance, profile) {
+            Ok(profile) => get_plugin_owners_from_instance(&self.instance, &profile),
+            Err(e) => {
+                tracing::error!("Could not read MO2 profile {}: {}", profile, e);
+                HashMap::new()
+            }
+        }
+    }
+
+    /**
+     * Mod plugins stay in their mod's folder or in overwrite, which wins over every mod
+     * like it does in MO2's virtual Data folder. Only the game's own plugins are in Data.
+     */
+    fn plugin_path(&self, data_path: Option<&Path>, plugin: &str) -> Option<PathBuf> {
+        let mod_path = self
+            .plugin_mods
+            .get(&plugin.to_lowercase())
+            .map(|mod_name| self.instance.mods_path.join(mod_name));
+        [Some(self.instance.overwrite_path.clone()), mod_path]
+            .into_iter()
+            .flatten()
+            .chain(data_path.map(Path::to_path_buf))
+            .find_map(|folder| find_plugin_file(&folder, plugin))
+    }
+
+    fn mod_details(&self, plugin: &str) -> Option<ModDetails> {
+        let mod_name = self.plugin_mods.get(&plugin.to_lowercase())?;
+        self.mod_details.get(mod_name).cloned()
+    }
+
+    fn mod_categories(&self, plugin: &str) -> Vec<String> {
+        self.plugin_mods
+            .get(&plugin.to_lowercase())
+            .and_then(|mod_name| self.mod_categories.get(mod_name))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn save_folders(&self) -> Vec<(String, PathBuf)> {
+        get_profile_save_folders(std::slice::from_ref(&self.instance))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::load_order::dependency_graph::{build_dependency_graph_from_files, MasterProblem};
+    use crate::loot::sorting::read_sort_plugins;
+    use crate::mod_search::mo2_scanner::parse_instance;
+    use crate::plugin_file::test_data::plugin_bytes;
+    use std::fs;
+
+    #[test]
+    fn download_is_preferred_over_mod_meta() {
+        let meta = Mo2ModMeta {
+            game_name: Some("SkyrimSE".to_string()),
+            mod_id: Some(266),
+            version: Some("4.2.5".to_string()),
+            ..Default::default()
+        };
+        let details = get_mod_details("USSEP", &meta, None);
+        assert_eq!(details.version.as_deref(), Some("4.2.5"));
+        assert_eq!(
+            details.url.as_deref(),
+            Some("https://www.nexusmods.com/skyrimspecialedition/mods/266")
+        );
+
+        let download = Mo2Download {
+            version: Some("4.2.6".to_string()),
+            url: Some("https://www.nexusmods.com/skyrimspecialedition/mods/266?tab=files".into()),
+            ..Default::default()
+        };
+        let details = get_mod_details("USSEP", &meta, Some(&download));
+        assert_eq!(details.name, "USSEP");
+        assert_eq!(details.version.as_deref(), Some("4.2.6"));
+        assert!(details.url.unwrap().ends_with("?tab=files"));
+    }
+
+    #[test]
+    fn plugins_are_found_in_mod_folders_and_overwrite() {
+        let path = std::env::temp_dir().join(format!("arcanaeum-mo2-{}", std::process::id()));
+        let instance = parse_instance(
+            "[General]\ngameName=Skyrim Special Edition\nselected_profile=Default\n",
+            &path,
+            true,
+        )
+        .unwrap();
+        let data_path = path.join("Data");
+        let profile_path = instance.profiles_path.join("Default");
+        for folder in [&data_path, &profile_path, &instance.overwrite_path] {
+            fs::create_dir_all(folder).unwrap();
+        }
+        fs::create_dir_all(instance.mods_path.join("Lore")).unwrap();
+        fs::write(profile_path.join("modlist.txt"), "+Lore\n").unwrap();
+        fs::write(profile_path.join("plugins.txt"), "*Lore.esp\n*Patch.esp\n").unwrap();
+        fs::write(data_path.join("Skyrim.esm"), plugin_bytes(1, &[])).unwrap();
+        fs::write(
+            instance.mods_path.join("Lore").join("Lore.esp"),
+            plugin_bytes(0, &["Skyrim.esm"]),
+        )
+        .unwrap();
+        fs::write(
+            instance.overwrite_path.join("Patch.esp"),
+            plugin_bytes(0, &["Lore.esp", "Skyrim.esm"]),
+        )
+        .unwrap();
+
+        let manager = Mo2Manager::new(instance.clone());
+        assert_eq!(
+            manager.plugin_path(Some(&data_path), "lore.esp"),
+            Some(instance.mods_path.join("Lore").join("Lore.esp"))
+        );
+        let graph = build_dependency_graph_from_files(
+            &["Skyrim.esm", "Patch.esp", "Lore.esp"].map(String::from),
+            &[],
+            |name| manager.plugin_path(Some(&data_path), name),
+        );
+        assert!(graph.nodes.iter().all(|node| node.header_found));
+        assert_eq!(
+            graph.get("Patch.esp").unwrap().problems,
+            vec![MasterProblem::LoadsAfter("Lore.esp".to_string())]
+        );
+        let sort_plugins = read_sort_plugins(&["Patch.esp".to_string()], &|name| {
+            manager.plugin_path(Some(&data_path), name)
+        });
+        assert_eq!(sort_plugins[0].masters, vec!["Lore.esp", "Skyrim.esm"]);
+        fs::remove_dir_all(path).unwrap();
+    }
+}
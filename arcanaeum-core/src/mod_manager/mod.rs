@@ -0,0 +1,394 @@
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+use std::io::Error;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::load_order::profile::{get_active_plugins, ProfilePlugin};
+use crate::mod_search::mo2_scanner::read_instance;
+use crate::plugin_file::find_plugin_file;
+
+pub mod data_folder;
+pub mod mo2;
+pub mod vortex;
+
+use data_folder::DataFolderManager;
+use mo2::Mo2Manager;
+use vortex::VortexManager;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ModManagerKind {
+    #[default]
+    Vortex,
+    ModOrganizer2,
+    /**
+     * Mods installed by hand straight into the Data folder.
+     */
+    DataFolder,
+}
+
+impl ModManagerKind {
+    pub const ALL: [ModManagerKind; 3] = [
+        ModManagerKind::Vortex,
+        ModManagerKind::ModOrganizer2,
+        ModManagerKind::DataFolder,
+    ];
+}
+
+impl Display for ModManagerKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ModManagerKind::Vortex => write!(f, "Vortex"),
+            ModManagerKind::ModOrganizer2 => write!(f, "Mod Organizer 2"),
+            ModManagerKind::DataFolder => write!(f, "No mod manager"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Profile {
+    /**
+     * What the manager identifies the profile by, e.g. its folder name.
+     */
+    pub id: String,
+    pub name: String,
+}
+
+impl Profile {
+    /**
+     * For managers where the folder name is the profile name.
+     */
+    pub fn named(name: &str) -> Profile {
+        Profile {
+            id: name.to_string(),
+            name: name.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PluginState {
+    /**
+     * Active in at least one profile.
+     */
+    Enabled,
+    /**
+     * Installed, but not active in any profile.
+     */
+    Disabled,
+    Absent,
+}
+
+/**
+ * The mod a plugin was installed with, as far as the manager knows.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModDetails {
+    pub name: String,
+    pub version: Option<String>,
+    pub url: Option<String>,
+}
+
+/**
+ * What a mod manager knows about the plugins it installs. Methods taking a profile expect
+ * its id.
+ */
+pub trait ModManager: Send + Sync {
+    fn kind(&self) -> ModManagerKind;
+
+    /**
+     * The game the plugins are for, e.g. "skyrimse", which decides how plugins.txt reads.
+     */
+    fn game(&self) -> &str;
+
+    fn profiles(&self) -> Result<Vec<Profile>, Error>;
+
+    /**
+     * The folder holding the profile's plugins.txt, to compare or restore load orders.
+     */
+    fn profile_path(&self, profile: &str) -> Result<PathBuf, Error>;
+
+    fn load_order(&self, profile: &str) -> Result<Vec<ProfilePlugin>, Error>;
+
+    /**
+     * Maps plugin names, lowercased, to the name of the mod that installed them, for the
+     * plugins where that is known.
+     */
+    fn plugin_owners(&self, profile: &str) -> HashMap<String, String>;
+
+    /**
+     * Save folders to pick from, as (label, path).
+     */
+    fn save_folders(&self) -> Vec<(String, PathBuf)>;
+
+    /**
+     * The file of an installed plugin, to read its header. Managers that deploy into the
+     * Data folder leave it there.
+     */
+    fn plugin_path(&self, data_path: Option<&Path>, plugin: &str) -> Option<PathBuf> {
+        find_plugin_file(data_path?, plugin)
+    }
+
+    fn mod_details(&self, _plugin: &str) -> Option<ModDetails> {
+        None
+    }
+
+    /**
+     * The categories the user filed the plugin's mod under.
+     */
+    fn mod_categories(&self, _plugin: &str) -> Vec<String> {
+        Vec::new()
+    }
+
+    /**
+     * The profile the game would be launched with.
+     */
+    fn active_profile(&self) -> Option<String> {
+        self.profiles()
+            .ok()?
+            .into_iter()
+            .next()
+            .map(|profile| profile.id)
+    }
+
+    fn active_plugins(&self, profile: &str) -> Result<Vec<String>, Error> {
+        Ok(get_active_plugins(&self.load_order(profile)?, self.game()))
+    }
+}
+
+/**
+ * A plugin in the load order of at least one profile.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InstalledPlugin {
+    pub name: String,
+    /**
+     * The name of a profile the plugin is enabled in, or of the first one it was found in
+     * when it is disabled everywhere.
+     */
+    pub profile: String,
+    pub mod_name: Option<String>,
+    pub enabled: bool,
+}
+
+impl InstalledPlugin {
+    /**
+     * Where the plugin came from, for display.
+     */
+    pub fn source(&self, kind: ModManagerKind) -> String {
+        match &self.mod_name {
+            Some(mod_name) => format!("{} ({})", mod_name, self.profile),
+            None => format!("{} profile {}", kind, self.profile),
+        }
+    }
+}
+
+/**
+ * Collects the plugins of every profile, keyed by lowercased name since Windows file names
+ * are case insensitive. A plugin counts as enabled if any profile enables it.
+ */
+pub fn get_installed_plugins(manager: &dyn ModManager) -> HashMap<String, InstalledPlugin> {
+    let mut installed: HashMap<String, InstalledPlugin> = HashMap::new();
+    let profiles = match manager.profiles() {
+        Ok(profiles) => profiles,
+        Err(e) => {
+            tracing::error!("Could not find any {} profiles: {}", manager.kind(), e);
+            return installed;
+        }
+    };
+
+    for profile in profiles {
+        let load_order = match manager.load_order(&profile.id) {
+            Ok(load_order) => load_order,
+            Err(e) => {
+                tracing::error!("Cannot read plugins from profile {}: {}", profile.name, e);
+                continue;
+            }
+        };
+        let active = get_active_plugins(&load_order, manager.game());
+        let owners = manager.plugin_owners(&profile.id);
+
+        let plugins = active
+            .iter()
+            .map(|name| (name, true))
+            .chain(load_order.iter().map(|p| (&p.name, p.enabled)));
+        for (name, enabled) in plugins {
+            let key = name.to_lowercase();
+            if installed
+                .get(&key)
+                .is_some_and(|existing| existing.enabled || !enabled)
+            {
+                continue;
+            }
+            let mod_name = owners.get(&key).cloned();
+            installed.insert(
+                key,
+                InstalledPlugin {
+                    name: name.clone(),
+                    profile: profile.name.clone(),
+                    mod_name,
+                    enabled,
+                },
+            );
+        }
+    }
+    installed
+}
+
+/**
+ * Finds a plugin's file through the mod manager, or in the Data folder without one.
+ */
+pub fn find_plugin_path(
+    manager: Option<&dyn ModManager>,
+    data_path: Option<&Path>,
+    plugin: &str,
+) -> Option<PathBuf> {
+    match manager {
+        Some(manager) => manager.plugin_path(data_path, plugin),
+        None => find_plugin_file(data_path?, plugin),
+    }
+}
+
+pub fn get_plugin_state(installed: &HashMap<String, InstalledPlugin>, name: &str) -> PluginState {
+    match installed.get(&name.to_lowercase()) {
+        Some(plugin) if plugin.enabled => PluginState::Enabled,
+        Some(_) => PluginState::Disabled,
+        None => PluginState::Absent,
+    }
+}
+
+/**
+ * The folder name the game uses under My Games and %LOCALAPPDATA%.
+ */
+pub fn get_game_folder_name(game: &str) -> &'static str {
+    match game {
+        "skyrim" => "Skyrim",
+        "skyrimvr" => "Skyrim VR",
+        _ => "Skyrim Special Edition",
+    }
+}
+
+pub fn get_game_save_folder(game: &str) -> Option<PathBuf> {
+    let mut path = dirs::document_dir()?;
+    path.push("My Games");
+    path.push(get_game_folder_name(game));
+    path.push("Saves");
+    Some(path)
+}
+
+pub fn create_mod_manager(
+    kind: ModManagerKind,
+    game: &str,
+    mo2_instance_path: Option<&Path>,
+    data_path: Option<&Path>,
+) -> Result<Arc<dyn ModManager>, Error> {
+    let manager: Arc<dyn ModManager> = match kind {
+        ModManagerKind::Vortex => Arc::new(VortexManager::new(game, data_path)),
+        ModManagerKind::ModOrganizer2 => {
+            let instance = match mo2_instance_path {
+                Some(path) => read_instance(path, path.join("portable.txt").exists())?,
+                None => Mo2Manager::find_instance(game)?,
+            };
+            Arc::new(Mo2Manager::new(instance))
+        }
+        ModManagerKind::DataFolder => Arc::new(DataFolderManager::new(
+            game,
+            data_path.map(Path::to_path_buf),
+        )),
+    };
+    Ok(manager)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::ErrorKind;
+
+    struct FakeManager;
+
+    impl ModManager for FakeManager {
+        fn kind(&self) -> ModManagerKind {
+            ModManagerKind::ModOrganizer2
+        }
+
+        fn game(&self) -> &str {
+            "skyrimse"
+        }
+
+        fn profiles(&self) -> Result<Vec<Profile>, Error> {
+            Ok(vec![
+                Profile::named("Survival"),
+                Profile::named("Broken"),
+                Profile::named("Default"),
+            ])
+        }
+
+        fn profile_path(&self, profile: &str) -> Result<PathBuf, Error> {
+            Ok(PathBuf::from(profile))
+        }
+
+        fn load_order(&self, profile: &str) -> Result<Vec<ProfilePlugin>, Error> {
+            let plugin = |name: &str, enabled| ProfilePlugin {
+                name: name.to_string(),
+                enabled,
+            };
+            match profile {
+                "Survival" => Ok(vec![plugin("SkyUI_SE.esp", true), plugin("Off.esp", false)]),
+                "Default" => Ok(vec![
+                    plugin("skyui_se.esp", true),
+                    plugin("Off.esp", true),
+                    plugin("Unused.esp", false),
+                ]),
+                _ => Err(Error::new(ErrorKind::NotFound, "no plugins.txt")),
+            }
+        }
+
+        fn plugin_owners(&self, profile: &str) -> HashMap<String, String> {
+            match profile {
+                "Survival" => HashMap::from([("skyui_se.esp".to_string(), "SkyUI".to_string())]),
+                _ => HashMap::new(),
+            }
+        }
+
+        fn save_folders(&self) -> Vec<(String, PathBuf)> {
+            Vec::new()
+        }
+    }
+
+    #[test]
+    fn installed_plugins_remember_their_source() {
+        let installed = get_installed_plugins(&FakeManager);
+
+        let skyui = installed.get("skyui_se.esp").unwrap();
+        assert_eq!(
+            skyui.source(ModManagerKind::ModOrganizer2),
+            "SkyUI (Survival)"
+        );
+        let off = installed.get("off.esp").unwrap();
+        assert_eq!(
+            off.source(ModManagerKind::ModOrganizer2),
+            "Mod Organizer 2 profile Default"
+        );
+        // The base game is always active
+        assert!(installed.contains_key("skyrim.esm"));
+    }
+
+    #[test]
+    fn plugins_have_three_states() {
+        let installed = get_installed_plugins(&FakeManager);
+        assert_eq!(
+            get_plugin_state(&installed, "OFF.ESP"),
+            PluginState::Enabled
+        );
+        assert_eq!(
+            get_plugin_state(&installed, "Unused.esp"),
+            PluginState::Disabled
+        );
+        assert_eq!(
+            get_plugin_state(&installed, "Gone.esp"),
+            PluginState::Absent
+        );
+    }
+}
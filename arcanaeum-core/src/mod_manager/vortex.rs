@@ -0,0 +1,259 @@
+use std::collections::HashMap;
+use std::io::Error;
+use std::path::{Path, PathBuf};
+
+use crate::load_order::profile::ProfilePlugin;
+use crate::mod_search::vortex_scanner::{
+    get_deployed_file_sources, get_profile_path, get_profile_plugins, get_profiles_from_state,
+    normalize_deployed_path, read_deployment_manifests,
+};
+use crate::mod_search::vortex_state::{
+    get_plugin_mods_from_state, read_vortex_state, VortexMod, VortexState,
+};
+
+use super::{
+    get_game_folder_name, get_game_save_folder, ModDetails, ModManager, ModManagerKind, Profile,
+};
+
+pub struct VortexManager {
+    game: String,
+    /**
+     * Mods from Vortex's database, by id.
+     */
+    mods: HashMap<String, VortexMod>,
+    /**
+     * Mods from Vortex's database, keyed by the lowercased names of their plugins.
+     */
+    plugin_mods: HashMap<String, VortexMod>,
+    /**
+     * Deployed files from the deployment manifests, as normalized paths relative to the
+     * Data folder, mapped to the id of their mod.
+     */
+    deployed_files: HashMap<String, String>,
+    active_profile: Option<String>,
+    /**
+     * The profiles found when the manager was made. Reading them means replaying the whole
+     * database, which is too slow to do for every lookup.
+     */
+    profiles: Result<Vec<Profile>, Error>,
+}
+
+fn is_plugin_path(path: &str) -> bool {
+    !path.contains('/') && [".esp", ".esm", ".esl"].iter().any(|e| path.ends_with(e))
+}
+
+impl VortexManager {
+    pub fn new(game: &str, data_path: Option<&Path>) -> VortexManager {
+        let state = match read_vortex_state() {
+            Ok(state) => state,
+            Err(e) => {
+                tracing::info!("Could not read Vortex database: {}", e);
+                VortexState::default()
+            }
+        };
+        let plugin_mods = match get_plugin_mods_from_state(&state, game) {
+            Ok(plugin_mods) => plugin_mods,
+            Err(e) => {
+                tracing::info!("Could not read mods from Vortex database: {}", e);
+                HashMap::new()
+            }
+        };
+        let mods = state
+            .get_mods(game)
+            .into_iter()
+            .map(|vortex_mod| (vortex_mod.id.clone(), vortex_mod))
+            .collect();
+
+        let mut manifests = Vec::new();
+        if let Some(data_path) = data_path {
+            manifests.extend(read_deployment_manifests(data_path));
+        }
+        if manifests.is_empty() {
+            if let Ok(staging_path) = state.get_staging_path(game) {
+                manifests.extend(read_deployment_manifests(&staging_path));
+            }
+        }
+
+        let profiles = get_profiles_from_state(&state, game).map(|profiles| {
+            profiles
+                .into_iter()
+                .map(|profile| Profile {
+                    id: profile.id,
+                    name: profile.name,
+                })
+                .collect()
+        });
+
+        VortexManager {
+            game: game.to_string(),
+            mods,
+            plugin_mods,
+            deployed_files: get_deployed_file_sources(&manifests),
+            active_profile: state.get_active_profile(game),
+            profiles,
+        }
+    }
+
+    pub fn plugin_mods(&self) -> &HashMap<String, VortexMod> {
+        &self.plugin_mods
+    }
+
+    fn get_mod_name(&self, id: &str) -> String {
+        match self.mods.get(id) {
+            Some(vortex_mod) => vortex_mod.name.clone(),
+            None => id.to_string(),
+        }
+    }
+
+    /**
+     * The name of the mod that deployed a file, given its path relative to the Data folder.
+     */
+    pub fn file_owner(&self, path: &str) -> Option<String> {
+        let id = self.deployed_files.get(&normalize_deployed_path(path))?;
+        Some(self.get_mod_name(id))
+    }
+}
+
+impl ModManager for VortexManager {
+    fn kind(&self) -> ModManagerKind {
+        ModManagerKind::Vortex
+    }
+
+    fn game(&self) -> &str {
+        &self.game
+    }
+
+    fn profiles(&self) -> Result<Vec<Profile>, Error> {
+        match &self.profiles {
+            Ok(profiles) => Ok(profiles.clone()),
+            Err(e) => Err(Error::new(e.kind(), e.to_string())),
+        }
+    }
+
+    fn profile_path(&self, profile: &str) -> Result<PathBuf, Error> {
+        get_profile_path(&self.game, profile)
+    }
+
+    fn active_profile(&self) -> Option<String> {
+        match &self.active_profile {
+            Some(id) => Some(id.clone()),
+            None => self
+                .profiles()
+                .ok()?
+                .into_iter()
+                .next()
+                .map(|profile| profile.id),
+        }
+    }
+
+    fn load_order(&self, profile: &str) -> Result<Vec<ProfilePlugin>, Error> {
+        get_profile_plugins(&self.game, profile)
+    }
+
+    /**
+     * Vortex deploys every mod into the same Data folder. The deployment manifests say which
+     * mod won, the staging folders of the mods in its database fill in the rest.
+     */
+    fn plugin_owners(&self, _profile: &str) -> HashMap<String, String> {
+        let mut owners: HashMap<String, String> = self
+            .plugin_mods
+            .iter()
+            .map(|(plugin, vortex_mod)| (plugin.clone(), vortex_mod.name.clone()))
+            .collect();
+        for (path, id) in &self.deployed_files {
+            if is_plugin_path(path) {
+                owners.insert(path.clone(), self.get_mod_name(id));
+            }
+        }
+        owners
+    }
+
+    fn mod_details(&self, plugin: &str) -> Option<ModDetails> {
+        let key = plugin.to_lowercase();
+        let vortex_mod = match self.deployed_files.get(&key) {
+            Some(id) => self.mods.get(id),
+            None => self.plugin_mods.get(&key),
+        }?;
+        Some(ModDetails {
+            name: vortex_mod.name.clone(),
+            version: vortex_mod.version.clone(),
+            url: vortex_mod.nexus_url(&self.game),
+        })
+    }
+
+    fn save_folders(&self) -> Vec<(String, PathBuf)> {
+        get_game_save_folder(&self.game)
+            .map(|path| vec![(get_game_folder_name(&self.game).to_string(), path)])
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::ErrorKind;
+
+    fn vortex_mod(id: &str, name: &str) -> VortexMod {
+        VortexMod {
+            id: id.to_string(),
+            name: name.to_string(),
+            version: None,
+            source: None,
+            nexus_mod_id: None,
+            nexus_file_id: None,
+            installation_path: id.to_string(),
+        }
+    }
+
+    fn manager() -> VortexManager {
+        let skyui = vortex_mod("SkyUI-12604", "SkyUI");
+        let old = vortex_mod("OldUI-1", "Old UI");
+        VortexManager {
+            game: "skyrimse".to_string(),
+            mods: HashMap::from([(skyui.id.clone(), skyui), (old.id.clone(), old.clone())]),
+            plugin_mods: HashMap::from([("skyui_se.esp".to_string(), old)]),
+            deployed_files: HashMap::from([
+                ("skyui_se.esp".to_string(), "SkyUI-12604".to_string()),
+                (
+                    "scripts/ski_configbase.pex".to_string(),
+                    "SkyUI-12604".to_string(),
+                ),
+                ("gone.esp".to_string(), "Removed-5".to_string()),
+            ]),
+            active_profile: None,
+            profiles: Ok(vec![Profile::named("Default")]),
+        }
+    }
+
+    #[test]
+    fn deployed_files_decide_plugin_owners() {
+        let owners = manager().plugin_owners("Default");
+        assert_eq!(owners.get("skyui_se.esp").unwrap(), "SkyUI");
+        assert_eq!(owners.get("gone.esp").unwrap(), "Removed-5");
+        assert!(!owners.contains_key("scripts/ski_configbase.pex"));
+    }
+
+    #[test]
+    fn profiles_are_kept_from_when_the_manager_was_made() {
+        let manager = manager();
+        assert_eq!(manager.profiles().unwrap(), vec![Profile::named("Default")]);
+        assert_eq!(manager.active_profile().as_deref(), Some("Default"));
+
+        let missing = VortexManager {
+            profiles: Err(Error::new(ErrorKind::NotFound, "no profiles folder")),
+            ..manager
+        };
+        assert_eq!(missing.profiles().unwrap_err().kind(), ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn any_deployed_file_can_be_traced() {
+        assert_eq!(
+            manager()
+                .file_owner("Scripts\\SKI_ConfigBase.pex")
+                .as_deref(),
+            Some("SkyUI")
+        );
+        assert_eq!(manager().file_owner("Scripts\\Other.pex"), None);
+    }
+}
@@ -0,0 +1,694 @@
+use std::{
+    collections::HashMap,
+    env, fs,
+    io::{Error, ErrorKind},
+    path::{Path, PathBuf},
+};
+
+use ini::{Ini, ParseOption};
+use serde::{Deserialize, Serialize};
+
+use crate::load_order::profile::{read_profile_load_order, ProfilePlugin};
+
+const INSTANCE_INI: &str = "ModOrganizer.ini";
+const PLUGIN_EXTENSIONS: [&str; 3] = ["esp", "esm", "esl"];
+
+/**
+ * A Mod Organizer 2 instance, either global (in %LOCALAPPDATA%/ModOrganizer) or portable
+ * (next to ModOrganizer.exe).
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Mo2Instance {
+    pub name: String,
+    pub path: PathBuf,
+    pub portable: bool,
+    pub game_name: String,
+    pub game_path: Option<PathBuf>,
+    pub selected_profile: Option<String>,
+    pub mods_path: PathBuf,
+    pub profiles_path: PathBuf,
+    pub downloads_path: PathBuf,
+    pub overwrite_path: PathBuf,
+}
+
+impl Mo2Instance {
+    /**
+     * The game id used by the rest of Arcanaeum, e.g. for Vortex folders.
+     */
+    pub fn game(&self) -> Option<&'static str> {
+        match self.game_name.to_lowercase().as_str() {
+            "skyrim special edition" => Some("skyrimse"),
+            "skyrim" => Some("skyrim"),
+            "skyrim vr" => Some("skyrimvr"),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ModState {
+    Enabled,
+    Disabled,
+    /**
+     * Files MO2 doesn't manage, like the base game and DLC.
+     */
+    Unmanaged,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Mo2Mod {
+    pub name: String,
+    pub state: ModState,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Mo2Profile {
+    pub name: String,
+    pub path: PathBuf,
+    /**
+     * Mods in priority order, the last one wins when files conflict.
+     */
+    pub mods: Vec<Mo2Mod>,
+    pub plugins: Vec<ProfilePlugin>,
+    pub local_saves: bool,
+}
+
+impl Mo2Profile {
+    pub fn saves_path(&self) -> Option<PathBuf> {
+        match self.local_saves {
+            true => Some(self.path.join("saves")),
+            false => None,
+        }
+    }
+}
+
+/**
+ * Qt writes some values as @ByteArray(...) or in quotes, and doubles the backslashes in
+ * paths.
+ */
+fn read_qt_value(value: &str) -> String {
+    let value = value
+        .strip_prefix("@ByteArray(")
+        .and_then(|v| v.strip_suffix(')'))
+        .or_else(|| value.strip_prefix('"').and_then(|v| v.strip_suffix('"')))
+        .unwrap_or(value);
+    value.replace("\\\\", "\\")
+}
+
+/**
+ * Parses an INI written by Qt. Quotes are left in place, descriptions downloaded from
+ * Nexus can have unbalanced ones.
+ */
+fn parse_qt_ini(contents: &str) -> Result<Ini, Error> {
+    let options = ParseOption {
+        enabled_quote: false,
+        enabled_escape: false,
+    };
+    Ini::load_from_str_opt(contents, options)
+        .map_err(|e| Error::new(ErrorKind::InvalidData, e.to_string()))
+}
+
+fn load_ini(path: &Path) -> Result<Ini, Error> {
+    Ini::load_from_file_noescape(path)
+        .map_err(|e| Error::new(ErrorKind::InvalidData, e.to_string()))
+}
+
+/**
+ * Reads the paths of an instance from its ModOrganizer.ini. Folders that aren't set are
+ * relative to the base directory, which defaults to the instance folder.
+ */
+pub fn parse_instance(contents: &str, path: &Path, portable: bool) -> Result<Mo2Instance, Error> {
+    let ini = Ini::load_from_str_noescape(contents)
+        .map_err(|e| Error::new(ErrorKind::InvalidData, e.to_string()))?;
+    let get = |section: &str, key: &str| ini.get_from(Some(section), key).map(read_qt_value);
+
+    let base_path = get("Settings", "base_directory")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| path.to_path_buf());
+    let get_folder = |key: &str, default: &str| match get("Settings", key) {
+        Some(value) => PathBuf::from(value.replace("%BASE_DIR%", &base_path.to_string_lossy())),
+        None => base_path.join(default),
+    };
+
+    let name = path
+        .file_name()
+        .map(|f| f.to_string_lossy().to_string())
+        .unwrap_or_default();
+    Ok(Mo2Instance {
+        name,
+        path: path.to_path_buf(),
+        portable,
+        game_name: get("General", "gameName").unwrap_or_default(),
+        game_path: get("General", "gamePath").map(PathBuf::from),
+        selected_profile: get("General", "selected_profile"),
+        mods_path: get_folder("mod_directory", "mods"),
+        profiles_path: get_folder("profiles_directory", "profiles"),
+        downloads_path: get_folder("download_directory", "downloads"),
+        overwrite_path: get_folder("overwrite_directory", "overwrite"),
+    })
+}
+
+pub fn read_instance(path: &Path, portable: bool) -> Result<Mo2Instance, Error> {
+    let contents = fs::read_to_string(path.join(INSTANCE_INI))?;
+    parse_instance(&contents, path, portable)
+}
+
+fn get_global_instances_path() -> Result<PathBuf, Error> {
+    let local_app_data = env::var("LOCALAPPDATA")
+        .map_err(|_| Error::new(ErrorKind::NotFound, "LOCALAPPDATA is not set"))?;
+    Ok(PathBuf::from(local_app_data).join("ModOrganizer"))
+}
+
+/**
+ * The folders of the MO2 installs registered as nxm link handlers, read from the
+ * nxmhandler.ini in the global instances folder.
+ */
+fn get_handler_install_paths(global_path: &Path) -> Vec<PathBuf> {
+    let ini = match load_ini(&global_path.join("nxmhandler.ini")) {
+        Ok(ini) => ini,
+        Err(_) => return Vec::new(),
+    };
+    let handlers = match ini.section(Some("handlers")) {
+        Some(handlers) => handlers,
+        None => return Vec::new(),
+    };
+    handlers
+        .iter()
+        .filter(|(key, _)| key.ends_with("executable"))
+        .filter_map(|(_, value)| {
+            PathBuf::from(read_qt_value(value))
+                .parent()
+                .map(Path::to_path_buf)
+        })
+        .collect()
+}
+
+/**
+ * Portable installs register themselves as nxm link handlers, which is the only place
+ * outside their own folder that remembers where they are.
+ */
+fn get_portable_instance_paths(global_path: &Path) -> Vec<PathBuf> {
+    get_handler_install_paths(global_path)
+        .into_iter()
+        .filter(|path| path.join("portable.txt").exists())
+        .collect()
+}
+
+/**
+ * The folders MO2 is installed in, as far as they can be found.
+ */
+pub fn find_install_paths() -> Vec<PathBuf> {
+    match get_global_instances_path() {
+        Ok(global_path) => get_handler_install_paths(&global_path),
+        Err(_) => Vec::new(),
+    }
+}
+
+/**
+ * Finds the global instances and the portable ones registered as nxm handlers, plus any
+ * extra portable folders that were picked by hand.
+ */
+pub fn find_instances(extra_portable_paths: &[PathBuf]) -> Vec<Mo2Instance> {
+    let mut instances = Vec::new();
+    let mut portable_paths = extra_portable_paths.to_vec();
+
+    match get_global_instances_path() {
+        Ok(global_path) => {
+            if let Ok(entries) = fs::read_dir(&global_path) {
+                for entry in entries.filter_map(|e| e.ok()) {
+                    let path = entry.path();
+                    if path.join(INSTANCE_INI).exists() {
+                        match read_instance(&path, false) {
+                            Ok(instance) => instances.push(instance),
+                            Err(e) => {
+                                tracing::error!("Could not read MO2 instance {:?}: {}", path, e)
+                            }
+                        }
+                    }
+                }
+            }
+            portable_paths.extend(get_portable_instance_paths(&global_path));
+        }
+        Err(e) => tracing::info!("No global MO2 instances: {}", e),
+    }
+
+    for path in portable_paths {
+        if instances.iter().any(|i| i.path == path) {
+            continue;
+        }
+        match read_instance(&path, true) {
+            Ok(instance) => instances.push(instance),
+            Err(e) => tracing::error!("Could not read MO2 instance {:?}: {}", path, e),
+        }
+    }
+    instances
+}
+
+pub fn find_instances_for_game(game: &str) -> Vec<Mo2Instance> {
+    find_instances(&[])
+        .into_iter()
+        .filter(|instance| instance.game() == Some(game))
+        .collect()
+}
+
+/**
+ * Parses a modlist.txt. MO2 writes the highest priority mod first, so the list is reversed
+ * to priority order. Separators are only for display and are left out.
+ */
+pub fn parse_modlist_txt(contents: &str) -> Vec<Mo2Mod> {
+    let mut mods: Vec<Mo2Mod> = contents
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let state = match line.chars().next()? {
+                '+' => ModState::Enabled,
+                '-' => ModState::Disabled,
+                '*' => ModState::Unmanaged,
+                _ => return None,
+            };
+            let name = &line[1..];
+            match name.ends_with("_separator") {
+                true => None,
+                false => Some(Mo2Mod {
+                    name: name.to_string(),
+                    state,
+                }),
+            }
+        })
+        .collect();
+    mods.reverse();
+    mods
+}
+
+pub fn get_profiles(instance: &Mo2Instance) -> Result<Vec<String>, Error> {
+    let mut profiles: Vec<String> = fs::read_dir(&instance.profiles_path)?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .collect();
+    profiles.sort();
+    Ok(profiles)
+}
+
+pub fn read_profile(instance: &Mo2Instance, name: &str) -> Result<Mo2Profile, Error> {
+    let path = instance.profiles_path.join(name);
+    let mods = parse_modlist_txt(&fs::read_to_string(path.join("modlist.txt"))?);
+    let plugins = read_profile_load_order(&path, instance.game().unwrap_or("skyrimse"))?;
+    let local_saves = load_ini(&path.join("settings.ini"))
+        .ok()
+        .and_then(|ini| {
+            ini.get_from(Some("General"), "LocalSaves")
+                .map(|value| value.eq_ignore_ascii_case("true"))
+        })
+        .unwrap_or(false);
+
+    Ok(Mo2Profile {
+        name: name.to_string(),
+        path,
+        mods,
+        plugins,
+        local_saves,
+    })
+}
+
+fn list_plugin_files(folder: &Path) -> Vec<String> {
+    let entries = match fs::read_dir(folder) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .extension()
+                    .is_some_and(|e| PLUGIN_EXTENSIONS.iter().any(|p| e.eq_ignore_ascii_case(p)))
+        })
+        .filter_map(|path| path.file_name().map(|f| f.to_string_lossy().to_string()))
+        .collect()
+}
+
+/**
+ * Maps plugin names, lowercased, to the enabled mod that provides them. When several mods
+ * have the same plugin the one with the highest priority wins, and the overwrite folder
+ * wins over all of them, like in MO2's virtual file system.
+ */
+pub fn get_plugin_owners(
+    profile: &Mo2Profile,
+    list_plugins: impl Fn(&str) -> Vec<String>,
+) -> HashMap<String, String> {
+    let mut owners = HashMap::new();
+    for mod_entry in profile.mods.iter().filter(|m| m.state == ModState::Enabled) {
+        for plugin in list_plugins(&mod_entry.name) {
+            owners.insert(plugin.to_lowercase(), mod_entry.name.clone());
+        }
+    }
+    owners
+}
+
+pub fn get_plugin_owners_from_instance(
+    instance: &Mo2Instance,
+    profile: &Mo2Profile,
+) -> HashMap<String, String> {
+    let mut owners = get_plugin_owners(profile, |mod_name| {
+        list_plugin_files(&instance.mods_path.join(mod_name))
+    });
+    for plugin in list_plugin_files(&instance.overwrite_path) {
+        owners.insert(plugin.to_lowercase(), "Overwrite".to_string());
+    }
+    owners
+}
+
+/**
+ * The save folders of every profile that keeps its own saves, labelled with the instance
+ * and profile names.
+ */
+pub fn get_profile_save_folders(instances: &[Mo2Instance]) -> Vec<(String, PathBuf)> {
+    let mut folders = Vec::new();
+    for instance in instances {
+        for name in get_profiles(instance).unwrap_or_default() {
+            match read_profile(instance, &name) {
+                Ok(profile) => {
+                    if let Some(saves_path) = profile.saves_path() {
+                        folders.push((format!("MO2 {} / {}", instance.name, name), saves_path));
+                    }
+                }
+                Err(e) => tracing::error!("Could not read MO2 profile {}: {}", name, e),
+            }
+        }
+    }
+    folders
+}
+
+/**
+ * What MO2 saved about a downloaded archive in the .meta file next to it.
+ */
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Mo2Download {
+    pub archive: String,
+    pub game_name: Option<String>,
+    pub mod_id: Option<u64>,
+    pub file_id: Option<u64>,
+    pub name: Option<String>,
+    pub mod_name: Option<String>,
+    pub version: Option<String>,
+    pub url: Option<String>,
+}
+
+/**
+ * The Nexus domain for the game names MO2 writes in .meta files.
+ */
+fn get_nexus_domain(game_name: &str) -> Option<&'static str> {
+    match game_name.to_lowercase().as_str() {
+        "skyrimse" | "skyrimspecialedition" | "skyrim special edition" | "skyrimvr" => {
+            Some("skyrimspecialedition")
+        }
+        "skyrim" => Some("skyrim"),
+        _ => None,
+    }
+}
+
+impl Mo2Download {
+    pub fn nexus_url(&self) -> Option<String> {
+        if let Some(url) = self.url.as_ref().filter(|url| !url.is_empty()) {
+            return Some(url.clone());
+        }
+        let domain = get_nexus_domain(self.game_name.as_deref()?)?;
+        let mod_id = self.mod_id.filter(|id| *id > 0)?;
+        Some(format!(
+            "https://www.nexusmods.com/{}/mods/{}",
+            domain, mod_id
+        ))
+    }
+}
+
+fn get_general_value(ini: &Ini, key: &str) -> Option<String> {
+    ini.get_from(Some("General"), key)
+        .map(read_qt_value)
+        .filter(|value| !value.is_empty())
+}
+
+pub fn parse_download_meta(archive: &str, contents: &str) -> Result<Mo2Download, Error> {
+    let ini = parse_qt_ini(contents)?;
+    let get = |key: &str| get_general_value(&ini, key);
+    Ok(Mo2Download {
+        archive: archive.to_string(),
+        game_name: get("gameName"),
+        mod_id: get("modID").and_then(|v| v.parse().ok()),
+        file_id: get("fileID").and_then(|v| v.parse().ok()),
+        name: get("name"),
+        mod_name: get("modName"),
+        version: get("version"),
+        url: get("url"),
+    })
+}
+
+/**
+ * Indexes the .meta files in the downloads folder by the lowercased archive name.
+ */
+pub fn read_download_index(downloads_path: &Path) -> HashMap<String, Mo2Download> {
+    let entries = match fs::read_dir(downloads_path) {
+        Ok(entries) => entries,
+        Err(e) => {
+            tracing::info!("Could not read MO2 downloads {:?}: {}", downloads_path, e);
+            return HashMap::new();
+        }
+    };
+    let mut index = HashMap::new();
+    for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+        if !path
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("meta"))
+        {
+            continue;
+        }
+        let archive = match path.file_stem() {
+            Some(stem) => stem.to_string_lossy().to_string(),
+            None => continue,
+        };
+        match fs::read_to_string(&path).and_then(|c| parse_download_meta(&archive, &c)) {
+            Ok(download) => {
+                index.insert(archive.to_lowercase(), download);
+            }
+            Err(e) => tracing::error!("Could not read {:?}: {}", path, e),
+        }
+    }
+    index
+}
+
+/**
+ * The meta.ini in a mod folder, which remembers the archive the mod was installed from.
+ */
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Mo2ModMeta {
+    pub installation_file: Option<String>,
+    pub game_name: Option<String>,
+    pub mod_id: Option<u64>,
+    pub version: Option<String>,
+    pub url: Option<String>,
+    /**
+     * Ids into the instance's categories.dat.
+     */
+    pub categories: Vec<u32>,
+}
+
+pub fn parse_mod_meta(contents: &str) -> Result<Mo2ModMeta, Error> {
+    let ini = parse_qt_ini(contents)?;
+    let get = |key: &str| get_general_value(&ini, key);
+    Ok(Mo2ModMeta {
+        installation_file: get("installationFile"),
+        game_name: get("gameName"),
+        mod_id: get("modid").and_then(|v| v.parse().ok()),
+        version: get("version"),
+        url: get("url"),
+        categories: get("category")
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|id| id.trim().parse().ok())
+            .collect(),
+    })
+}
+
+pub fn read_mod_meta(instance: &Mo2Instance, mod_name: &str) -> Result<Mo2ModMeta, Error> {
+    let contents = fs::read_to_string(instance.mods_path.join(mod_name).join("meta.ini"))?;
+    parse_mod_meta(&contents)
+}
+
+/**
+ * Finds the download a mod was installed from. installationFile is either just the archive
+ * name or a full path, depending on how the mod was installed.
+ */
+pub fn find_mod_download<'a>(
+    index: &'a HashMap<String, Mo2Download>,
+    meta: &Mo2ModMeta,
+) -> Option<&'a Mo2Download> {
+    let installation_file = meta.installation_file.as_ref()?.replace('\\', "/");
+    let archive = installation_file.rsplit('/').next()?;
+    index.get(&archive.to_lowercase())
+}
+
+/**
+ * Parses MO2's categories.dat, which has a line per category in the form
+ * `id|name|nexus ids|parent id`.
+ */
+pub fn parse_categories(contents: &str) -> HashMap<u32, String> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split('|');
+            let id = fields.next()?.trim().parse().ok()?;
+            let name = fields.next()?.trim();
+            Some((id, name.to_string()))
+        })
+        .collect()
+}
+
+/**
+ * The categories of an instance by id. Instances that never changed them have no
+ * categories.dat.
+ */
+pub fn read_categories(instance: &Mo2Instance) -> HashMap<u32, String> {
+    match fs::read_to_string(instance.path.join("categories.dat")) {
+        Ok(contents) => parse_categories(&contents),
+        Err(_) => HashMap::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INSTANCE: &str = "[General]
+gameName=Skyrim Special Edition
+gamePath=@ByteArray(C:\\\\Steam\\\\steamapps\\\\common\\\\Skyrim Special Edition)
+selected_profile=@ByteArray(Survival)
+
+[Settings]
+base_directory=D:/Modding/SSE
+mod_directory=%BASE_DIR%/mods
+";
+
+    #[test]
+    fn parse_instance_resolves_folders() {
+        let instance = parse_instance(INSTANCE, Path::new("C:/MO2/SSE"), false).unwrap();
+        assert_eq!(instance.game(), Some("skyrimse"));
+        assert_eq!(
+            instance.game_path,
+            Some(PathBuf::from(
+                "C:\\Steam\\steamapps\\common\\Skyrim Special Edition"
+            ))
+        );
+        assert_eq!(instance.selected_profile.as_deref(), Some("Survival"));
+        assert_eq!(instance.mods_path, PathBuf::from("D:/Modding/SSE/mods"));
+        assert_eq!(
+            instance.profiles_path,
+            Path::new("D:/Modding/SSE").join("profiles")
+        );
+    }
+
+    #[test]
+    fn modlist_is_read_in_priority_order() {
+        let mods = parse_modlist_txt(
+            "# This file was automatically generated by Mod Organizer.\r\n\
+             +High Priority\r\n\
+             -Disabled Mod\r\n\
+             +Gameplay_separator\r\n\
+             *DLC: Dawnguard\r\n\
+             +Low Priority\r\n",
+        );
+        let names: Vec<&str> = mods.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "Low Priority",
+                "DLC: Dawnguard",
+                "Disabled Mod",
+                "High Priority"
+            ]
+        );
+        assert_eq!(mods[1].state, ModState::Unmanaged);
+        assert_eq!(mods[2].state, ModState::Disabled);
+    }
+
+    #[test]
+    fn higher_priority_mods_own_conflicting_plugins() {
+        let profile = Mo2Profile {
+            name: "Default".to_string(),
+            path: PathBuf::from("profiles/Default"),
+            mods: parse_modlist_txt("+Patch Hub\n-Old Patch\n+USSEP\n"),
+            plugins: Vec::new(),
+            local_saves: false,
+        };
+        let owners = get_plugin_owners(&profile, |mod_name| match mod_name {
+            "USSEP" => vec!["Unofficial Skyrim Special Edition Patch.esp".to_string()],
+            "Patch Hub" | "Old Patch" => vec![
+                "Unofficial Skyrim Special Edition Patch.esp".to_string(),
+                "Patch.esp".to_string(),
+            ],
+            _ => Vec::new(),
+        });
+        assert_eq!(
+            owners.get("unofficial skyrim special edition patch.esp"),
+            Some(&"Patch Hub".to_string())
+        );
+        assert_eq!(owners.len(), 2);
+    }
+
+    #[test]
+    fn download_meta_has_nexus_ids() {
+        let download = parse_download_meta(
+            "SkyUI_5_2_SE-12604-5-2SE.7z",
+            "[General]\r\n\
+             gameName=skyrimse\r\n\
+             modID=12604\r\n\
+             fileID=35407\r\n\
+             url=\"\"\r\n\
+             name=SkyUI\r\n\
+             description=\"Elegant, PC-friendly interface mod\r\n\
+             modName=SkyUI\r\n\
+             version=5.2SE\r\n\
+             installed=true\r\n",
+        )
+        .unwrap();
+        assert_eq!(download.mod_id, Some(12604));
+        assert_eq!(download.file_id, Some(35407));
+        assert_eq!(download.version.as_deref(), Some("5.2SE"));
+        assert_eq!(
+            download.nexus_url().as_deref(),
+            Some("https://www.nexusmods.com/skyrimspecialedition/mods/12604")
+        );
+    }
+
+    #[test]
+    fn mods_are_matched_to_their_download() {
+        let meta = parse_mod_meta(
+            "[General]\n\
+             gameName=SkyrimSE\n\
+             modid=12604\n\
+             version=5.2SE\n\
+             installationFile=C:/Modding/downloads/SkyUI_5_2_SE-12604-5-2SE.7z\n",
+        )
+        .unwrap();
+        let index = HashMap::from([(
+            "skyui_5_2_se-12604-5-2se.7z".to_string(),
+            Mo2Download {
+                archive: "SkyUI_5_2_SE-12604-5-2SE.7z".to_string(),
+                file_id: Some(35407),
+                ..Default::default()
+            },
+        )]);
+        let download = find_mod_download(&index, &meta).unwrap();
+        assert_eq!(download.file_id, Some(35407));
+    }
+
+    #[test]
+    fn mod_categories_are_named_from_categories_dat() {
+        let meta = parse_mod_meta("[General]\ncategory=\"12,59,\"\n").unwrap();
+        assert_eq!(meta.categories, vec![12, 59]);
+        let categories = parse_categories("12|Patches|79,84|0\r\n59|Gameplay|24|0\r\n");
+        assert_eq!(categories.get(&12).map(String::as_str), Some("Patches"));
+        assert_eq!(categories.get(&59).map(String::as_str), Some("Gameplay"));
+    }
+}
@@ -0,0 +1,68 @@
+pub mod mo2_scanner;
+pub mod nexus_api;
+pub mod nexus_search;
+pub mod vortex_scanner;
+pub mod vortex_state;
+
+use std::io::Error;
+
+use urlencoding::encode;
+
+use nexus_search::{NexusSearchClient, NexusSearchResult, SEARCH_URL};
+
+pub fn get_search_url(base_url: &str, game_id: u32, term: &str) -> String {
+    let encoded = encode(term).to_string();
+    format!(
+        "{}/mods?terms={}&game_id={}&blocked_tags=1069&blocked_authors=&include_adult=1",
+        base_url, encoded, game_id
+    )
+}
+
+pub fn get_url_for_search(term: &str) -> String {
+    get_search_url(SEARCH_URL, 1704, term)
+}
+
+/**
+ * Searches the Nexus for Skyrim Special Edition mods, without caching. The base URL is
+ * SEARCH_URL for the real search.
+ */
+pub async fn search_nexus_mods(
+    base_url: &str,
+    term: &str,
+) -> Result<Vec<NexusSearchResult>, Error> {
+    NexusSearchClient::new("skyrimse")
+        .with_base_url(base_url)
+        .search(term)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::nexus_search::test_server::TestServer;
+    use super::*;
+
+    #[test]
+    fn get_search_result_url() {
+        let url = get_url_for_search("cheese grater");
+        assert_eq!(url, "https://search.nexusmods.com/mods?terms=cheese%20grater&game_id=1704&blocked_tags=1069&blocked_authors=&include_adult=1".to_string());
+    }
+
+    #[tokio::test]
+    async fn search_nexus_mods_test() {
+        let server = TestServer::start(
+            200,
+            &[],
+            r#"{"total": 1, "results": [{"name": "Frostfall - Hypothermia Camping Survival", "mod_id": 671, "game_name": "skyrimspecialedition", "url": "/skyrimspecialedition/mods/671", "username": "Chesko", "endorsements": 1200}]}"#,
+        )
+        .await;
+        let results = search_nexus_mods(&server.url, "Frostfall").await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].mod_id, 671);
+        assert_eq!(results[0].username, "Chesko");
+        assert_eq!(
+            results[0].page_url(),
+            "https://www.nexusmods.com/skyrimspecialedition/mods/671"
+        );
+        assert!(server.request_lines()[0].starts_with("GET /mods?terms=Frostfall&game_id=1704&"));
+    }
+}
@@ -0,0 +1,632 @@
+use std::io::{Error, ErrorKind};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use reqwest::header::HeaderMap;
+use reqwest::{Method, RequestBuilder, Response, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+pub const API_URL: &str = "https://api.nexusmods.com";
+
+/**
+ * The game domain the API uses in its paths.
+ */
+pub fn get_nexus_game_domain(game: &str) -> &'static str {
+    match game {
+        "skyrim" => "skyrim",
+        _ => "skyrimspecialedition",
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NexusUser {
+    pub user_id: u64,
+    pub name: String,
+    pub is_premium: bool,
+    pub is_supporter: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NexusModInfo {
+    pub mod_id: u64,
+    pub domain_name: String,
+    pub name: Option<String>,
+    pub summary: Option<String>,
+    pub version: Option<String>,
+    pub author: Option<String>,
+    pub category_id: Option<u32>,
+    pub endorsement_count: u64,
+    pub updated_timestamp: u64,
+    pub available: bool,
+    pub status: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NexusFile {
+    pub file_id: u64,
+    pub name: String,
+    pub version: Option<String>,
+    pub category_name: Option<String>,
+    pub file_name: String,
+    pub size_kb: u64,
+    pub uploaded_timestamp: u64,
+    pub md5: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NexusFileUpdate {
+    pub old_file_id: u64,
+    pub new_file_id: u64,
+    pub uploaded_timestamp: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NexusModFiles {
+    pub files: Vec<NexusFile>,
+    pub file_updates: Vec<NexusFileUpdate>,
+}
+
+impl NexusModFiles {
+    /**
+     * The newest file replacing the given one, following the chain of updates the author
+     * recorded. None when the file is current.
+     */
+    pub fn latest_update(&self, file_id: u64) -> Option<&NexusFile> {
+        let mut current = file_id;
+        let mut seen = vec![current];
+        while let Some(update) = self.file_updates.iter().find(|u| u.old_file_id == current) {
+            if seen.contains(&update.new_file_id) {
+                break;
+            }
+            current = update.new_file_id;
+            seen.push(current);
+        }
+        match current == file_id {
+            true => None,
+            false => self.files.iter().find(|f| f.file_id == current),
+        }
+    }
+}
+
+/**
+ * A file found by its MD5, with the mod it belongs to.
+ */
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NexusMd5Match {
+    #[serde(rename = "mod")]
+    pub mod_info: NexusModInfo,
+    pub file_details: NexusFile,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NexusUpdatedMod {
+    pub mod_id: u64,
+    pub latest_file_update: u64,
+    pub latest_mod_activity: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NexusEndorsement {
+    pub mod_id: u64,
+    pub domain_name: String,
+    pub date: u64,
+    pub version: Option<String>,
+    pub status: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NexusTrackedMod {
+    pub mod_id: u64,
+    pub domain_name: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpdatePeriod {
+    Day,
+    Week,
+    Month,
+}
+
+impl UpdatePeriod {
+    fn as_query(&self) -> &'static str {
+        match self {
+            UpdatePeriod::Day => "1d",
+            UpdatePeriod::Week => "1w",
+            UpdatePeriod::Month => "1m",
+        }
+    }
+}
+
+/**
+ * Reads the times the rate limit headers give, which come as "2024-01-01T13:00:00+00:00"
+ * or "2024-01-02 00:00:00 +0000". Fractions of a second are dropped.
+ */
+pub fn parse_timestamp(text: &str) -> Option<SystemTime> {
+    let text = text.trim();
+    let number = |range: std::ops::Range<usize>| -> Option<i64> {
+        let digits = text.get(range)?;
+        match digits.bytes().all(|b| b.is_ascii_digit()) {
+            true => digits.parse().ok(),
+            false => None,
+        }
+    };
+    let (year, month, day) = (number(0..4)?, number(5..7)?, number(8..10)?);
+    let (hour, minute, second) = (number(11..13)?, number(14..16)?, number(17..19)?);
+    if !matches!(text.as_bytes().get(10), Some(b'T' | b' ')) || !(1..=12).contains(&month) {
+        return None;
+    }
+
+    let mut zone = text[19..].trim_start_matches(|c: char| c == '.' || c.is_ascii_digit());
+    zone = zone.trim_start();
+    let offset = match zone {
+        "" | "Z" | "UTC" => 0,
+        _ => {
+            let sign = match zone.as_bytes()[0] {
+                b'+' => 1,
+                b'-' => -1,
+                _ => return None,
+            };
+            let digits = zone[1..].replace(':', "");
+            if digits.len() != 4 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+                return None;
+            }
+            let hours: i64 = digits[..2].parse().ok()?;
+            let minutes: i64 = digits[2..].parse().ok()?;
+            sign * (hours * 60 + minutes) * 60
+        }
+    };
+
+    // Days since 1970-01-01 in the proleptic Gregorian calendar
+    let (y, m) = match month <= 2 {
+        true => (year - 1, month + 9),
+        false => (year, month - 3),
+    };
+    let era = y.div_euclid(400);
+    let year_of_era = y - era * 400;
+    let day_of_year = (153 * m + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146097 + day_of_era - 719468;
+
+    let seconds = days * 86400 + hour * 3600 + minute * 60 + second - offset;
+    let seconds = u64::try_from(seconds).ok()?;
+    Some(UNIX_EPOCH + Duration::from_secs(seconds))
+}
+
+/**
+ * What the last response said about the requests left. The API allows a daily amount,
+ * and once that is used up an hourly one.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateLimit {
+    pub received: SystemTime,
+    pub hourly_limit: Option<u32>,
+    pub hourly_remaining: Option<u32>,
+    pub hourly_reset: Option<SystemTime>,
+    pub daily_limit: Option<u32>,
+    pub daily_remaining: Option<u32>,
+    pub daily_reset: Option<SystemTime>,
+}
+
+impl RateLimit {
+    /**
+     * None when the response has no rate limit headers, as errors from the gateway don't.
+     */
+    pub fn from_headers(headers: &HeaderMap) -> Option<RateLimit> {
+        let get = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.to_string())
+        };
+        let get_number = |name: &str| get(name).and_then(|value| value.parse().ok());
+        let rate_limit = RateLimit {
+            received: SystemTime::now(),
+            hourly_limit: get_number("x-rl-hourly-limit"),
+            hourly_remaining: get_number("x-rl-hourly-remaining"),
+            hourly_reset: get("x-rl-hourly-reset").and_then(|value| parse_timestamp(&value)),
+            daily_limit: get_number("x-rl-daily-limit"),
+            daily_remaining: get_number("x-rl-daily-remaining"),
+            daily_reset: get("x-rl-daily-reset").and_then(|value| parse_timestamp(&value)),
+        };
+        match rate_limit.hourly_remaining.is_some() || rate_limit.daily_remaining.is_some() {
+            true => Some(rate_limit),
+            false => None,
+        }
+    }
+
+    /**
+     * When requests can be made again once both allowances are used up, which is whichever
+     * resets first. Without a reset header the hourly allowance is assumed back an hour
+     * after the response, and the daily one a day after.
+     */
+    pub fn resets_at(&self) -> SystemTime {
+        let hourly = self
+            .hourly_reset
+            .unwrap_or(self.received + Duration::from_secs(60 * 60));
+        let daily = self
+            .daily_reset
+            .unwrap_or(self.received + Duration::from_secs(24 * 60 * 60));
+        hourly.min(daily)
+    }
+
+    /**
+     * Whether no requests are left at the given time: both allowances are used up and
+     * neither has reset since.
+     */
+    pub fn is_exhausted_at(&self, now: SystemTime) -> bool {
+        self.daily_remaining == Some(0)
+            && self.hourly_remaining == Some(0)
+            && now < self.resets_at()
+    }
+
+    pub fn is_exhausted(&self) -> bool {
+        self.is_exhausted_at(SystemTime::now())
+    }
+}
+
+fn to_io_error(e: reqwest::Error) -> Error {
+    Error::other(e.to_string())
+}
+
+fn rate_limited(rate_limit: Option<&RateLimit>) -> Error {
+    let minutes = rate_limit
+        .and_then(|r| r.resets_at().duration_since(SystemTime::now()).ok())
+        .map(|left| left.as_secs() / 60 + 1);
+    Error::other(match minutes {
+        Some(minutes) => format!(
+            "Nexus API rate limit reached, it resets in {} minutes",
+            minutes
+        ),
+        None => "Nexus API rate limit reached, it resets within the hour".to_string(),
+    })
+}
+
+/**
+ * A client for the Nexus Mods v1 API, authenticated with the user's personal API key.
+ * Clones share what is known about the rate limit.
+ */
+#[derive(Debug, Clone)]
+pub struct NexusApiClient {
+    base_url: String,
+    api_key: String,
+    client: reqwest::Client,
+    rate_limit: Arc<Mutex<Option<RateLimit>>>,
+}
+
+impl NexusApiClient {
+    pub fn new(api_key: &str) -> NexusApiClient {
+        NexusApiClient {
+            base_url: API_URL.to_string(),
+            api_key: api_key.to_string(),
+            client: reqwest::Client::new(),
+            rate_limit: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub fn with_base_url(mut self, base_url: &str) -> NexusApiClient {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /**
+     * The rate limit from the last response that had one.
+     */
+    pub fn rate_limit(&self) -> Option<RateLimit> {
+        self.rate_limit.lock().unwrap().clone()
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        self.client
+            .request(method, format!("{}/v1/{}", self.base_url, path))
+            .header("apikey", &self.api_key)
+            .header("accept", "application/json")
+            .header("Application-Name", "Arcanaeum")
+            .header("Application-Version", env!("CARGO_PKG_VERSION"))
+    }
+
+    async fn send(&self, request: RequestBuilder) -> Result<Response, Error> {
+        {
+            let rate_limit = self.rate_limit.lock().unwrap();
+            if rate_limit.as_ref().is_some_and(RateLimit::is_exhausted) {
+                return Err(rate_limited(rate_limit.as_ref()));
+            }
+        }
+
+        let response = request.send().await.map_err(to_io_error)?;
+        let rate_limit = RateLimit::from_headers(response.headers());
+        if rate_limit.is_some() {
+            *self.rate_limit.lock().unwrap() = rate_limit.clone();
+        }
+        match response.status() {
+            StatusCode::TOO_MANY_REQUESTS => Err(rate_limited(rate_limit.as_ref())),
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Err(Error::new(
+                ErrorKind::PermissionDenied,
+                "The Nexus rejected the API key",
+            )),
+            StatusCode::NOT_FOUND => Err(Error::new(
+                ErrorKind::NotFound,
+                format!("Not found on the Nexus: {}", response.url().path()),
+            )),
+            _ => response.error_for_status().map_err(to_io_error),
+        }
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T, Error> {
+        let response = self.send(self.request(Method::GET, path)).await?;
+        response.json().await.map_err(to_io_error)
+    }
+
+    /**
+     * The user the API key belongs to, to check the key works.
+     */
+    pub async fn validate(&self) -> Result<NexusUser, Error> {
+        self.get("users/validate.json").await
+    }
+
+    pub async fn mod_info(&self, game: &str, mod_id: u64) -> Result<NexusModInfo, Error> {
+        let domain = get_nexus_game_domain(game);
+        self.get(&format!("games/{}/mods/{}.json", domain, mod_id))
+            .await
+    }
+
+    pub async fn mod_files(&self, game: &str, mod_id: u64) -> Result<NexusModFiles, Error> {
+        let domain = get_nexus_game_domain(game);
+        self.get(&format!("games/{}/mods/{}/files.json", domain, mod_id))
+            .await
+    }
+
+    /**
+     * Finds the mod files with the given MD5, to identify an archive or file whatever it
+     * was renamed to. No match is an empty list rather than an error.
+     */
+    pub async fn find_by_md5(&self, game: &str, md5: &str) -> Result<Vec<NexusMd5Match>, Error> {
+        let domain = get_nexus_game_domain(game);
+        let path = format!("games/{}/mods/md5_search/{}.json", domain, md5);
+        match self.get(&path).await {
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Vec::new()),
+            result => result,
+        }
+    }
+
+    /**
+     * The mods of the game that changed in the period, to check installed ones for
+     * updates without asking for each.
+     */
+    pub async fn updated_mods(
+        &self,
+        game: &str,
+        period: UpdatePeriod,
+    ) -> Result<Vec<NexusUpdatedMod>, Error> {
+        let domain = get_nexus_game_domain(game);
+        self.get(&format!(
+            "games/{}/mods/updated.json?period={}",
+            domain,
+            period.as_query()
+        ))
+        .await
+    }
+
+    /**
+     * The newer file for an installed one, if the author marked it as updated.
+     */
+    pub async fn check_file_update(
+        &self,
+        game: &str,
+        mod_id: u64,
+        file_id: u64,
+    ) -> Result<Option<NexusFile>, Error> {
+        let files = self.mod_files(game, mod_id).await?;
+        Ok(files.latest_update(file_id).cloned())
+    }
+
+    pub async fn endorsements(&self) -> Result<Vec<NexusEndorsement>, Error> {
+        self.get("user/endorsements.json").await
+    }
+
+    /**
+     * Endorses a mod, or abstains from endorsing it. The version is the one installed.
+     */
+    pub async fn endorse(
+        &self,
+        game: &str,
+        mod_id: u64,
+        version: &str,
+        endorse: bool,
+    ) -> Result<(), Error> {
+        let domain = get_nexus_game_domain(game);
+        let action = match endorse {
+            true => "endorse",
+            false => "abstain",
+        };
+        let request = self
+            .request(
+                Method::POST,
+                &format!("games/{}/mods/{}/{}.json", domain, mod_id, action),
+            )
+            .form(&[("version", version)]);
+        self.send(request).await.map(|_| ())
+    }
+
+    pub async fn tracked_mods(&self) -> Result<Vec<NexusTrackedMod>, Error> {
+        self.get("user/tracked_mods.json").await
+    }
+
+    pub async fn track(&self, game: &str, mod_id: u64, track: bool) -> Result<(), Error> {
+        let domain = get_nexus_game_domain(game);
+        let method = match track {
+            true => Method::POST,
+            false => Method::DELETE,
+        };
+        let request = self
+            .request(
+                method,
+                &format!("user/tracked_mods.json?domain_name={}", domain),
+            )
+            .form(&[("mod_id", mod_id.to_string())]);
+        self.send(request).await.map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::nexus_search::test_server::TestServer;
+    use super::*;
+
+    const RATE_LIMIT: [(&str, &str); 6] = [
+        ("X-RL-Hourly-Limit", "100"),
+        ("X-RL-Hourly-Remaining", "99"),
+        ("X-RL-Hourly-Reset", "2024-01-01T13:00:00+00:00"),
+        ("X-RL-Daily-Limit", "2500"),
+        ("X-RL-Daily-Remaining", "2400"),
+        ("X-RL-Daily-Reset", "2024-01-02 00:00:00 +0000"),
+    ];
+
+    #[tokio::test]
+    async fn requests_carry_the_key_and_remember_the_rate_limit() {
+        let server = TestServer::start(
+            200,
+            &RATE_LIMIT,
+            r#"{"mod_id": 266, "domain_name": "skyrimspecialedition", "name": "Unofficial Skyrim Special Edition Patch", "version": "4.2.9", "available": true, "status": "published"}"#,
+        )
+        .await;
+        let client = NexusApiClient::new("secret").with_base_url(&server.url);
+        let info = client.mod_info("skyrimse", 266).await.unwrap();
+        assert_eq!(info.version.as_deref(), Some("4.2.9"));
+
+        let request = server.requests.lock().unwrap()[0].to_lowercase();
+        assert!(request.starts_with("get /v1/games/skyrimspecialedition/mods/266.json"));
+        assert!(request.contains("apikey: secret"));
+
+        let rate_limit = client.rate_limit().unwrap();
+        assert_eq!(rate_limit.hourly_remaining, Some(99));
+        assert_eq!(rate_limit.daily_limit, Some(2500));
+        assert!(!rate_limit.is_exhausted());
+    }
+
+    #[tokio::test]
+    async fn exhausted_rate_limit_stops_requests() {
+        let server = TestServer::start(
+            200,
+            &[
+                ("X-RL-Hourly-Remaining", "0"),
+                ("X-RL-Daily-Remaining", "0"),
+                ("X-RL-Hourly-Reset", "2999-01-01T13:00:00+00:00"),
+            ],
+            "[]",
+        )
+        .await;
+        let client = NexusApiClient::new("secret").with_base_url(&server.url);
+        assert!(client.tracked_mods().await.unwrap().is_empty());
+        let e = client.endorsements().await.unwrap_err();
+        assert!(e.to_string().contains("rate limit reached"));
+        assert_eq!(server.request_lines().len(), 1);
+    }
+
+    #[test]
+    fn rate_limits_are_exhausted_until_their_reset() {
+        let mut headers = HeaderMap::new();
+        for (name, value) in RATE_LIMIT {
+            let value = match name.ends_with("Remaining") {
+                true => "0",
+                false => value,
+            };
+            headers.insert(name, value.parse().unwrap());
+        }
+        let rate_limit = RateLimit::from_headers(&headers).unwrap();
+        let hourly_reset = UNIX_EPOCH + Duration::from_secs(1704114000);
+        assert_eq!(rate_limit.hourly_reset, Some(hourly_reset));
+        assert_eq!(
+            rate_limit.daily_reset,
+            Some(UNIX_EPOCH + Duration::from_secs(1704153600))
+        );
+        assert!(rate_limit.is_exhausted_at(hourly_reset - Duration::from_secs(1)));
+        assert!(!rate_limit.is_exhausted_at(hourly_reset));
+        assert!(!rate_limit.is_exhausted());
+    }
+
+    #[test]
+    fn timestamps_are_read_with_their_offset() {
+        let at = |seconds| Some(UNIX_EPOCH + Duration::from_secs(seconds));
+        assert_eq!(parse_timestamp("1970-01-01T00:00:00Z"), at(0));
+        assert_eq!(
+            parse_timestamp("2024-02-29 12:30:15.250 +0000"),
+            at(1709209815)
+        );
+        assert_eq!(parse_timestamp("2024-01-01T14:00:00+01:00"), at(1704114000));
+        assert_eq!(parse_timestamp("2024-01-01T08:00:00-05:00"), at(1704114000));
+        assert_eq!(parse_timestamp("next hour"), None);
+        assert_eq!(parse_timestamp("2024-13-01T00:00:00Z"), None);
+    }
+
+    #[tokio::test]
+    async fn rejected_keys_are_permission_errors() {
+        let server =
+            TestServer::start(401, &[], r#"{"message": "Please provide a valid API Key"}"#).await;
+        let client = NexusApiClient::new("wrong").with_base_url(&server.url);
+        let e = client.validate().await.unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::PermissionDenied);
+    }
+
+    #[tokio::test]
+    async fn md5_search_finds_the_mod() {
+        let server = TestServer::start(
+            200,
+            &[],
+            r#"[{"mod": {"mod_id": 12604, "name": "SkyUI"}, "file_details": {"file_id": 35407, "name": "SkyUI_5_2_SE", "md5": "abc"}}]"#,
+        )
+        .await;
+        let client = NexusApiClient::new("secret").with_base_url(&server.url);
+        let matches = client.find_by_md5("skyrimse", "abc").await.unwrap();
+        assert_eq!(matches[0].mod_info.mod_id, 12604);
+        assert_eq!(matches[0].file_details.file_id, 35407);
+
+        let missing = TestServer::start(404, &[], "{}").await;
+        let client = client.with_base_url(&missing.url);
+        assert!(client
+            .find_by_md5("skyrimse", "def")
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn endorsing_posts_the_version() {
+        let server =
+            TestServer::start(200, &[], r#"{"message": "Endorsed", "status": "Endorsed"}"#).await;
+        let client = NexusApiClient::new("secret").with_base_url(&server.url);
+        client.endorse("skyrim", 3863, "5.2", true).await.unwrap();
+        assert_eq!(
+            server.request_lines(),
+            vec!["POST /v1/games/skyrim/mods/3863/endorse.json HTTP/1.1"]
+        );
+    }
+
+    #[test]
+    fn file_updates_are_followed_to_the_latest() {
+        let file = |file_id| NexusFile {
+            file_id,
+            ..Default::default()
+        };
+        let update = |old_file_id, new_file_id| NexusFileUpdate {
+            old_file_id,
+            new_file_id,
+            ..Default::default()
+        };
+        let files = NexusModFiles {
+            files: vec![file(1), file(2), file(3)],
+            file_updates: vec![update(2, 3), update(1, 2)],
+        };
+        assert_eq!(files.latest_update(1).map(|f| f.file_id), Some(3));
+        assert_eq!(files.latest_update(3), None);
+    }
+}
@@ -0,0 +1,412 @@
+use std::fs;
+use std::io::Error;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
+
+use super::get_search_url;
+
+pub const SEARCH_URL: &str = "https://search.nexusmods.com";
+const NEXUS_URL: &str = "https://www.nexusmods.com";
+
+/**
+ * The Nexus game ids the search takes, for the games Arcanaeum knows.
+ */
+pub fn get_nexus_game_id(game: &str) -> u32 {
+    match game {
+        "skyrim" => 110,
+        _ => 1704,
+    }
+}
+
+/**
+ * A mod found by the Nexus search. Only the fields shown are read, the search returns more.
+ */
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NexusSearchResult {
+    pub name: String,
+    pub mod_id: u64,
+    pub game_name: String,
+    /**
+     * The path of the mod page, e.g. /skyrimspecialedition/mods/671.
+     */
+    pub url: String,
+    pub image: Option<String>,
+    pub username: String,
+    pub downloads: u64,
+    pub endorsements: u64,
+    pub adult: bool,
+}
+
+impl NexusSearchResult {
+    pub fn page_url(&self) -> String {
+        match self.url.starts_with("http") {
+            true => self.url.clone(),
+            false => format!("{}{}", NEXUS_URL, self.url),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NexusSearchResponse {
+    pub terms: Vec<String>,
+    pub total: u64,
+    pub results: Vec<NexusSearchResult>,
+}
+
+/**
+ * Turns a plugin name into words the search can match: the extension goes, and
+ * separators and CamelCase become spaces, so "TrueStorms_SE.esp" is "True Storms SE".
+ */
+pub fn get_search_terms(plugin: &str) -> String {
+    let lower = plugin.to_lowercase();
+    let stem = [".esp", ".esm", ".esl"]
+        .iter()
+        .find_map(|extension| {
+            lower
+                .ends_with(extension)
+                .then(|| &plugin[..plugin.len() - extension.len()])
+        })
+        .unwrap_or(plugin);
+
+    let chars: Vec<char> = stem.chars().collect();
+    let mut words = Vec::new();
+    let mut word = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if !c.is_alphanumeric() {
+            words.push(std::mem::take(&mut word));
+            continue;
+        }
+        let previous = i.checked_sub(1).map(|i| chars[i]);
+        let next = chars.get(i + 1);
+        let starts_word = match previous {
+            Some(p) if c.is_uppercase() => {
+                p.is_lowercase() || (p.is_uppercase() && next.is_some_and(|n| n.is_lowercase()))
+            }
+            Some(p) => c.is_numeric() != p.is_numeric() && p.is_alphanumeric(),
+            None => false,
+        };
+        if starts_word {
+            words.push(std::mem::take(&mut word));
+        }
+        word.push(c);
+    }
+    words.push(word);
+    words.retain(|w| !w.is_empty());
+    words.join(" ")
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedSearch {
+    term: String,
+    results: Vec<NexusSearchResult>,
+}
+
+/**
+ * Search results kept on disk, one file per search term, so looking a plugin up again
+ * doesn't hit the Nexus.
+ */
+#[derive(Debug, Clone)]
+pub struct SearchCache {
+    folder: PathBuf,
+    max_age: Duration,
+}
+
+impl SearchCache {
+    pub fn new(folder: PathBuf, max_age: Duration) -> SearchCache {
+        SearchCache { folder, max_age }
+    }
+
+    /**
+     * A week old results in the user's cache folder.
+     */
+    pub fn in_cache_dir() -> Option<SearchCache> {
+        let folder = dirs::cache_dir()?.join("Arcanaeum").join("nexus_search");
+        Some(SearchCache::new(
+            folder,
+            Duration::from_secs(7 * 24 * 60 * 60),
+        ))
+    }
+
+    fn get_path(&self, game_id: u32, term: &str) -> PathBuf {
+        let name: String = term
+            .to_lowercase()
+            .chars()
+            .map(|c| match c.is_alphanumeric() {
+                true => c,
+                false => '_',
+            })
+            .collect();
+        self.folder.join(format!("{}-{}.json", game_id, name))
+    }
+
+    pub fn get(&self, game_id: u32, term: &str) -> Option<Vec<NexusSearchResult>> {
+        let path = self.get_path(game_id, term);
+        let modified = fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+        let age = SystemTime::now()
+            .duration_since(modified)
+            .unwrap_or_default();
+        if age > self.max_age {
+            return None;
+        }
+        let cached: CachedSearch = serde_json::from_str(&fs::read_to_string(path).ok()?).ok()?;
+        // Different terms can share a file name
+        match cached.term.eq_ignore_ascii_case(term) {
+            true => Some(cached.results),
+            false => None,
+        }
+    }
+
+    pub fn put(
+        &self,
+        game_id: u32,
+        term: &str,
+        results: &[NexusSearchResult],
+    ) -> Result<(), Error> {
+        fs::create_dir_all(&self.folder)?;
+        let cached = CachedSearch {
+            term: term.to_string(),
+            results: results.to_vec(),
+        };
+        fs::write(
+            self.get_path(game_id, term),
+            serde_json::to_string(&cached)?,
+        )
+    }
+}
+
+fn to_io_error(e: reqwest::Error) -> Error {
+    Error::other(e.to_string())
+}
+
+/**
+ * Searches the Nexus for mods of a game by name.
+ */
+#[derive(Debug, Clone)]
+pub struct NexusSearchClient {
+    base_url: String,
+    game_id: u32,
+    cache: Option<SearchCache>,
+    client: reqwest::Client,
+}
+
+impl NexusSearchClient {
+    pub fn new(game: &str) -> NexusSearchClient {
+        NexusSearchClient {
+            base_url: SEARCH_URL.to_string(),
+            game_id: get_nexus_game_id(game),
+            cache: None,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    pub fn with_base_url(mut self, base_url: &str) -> NexusSearchClient {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    pub fn with_cache(mut self, cache: Option<SearchCache>) -> NexusSearchClient {
+        self.cache = cache;
+        self
+    }
+
+    pub async fn search(&self, term: &str) -> Result<Vec<NexusSearchResult>, Error> {
+        if let Some(results) = self.cache.as_ref().and_then(|c| c.get(self.game_id, term)) {
+            return Ok(results);
+        }
+
+        let url = get_search_url(&self.base_url, self.game_id, term);
+        tracing::info!("Searching the Nexus: {}", url);
+        let response = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(to_io_error)?;
+        let response: NexusSearchResponse = response.json().await.map_err(to_io_error)?;
+
+        if let Some(cache) = &self.cache {
+            if let Err(e) = cache.put(self.game_id, term, &response.results) {
+                tracing::error!("Could not cache the search for {}: {}", term, e);
+            }
+        }
+        Ok(response.results)
+    }
+
+    /**
+     * Searches for the mod a plugin likely came from.
+     */
+    pub async fn search_plugin(&self, plugin: &str) -> Result<Vec<NexusSearchResult>, Error> {
+        self.search(&get_search_terms(plugin)).await
+    }
+}
+
+#[cfg(test)]
+pub mod test_server {
+    use std::sync::{Arc, Mutex};
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    fn get_content_length(head: &str) -> usize {
+        head.lines()
+            .filter_map(|line| line.split_once(':'))
+            .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+            .and_then(|(_, value)| value.trim().parse().ok())
+            .unwrap_or(0)
+    }
+
+    /**
+     * Reads the head of a request and as much body as it says it has.
+     */
+    async fn read_request(stream: &mut TcpStream) -> String {
+        let mut request = Vec::new();
+        let mut buf = [0; 1024];
+        loop {
+            let text = String::from_utf8_lossy(&request).to_string();
+            if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                if body.len() >= get_content_length(head) {
+                    return text;
+                }
+            }
+            match stream.read(&mut buf).await {
+                Ok(0) | Err(_) => return text,
+                Ok(read) => request.extend_from_slice(&buf[..read]),
+            }
+        }
+    }
+
+    /**
+     * Answers every request with the same response and remembers the request heads.
+     */
+    pub struct TestServer {
+        pub url: String,
+        pub requests: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl TestServer {
+        pub async fn start(status: u16, headers: &[(&str, &str)], body: &str) -> TestServer {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let url = format!("http://{}", listener.local_addr().unwrap());
+            let requests = Arc::new(Mutex::new(Vec::new()));
+            let mut response = format!(
+                "HTTP/1.1 {} Test\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n",
+                status,
+                body.len()
+            );
+            for (name, value) in headers {
+                response.push_str(&format!("{}: {}\r\n", name, value));
+            }
+            response.push_str("\r\n");
+            response.push_str(body);
+
+            let seen = requests.clone();
+            tokio::spawn(async move {
+                loop {
+                    let (mut stream, _) = match listener.accept().await {
+                        Ok(connection) => connection,
+                        Err(_) => return,
+                    };
+                    let request = read_request(&mut stream).await;
+                    seen.lock().unwrap().push(request);
+                    let _ = stream.write_all(response.as_bytes()).await;
+                    let _ = stream.shutdown().await;
+                }
+            });
+            TestServer { url, requests }
+        }
+
+        pub fn request_lines(&self) -> Vec<String> {
+            self.requests
+                .lock()
+                .unwrap()
+                .iter()
+                .filter_map(|r| r.lines().next().map(|l| l.to_string()))
+                .collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_server::TestServer;
+    use super::*;
+    use std::env;
+
+    const RESPONSE: &str = r#"{
+        "terms": ["frostfall"],
+        "exclude_authors": [],
+        "include_adult": true,
+        "took": 7,
+        "total": 1,
+        "results": [{
+            "name": "Frostfall - Hypothermia Camping Survival",
+            "downloads": 1215000,
+            "endorsements": 30000,
+            "url": "/skyrimspecialedition/mods/671",
+            "image": "/images/1704/671/thumb.jpg",
+            "username": "Chesko",
+            "user_id": 1053,
+            "game_name": "skyrimspecialedition",
+            "game_id": 1704,
+            "mod_id": 671,
+            "adult": false
+        }]
+    }"#;
+
+    #[test]
+    fn plugin_names_become_search_terms() {
+        assert_eq!(get_search_terms("TrueStormsSE.esp"), "True Storms SE");
+        assert_eq!(get_search_terms("Frostfall.esp"), "Frostfall");
+        assert_eq!(get_search_terms("SkyUI_SE-Patch.esl"), "Sky UI SE Patch");
+        assert_eq!(
+            get_search_terms("ImmersiveArmors2.ESM"),
+            "Immersive Armors 2"
+        );
+    }
+
+    #[tokio::test]
+    async fn search_reads_results_from_the_server() {
+        let server = TestServer::start(200, &[], RESPONSE).await;
+        let client = NexusSearchClient::new("skyrimse").with_base_url(&server.url);
+        let results = client.search_plugin("Frostfall.esp").await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].mod_id, 671);
+        assert_eq!(results[0].username, "Chesko");
+        assert_eq!(
+            results[0].page_url(),
+            "https://www.nexusmods.com/skyrimspecialedition/mods/671"
+        );
+        let requests = server.request_lines();
+        assert!(requests[0].starts_with("GET /mods?terms=Frostfall&game_id=1704&"));
+    }
+
+    #[tokio::test]
+    async fn server_errors_are_reported() {
+        let server = TestServer::start(503, &[], "{}").await;
+        let client = NexusSearchClient::new("skyrimse").with_base_url(&server.url);
+        assert!(client.search("Frostfall").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn cached_results_are_used_again() {
+        let folder = env::temp_dir().join(format!("arcanaeum-nexus-{}", std::process::id()));
+        let cache = SearchCache::new(folder.clone(), Duration::from_secs(60));
+        let server = TestServer::start(200, &[], RESPONSE).await;
+        let client = NexusSearchClient::new("skyrim")
+            .with_base_url(&server.url)
+            .with_cache(Some(cache.clone()));
+
+        let first = client.search("Frostfall").await.unwrap();
+        let second = client.search("frostfall").await.unwrap();
+        assert_eq!(first, second);
+        assert_eq!(server.request_lines().len(), 1);
+        assert_eq!(cache.get(110, "Frostfall"), Some(first));
+        assert_eq!(cache.get(1704, "Frostfall"), None);
+        fs::remove_dir_all(folder).unwrap();
+    }
+}
@@ -0,0 +1,343 @@
+use std::{
+    collections::HashMap,
+    env, fs,
+    io::{Error, ErrorKind},
+    path::PathBuf,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::load_order::profile::{read_profile_load_order, ProfilePlugin};
+use crate::loot::masterlist::PluginMetadata;
+use crate::loot::read_game_masterlist;
+
+use super::vortex_state::{read_vortex_state, VortexState};
+
+#[derive(Clone, Debug)]
+pub struct Plugin {
+    pub name: String,
+    pub urls: Vec<String>,
+}
+
+pub fn get_vortex_path() -> Result<PathBuf, Error> {
+    let app_data_path =
+        env::var("APPDATA").map_err(|_| Error::new(ErrorKind::NotFound, "APPDATA is not set"))?;
+    let mut path_buf = PathBuf::new();
+    path_buf.push(app_data_path);
+    path_buf.push("Vortex");
+    Ok(path_buf)
+}
+
+/**
+ * The LOOT masterlist Vortex downloads for a game.
+ */
+pub fn get_masterlist_path(game: &str) -> Result<PathBuf, Error> {
+    let mut path_buf = get_vortex_path()?;
+    path_buf.push(game);
+    path_buf.push("masterlist");
+    path_buf.push("masterlist.yaml");
+    Ok(path_buf)
+}
+
+pub fn get_masterlist_data(game: &str) -> Result<Vec<Plugin>, Error> {
+    tracing::info!("Looking for vortex at: {:?}", get_masterlist_path(game)?);
+
+    let masterlist = read_game_masterlist(game)?;
+    let plugin_master_list = masterlist.plugins.iter().map(parse_plugin).collect();
+    Ok(plugin_master_list)
+}
+
+/**
+ * The folder holding a Vortex profile's plugins.txt and loadorder.txt.
+ */
+pub fn get_profile_path(game: &str, profile: &str) -> Result<PathBuf, Error> {
+    let mut path_buf = get_vortex_path()?;
+    path_buf.push(game);
+    path_buf.push("profiles");
+    path_buf.push(profile);
+    Ok(path_buf)
+}
+
+/**
+ * A Vortex profile. Its folder is named after the id, the name is only in Vortex's state.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VortexProfile {
+    pub id: String,
+    pub name: String,
+    pub game: String,
+}
+
+/**
+ * Reads the profiles of a game from a dump of Vortex's state, as written to
+ * temp/state_backups, where they are under persistent.profiles.
+ */
+pub fn parse_state_profiles(state: &serde_json::Value, game: &str) -> Vec<VortexProfile> {
+    let profiles = match state
+        .pointer("/persistent/profiles")
+        .and_then(|p| p.as_object())
+    {
+        Some(profiles) => profiles,
+        None => return Vec::new(),
+    };
+    let mut found: Vec<VortexProfile> = profiles
+        .iter()
+        .filter_map(|(id, profile)| {
+            let profile_game = profile.get("gameId")?.as_str()?;
+            let name = profile
+                .get("name")
+                .and_then(|n| n.as_str())
+                .unwrap_or(id.as_str());
+            Some(VortexProfile {
+                id: id.clone(),
+                name: name.to_string(),
+                game: profile_game.to_string(),
+            })
+        })
+        .filter(|profile| profile.game == game)
+        .collect();
+    found.sort_by(|a, b| a.name.cmp(&b.name));
+    found
+}
+
+fn get_state_backup_path() -> Result<PathBuf, Error> {
+    let mut path_buf = get_vortex_path()?;
+    path_buf.push("temp");
+    path_buf.push("state_backups");
+    path_buf.push("startup.json");
+    Ok(path_buf)
+}
+
+/**
+ * The profile folders of a game, named after their ids.
+ */
+fn get_profile_ids(game: &str) -> Result<Vec<String>, Error> {
+    let mut path_buf = get_vortex_path()?;
+    path_buf.push(game);
+    path_buf.push("profiles");
+
+    let items = fs::read_dir(path_buf)?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .collect();
+    Ok(items)
+}
+
+fn get_backup_profiles(game: &str) -> Result<Vec<VortexProfile>, Error> {
+    let profiles = match fs::read_to_string(get_state_backup_path()?) {
+        Ok(contents) => match serde_json::from_str(&contents) {
+            Ok(state) => parse_state_profiles(&state, game),
+            Err(e) => {
+                tracing::error!("Could not parse Vortex state: {}", e);
+                Vec::new()
+            }
+        },
+        Err(e) => {
+            tracing::info!("No Vortex state backup: {}", e);
+            Vec::new()
+        }
+    };
+    Ok(profiles)
+}
+
+/**
+ * Lists the profiles of a game with their names, read from Vortex's database or else from
+ * its last state backup. Profile folders missing from both are listed by id.
+ */
+pub fn get_profiles(game: &str) -> Result<Vec<VortexProfile>, Error> {
+    match read_vortex_state() {
+        Ok(state) => get_profiles_from_state(&state, game),
+        Err(e) => {
+            tracing::info!("Could not read Vortex database: {}", e);
+            get_profiles_from_state(&VortexState::default(), game)
+        }
+    }
+}
+
+/**
+ * Like get_profiles, with the database already read.
+ */
+pub fn get_profiles_from_state(
+    state: &VortexState,
+    game: &str,
+) -> Result<Vec<VortexProfile>, Error> {
+    let ids = get_profile_ids(game)?;
+    let mut profiles = state.get_profiles(game);
+    if profiles.is_empty() {
+        profiles = get_backup_profiles(game)?;
+    }
+    profiles.retain(|profile| ids.contains(&profile.id));
+    for id in ids {
+        if !profiles.iter().any(|profile| profile.id == id) {
+            profiles.push(VortexProfile {
+                name: id.clone(),
+                id,
+                game: game.to_string(),
+            });
+        }
+    }
+    Ok(profiles)
+}
+
+/**
+ * The plugins of a profile, enabled or not, in load order.
+ */
+pub fn get_profile_plugins(game: &str, profile_id: &str) -> Result<Vec<ProfilePlugin>, Error> {
+    read_profile_load_order(&get_profile_path(game, profile_id)?, game)
+}
+
+/**
+ * vortex.deployment.json, written by Vortex into every folder it deploys to. It lists each
+ * deployed file with the mod it came from.
+ */
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DeploymentManifest {
+    pub game_id: Option<String>,
+    pub staging_path: Option<String>,
+    pub target_path: Option<String>,
+    pub files: Vec<DeployedFile>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DeployedFile {
+    /**
+     * Path relative to the deployment target.
+     */
+    pub rel_path: String,
+    /**
+     * The id of the mod the file was deployed from, its staging folder name.
+     */
+    pub source: String,
+    /**
+     * Mods whose copies of the file were merged into this one.
+     */
+    pub merged: Vec<String>,
+}
+
+pub fn parse_deployment_manifest(contents: &str) -> Result<DeploymentManifest, Error> {
+    serde_json::from_str(contents).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+}
+
+/**
+ * Reads the manifests in a folder. Vortex writes one per mod type, e.g.
+ * vortex.deployment.json and vortex.deployment.dinput.json.
+ */
+pub fn read_deployment_manifests(folder: &std::path::Path) -> Vec<DeploymentManifest> {
+    let entries = match fs::read_dir(folder) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .map(|f| f.to_string_lossy().to_lowercase())
+                .is_some_and(|f| f.starts_with("vortex.deployment") && f.ends_with(".json"))
+        })
+        .filter_map(|path| {
+            match fs::read_to_string(&path).and_then(|c| parse_deployment_manifest(&c)) {
+                Ok(manifest) => Some(manifest),
+                Err(e) => {
+                    tracing::error!("Could not read deployment manifest {:?}: {}", path, e);
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/**
+ * Normalizes a deployed path so it can be looked up: lowercased, with forward slashes.
+ */
+pub fn normalize_deployed_path(path: &str) -> String {
+    path.replace('\\', "/").to_lowercase()
+}
+
+/**
+ * Maps every deployed file, as a normalized path, to the id of the mod that provides it.
+ */
+pub fn get_deployed_file_sources(manifests: &[DeploymentManifest]) -> HashMap<String, String> {
+    manifests
+        .iter()
+        .flat_map(|manifest| manifest.files.iter())
+        .map(|file| (normalize_deployed_path(&file.rel_path), file.source.clone()))
+        .collect()
+}
+
+pub fn parse_plugin(metadata: &PluginMetadata) -> Plugin {
+    Plugin {
+        name: metadata.name.clone(),
+        urls: metadata.url.iter().map(|url| url.link.clone()).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_masterlist_data_test() {
+        let plugins = get_masterlist_data("skyrimse");
+        println!("{:?}", plugins);
+    }
+
+    #[test]
+    fn get_profile_data_test() {
+        let p = get_profiles("skyrimse");
+        println!("String interpolation yay {:?}", p)
+    }
+
+    #[test]
+    fn deployment_manifest_maps_files_to_mods() {
+        let manifest = parse_deployment_manifest(
+            r#"{
+                "version": 1,
+                "instance": "r1Kz9a",
+                "deploymentMethod": "hardlink_activator",
+                "gameId": "skyrimse",
+                "stagingPath": "D:\\Vortex Mods\\skyrimse",
+                "targetPath": "C:\\Games\\Skyrim Special Edition\\Data",
+                "files": [
+                    { "relPath": "SkyUI_SE.esp", "source": "SkyUI-12604-5-2SE", "target": "", "time": 1 },
+                    { "relPath": "Scripts\\SKI_ConfigBase.pex", "source": "SkyUI-12604-5-2SE", "time": 1 },
+                    { "relPath": "SkyUI_SE.bsa", "source": "SkyUI-12604-5-2SE", "merged": ["Other"] }
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(manifest.game_id.as_deref(), Some("skyrimse"));
+        assert_eq!(manifest.files[2].merged, vec!["Other"]);
+
+        let sources = get_deployed_file_sources(&[manifest]);
+        assert_eq!(
+            sources
+                .get("scripts/ski_configbase.pex")
+                .map(|s| s.as_str()),
+            Some("SkyUI-12604-5-2SE")
+        );
+        assert!(sources.contains_key("skyui_se.esp"));
+    }
+
+    #[test]
+    fn profile_names_come_from_the_state() {
+        let state = serde_json::json!({
+            "persistent": {
+                "profiles": {
+                    "rJ8x2Kq": { "id": "rJ8x2Kq", "gameId": "skyrimse", "name": "Survival" },
+                    "Hk3pQ0a": { "id": "Hk3pQ0a", "gameId": "skyrimse", "name": "Default" },
+                    "Sy9fWx1": { "id": "Sy9fWx1", "gameId": "fallout4", "name": "Default" }
+                }
+            }
+        });
+        let profiles = parse_state_profiles(&state, "skyrimse");
+        let names: Vec<(&str, &str)> = profiles
+            .iter()
+            .map(|p| (p.id.as_str(), p.name.as_str()))
+            .collect();
+        assert_eq!(names, vec![("Hk3pQ0a", "Default"), ("rJ8x2Kq", "Survival")]);
+    }
+}
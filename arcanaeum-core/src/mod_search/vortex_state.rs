@@ -0,0 +1,359 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::Error;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::leveldb::read_database;
+
+use super::vortex_scanner::{get_vortex_path, VortexProfile};
+
+/**
+ * Vortex flattens its state into one LevelDB key per leaf, joining the path with this.
+ */
+const KEY_SEPARATOR: &str = "###";
+
+const PLUGIN_EXTENSIONS: [&str; 3] = ["esp", "esm", "esl"];
+
+/**
+ * A mod in Vortex's database, with what it knows about where it was downloaded from.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VortexMod {
+    pub id: String,
+    pub name: String,
+    pub version: Option<String>,
+    pub source: Option<String>,
+    pub nexus_mod_id: Option<u64>,
+    pub nexus_file_id: Option<u64>,
+    pub installation_path: String,
+}
+
+impl VortexMod {
+    pub fn nexus_url(&self, game: &str) -> Option<String> {
+        if self.source.as_deref().is_some_and(|s| s != "nexus") {
+            return None;
+        }
+        let domain = match game {
+            "skyrimse" => "skyrimspecialedition",
+            "skyrimvr" => "skyrimspecialedition",
+            other => other,
+        };
+        self.nexus_mod_id
+            .map(|id| format!("https://www.nexusmods.com/{}/mods/{}", domain, id))
+    }
+}
+
+/**
+ * The leaves of Vortex's state, keyed by their path.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct VortexState {
+    pub entries: BTreeMap<Vec<String>, Value>,
+}
+
+impl VortexState {
+    /**
+     * Values are stored as JSON. Anything that isn't is kept as a string.
+     */
+    pub fn from_database(database: BTreeMap<Vec<u8>, Vec<u8>>) -> VortexState {
+        let entries = database
+            .into_iter()
+            .map(|(key, value)| {
+                let key = String::from_utf8_lossy(&key)
+                    .split(KEY_SEPARATOR)
+                    .map(|part| part.to_string())
+                    .collect();
+                let value = serde_json::from_slice(&value)
+                    .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&value).to_string()));
+                (key, value)
+            })
+            .collect();
+        VortexState { entries }
+    }
+
+    pub fn get(&self, path: &[&str]) -> Option<&Value> {
+        let key: Vec<String> = path.iter().map(|p| p.to_string()).collect();
+        self.entries.get(&key)
+    }
+
+    /**
+     * The entries under a path, with the path taken off their keys.
+     */
+    pub fn children(&self, path: &[&str]) -> impl Iterator<Item = (&[String], &Value)> {
+        let prefix: Vec<String> = path.iter().map(|p| p.to_string()).collect();
+        let depth = prefix.len();
+        self.entries
+            .range(prefix.clone()..)
+            .take_while(move |(key, _)| key.starts_with(&prefix))
+            .map(move |(key, value)| (&key[depth..], value))
+    }
+
+    /**
+     * The distinct names directly under a path, e.g. the ids of the mods of a game.
+     */
+    fn child_names(&self, path: &[&str]) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .children(path)
+            .filter_map(|(key, _)| key.first().cloned())
+            .collect();
+        names.dedup();
+        names
+    }
+
+    pub fn get_profiles(&self, game: &str) -> Vec<VortexProfile> {
+        let mut profiles: Vec<VortexProfile> = self
+            .child_names(&["persistent", "profiles"])
+            .into_iter()
+            .filter_map(|id| {
+                let get = |field: &str| {
+                    self.get(&["persistent", "profiles", &id, field])
+                        .and_then(|v| v.as_str())
+                        .map(|v| v.to_string())
+                };
+                let profile_game = get("gameId")?;
+                Some(VortexProfile {
+                    name: get("name").unwrap_or_else(|| id.clone()),
+                    id,
+                    game: profile_game,
+                })
+            })
+            .filter(|profile| profile.game == game)
+            .collect();
+        profiles.sort_by(|a, b| a.name.cmp(&b.name));
+        profiles
+    }
+
+    pub fn get_mods(&self, game: &str) -> Vec<VortexMod> {
+        self.child_names(&["persistent", "mods", game])
+            .into_iter()
+            .map(|id| {
+                let mod_path = ["persistent", "mods", game, id.as_str()];
+                let attribute = |name: &str| {
+                    let mut path = mod_path.to_vec();
+                    path.extend(["attributes", name]);
+                    self.get(&path)
+                };
+                let text = |name: &str| {
+                    attribute(name).and_then(|v| match v {
+                        Value::String(s) => Some(s.clone()),
+                        Value::Number(n) => Some(n.to_string()),
+                        _ => None,
+                    })
+                };
+                let number = |name: &str| {
+                    attribute(name).and_then(|v| match v {
+                        Value::Number(n) => n.as_u64(),
+                        Value::String(s) => s.parse().ok(),
+                        _ => None,
+                    })
+                };
+
+                let mut installation_path = mod_path.to_vec();
+                installation_path.push("installationPath");
+                VortexMod {
+                    name: text("customFileName")
+                        .or_else(|| text("modName"))
+                        .or_else(|| text("logicalFileName"))
+                        .or_else(|| text("name"))
+                        .unwrap_or_else(|| id.clone()),
+                    version: text("version"),
+                    source: text("source"),
+                    nexus_mod_id: number("modId"),
+                    nexus_file_id: number("fileId"),
+                    installation_path: self
+                        .get(&installation_path)
+                        .and_then(|v| v.as_str())
+                        .unwrap_or(&id)
+                        .to_string(),
+                    id,
+                }
+            })
+            .collect()
+    }
+
+    /**
+     * Where Vortex keeps the installed mods before deploying them, {game} in the setting is
+     * replaced by the game id.
+     */
+    pub fn get_staging_path(&self, game: &str) -> Result<PathBuf, Error> {
+        match self
+            .get(&["settings", "mods", "installPath", game])
+            .and_then(|v| v.as_str())
+        {
+            Some(path) => Ok(PathBuf::from(path.replace("{game}", game))),
+            None => Ok(get_vortex_path()?.join(game).join("mods")),
+        }
+    }
+
+    /**
+     * The id of the profile last used for the game.
+     */
+    pub fn get_active_profile(&self, game: &str) -> Option<String> {
+        self.get(&["settings", "profiles", "lastActiveProfile", game])
+            .and_then(|v| v.as_str())
+            .map(|id| id.to_string())
+    }
+}
+
+fn get_state_path() -> Result<PathBuf, Error> {
+    Ok(get_vortex_path()?.join("state.v2"))
+}
+
+pub fn read_vortex_state() -> Result<VortexState, Error> {
+    Ok(VortexState::from_database(read_database(
+        &get_state_path()?
+    )?))
+}
+
+fn list_plugin_files(folder: &Path) -> Vec<String> {
+    let entries = match fs::read_dir(folder) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|name| {
+            Path::new(name)
+                .extension()
+                .is_some_and(|e| PLUGIN_EXTENSIONS.iter().any(|p| e.eq_ignore_ascii_case(p)))
+        })
+        .collect()
+}
+
+/**
+ * Maps plugin names, lowercased, to the mod that has them in its staging folder.
+ */
+pub fn get_plugin_mods(
+    mods: &[VortexMod],
+    list_plugins: impl Fn(&VortexMod) -> Vec<String>,
+) -> HashMap<String, VortexMod> {
+    let mut plugin_mods = HashMap::new();
+    for vortex_mod in mods {
+        for plugin in list_plugins(vortex_mod) {
+            plugin_mods.insert(plugin.to_lowercase(), vortex_mod.clone());
+        }
+    }
+    plugin_mods
+}
+
+pub fn get_plugin_mods_from_state(
+    state: &VortexState,
+    game: &str,
+) -> Result<HashMap<String, VortexMod>, Error> {
+    let staging_path = state.get_staging_path(game)?;
+    Ok(get_plugin_mods(&state.get_mods(game), |vortex_mod| {
+        list_plugin_files(&staging_path.join(&vortex_mod.installation_path))
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(entries: &[(&str, &str)]) -> VortexState {
+        VortexState::from_database(
+            entries
+                .iter()
+                .map(|(key, value)| (key.as_bytes().to_vec(), value.as_bytes().to_vec()))
+                .collect(),
+        )
+    }
+
+    fn sample_state() -> VortexState {
+        state(&[
+            ("persistent###profiles###rJ8x2Kq###gameId", "\"skyrimse\""),
+            ("persistent###profiles###rJ8x2Kq###name", "\"Survival\""),
+            ("persistent###profiles###Sy9fWx1###gameId", "\"fallout4\""),
+            (
+                "persistent###mods###skyrimse###SkyUI-12604-5-2SE###attributes###modId",
+                "12604",
+            ),
+            (
+                "persistent###mods###skyrimse###SkyUI-12604-5-2SE###attributes###fileId",
+                "\"35407\"",
+            ),
+            (
+                "persistent###mods###skyrimse###SkyUI-12604-5-2SE###attributes###modName",
+                "\"SkyUI\"",
+            ),
+            (
+                "persistent###mods###skyrimse###SkyUI-12604-5-2SE###attributes###version",
+                "\"5.2.0\"",
+            ),
+            (
+                "persistent###mods###skyrimse###SkyUI-12604-5-2SE###attributes###source",
+                "\"nexus\"",
+            ),
+            (
+                "persistent###mods###skyrimse###SkyUI-12604-5-2SE###installationPath",
+                "\"SkyUI-12604-5-2SE\"",
+            ),
+            (
+                "persistent###mods###skyrimse###MyPatch###installationPath",
+                "\"MyPatch\"",
+            ),
+            (
+                "settings###profiles###lastActiveProfile###skyrimse",
+                "\"rJ8x2Kq\"",
+            ),
+            (
+                "settings###mods###installPath###skyrimse",
+                "\"D:\\\\Vortex Mods\\\\{game}\"",
+            ),
+        ])
+    }
+
+    #[test]
+    fn profiles_are_read_for_one_game() {
+        let profiles = sample_state().get_profiles("skyrimse");
+        assert_eq!(profiles.len(), 1);
+        assert_eq!(profiles[0].name, "Survival");
+        assert_eq!(profiles[0].id, "rJ8x2Kq");
+        assert_eq!(
+            sample_state().get_active_profile("skyrimse").as_deref(),
+            Some("rJ8x2Kq")
+        );
+        assert_eq!(sample_state().get_active_profile("fallout4"), None);
+    }
+
+    #[test]
+    fn mods_have_their_nexus_ids() {
+        let mods = sample_state().get_mods("skyrimse");
+        assert_eq!(mods.len(), 2);
+
+        let skyui = mods.iter().find(|m| m.name == "SkyUI").unwrap();
+        assert_eq!(skyui.nexus_mod_id, Some(12604));
+        assert_eq!(skyui.nexus_file_id, Some(35407));
+        assert_eq!(skyui.version.as_deref(), Some("5.2.0"));
+        assert_eq!(
+            skyui.nexus_url("skyrimse").as_deref(),
+            Some("https://www.nexusmods.com/skyrimspecialedition/mods/12604")
+        );
+
+        let patch = mods.iter().find(|m| m.id == "MyPatch").unwrap();
+        assert_eq!(patch.name, "MyPatch");
+        assert_eq!(patch.nexus_url("skyrimse"), None);
+    }
+
+    #[test]
+    fn staging_path_fills_in_the_game() {
+        assert_eq!(
+            sample_state().get_staging_path("skyrimse").unwrap(),
+            PathBuf::from("D:\\Vortex Mods\\skyrimse")
+        );
+    }
+
+    #[test]
+    fn plugins_are_matched_to_mods() {
+        let mods = sample_state().get_mods("skyrimse");
+        let plugin_mods = get_plugin_mods(&mods, |m| match m.id.as_str() {
+            "SkyUI-12604-5-2SE" => vec!["SkyUI_SE.esp".to_string()],
+            _ => Vec::new(),
+        });
+        assert_eq!(plugin_mods.get("skyui_se.esp").unwrap().name, "SkyUI");
+    }
+}
@@ -0,0 +1,321 @@
+use std::fs::File;
+use std::io::{Error, ErrorKind, Read};
+use std::path::{Path, PathBuf};
+
+use encoding_rs::WINDOWS_1252;
+use serde::{Deserialize, Serialize};
+
+/**
+ * Record header size for Skyrim plugins: type, data size, flags, form id,
+ * version control info, form version and an unknown u16.
+ */
+const RECORD_HEADER_SIZE: usize = 24;
+
+/**
+ * TES4 records are mostly masters and overridden form ids, far below this even for the
+ * largest plugins. Anything bigger is not a plugin header.
+ */
+const MAX_HEADER_DATA_SIZE: usize = 16 * 1024 * 1024;
+
+pub const FLAG_MASTER: u32 = 0x1;
+pub const FLAG_LOCALIZED: u32 = 0x80;
+pub const FLAG_LIGHT: u32 = 0x200;
+
+/**
+ * The contents of the TES4 record at the start of every .esp, .esm and .esl file.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PluginHeader {
+    pub file_name: String,
+    pub flags: u32,
+    pub version: f32,
+    pub record_count: u32,
+    pub next_object_id: u32,
+    pub author: Option<String>,
+    pub description: Option<String>,
+    pub masters: Vec<String>,
+}
+
+impl PluginHeader {
+    fn has_extension(&self, extension: &str) -> bool {
+        Path::new(&self.file_name)
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case(extension))
+    }
+
+    /**
+     * .esm and .esl files are loaded as masters whether or not the flag is set.
+     */
+    pub fn is_master(&self) -> bool {
+        self.flags & FLAG_MASTER != 0 || self.has_extension("esm") || self.has_extension("esl")
+    }
+
+    pub fn is_light(&self) -> bool {
+        self.flags & FLAG_LIGHT != 0 || self.has_extension("esl")
+    }
+
+    pub fn is_localized(&self) -> bool {
+        self.flags & FLAG_LOCALIZED != 0
+    }
+}
+
+fn invalid_data(message: String) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+fn read_u32(buf: &[u8], start: usize) -> Result<u32, Error> {
+    buf.get(start..start + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| invalid_data(format!("Unexpected end of plugin at {start}")))
+}
+
+fn read_u16(buf: &[u8], start: usize) -> Result<u16, Error> {
+    buf.get(start..start + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .ok_or_else(|| invalid_data(format!("Unexpected end of plugin at {start}")))
+}
+
+/**
+ * Reads a null terminated string. Plugins are written in Windows-1252, as saves are, so
+ * master names decode the same as the plugin list of a save.
+ */
+fn read_zstring(data: &[u8]) -> String {
+    let end = data.iter().position(|b| *b == 0).unwrap_or(data.len());
+    let (string, _) = WINDOWS_1252.decode_without_bom_handling(&data[..end]);
+    string.to_string()
+}
+
+/**
+ * Parses the TES4 record from the start of a plugin file.
+ */
+pub fn parse_plugin_header(buf: &[u8], file_name: &str) -> Result<PluginHeader, Error> {
+    if buf.get(0..4) != Some(b"TES4".as_slice()) {
+        return Err(invalid_data(format!("{file_name} is not a Skyrim plugin")));
+    }
+    let data_size = read_u32(buf, 4)? as usize;
+    let flags = read_u32(buf, 8)?;
+
+    let mut header = PluginHeader {
+        file_name: file_name.to_string(),
+        flags,
+        version: 0.0,
+        record_count: 0,
+        next_object_id: 0,
+        author: None,
+        description: None,
+        masters: Vec::new(),
+    };
+
+    let end = RECORD_HEADER_SIZE + data_size;
+    let mut cursor = RECORD_HEADER_SIZE;
+    let mut next_size = None;
+    while cursor + 6 <= end {
+        let subrecord_type = buf
+            .get(cursor..cursor + 4)
+            .ok_or_else(|| invalid_data(format!("{file_name} has a truncated header")))?;
+        let size = match next_size.take() {
+            Some(size) => size,
+            None => read_u16(buf, cursor + 4)? as usize,
+        };
+        let data = buf
+            .get(cursor + 6..cursor + 6 + size)
+            .ok_or_else(|| invalid_data(format!("{file_name} has a truncated header")))?;
+
+        match subrecord_type {
+            b"HEDR" if size >= 12 => {
+                header.version = f32::from_le_bytes([data[0], data[1], data[2], data[3]]);
+                header.record_count = read_u32(data, 4)?;
+                header.next_object_id = read_u32(data, 8)?;
+            }
+            b"CNAM" => header.author = Some(read_zstring(data)),
+            b"SNAM" => header.description = Some(read_zstring(data)),
+            b"MAST" => header.masters.push(read_zstring(data)),
+            // XXXX holds the size of the next subrecord when it doesn't fit in a u16
+            b"XXXX" if size >= 4 => next_size = Some(read_u32(data, 0)? as usize),
+            _ => {}
+        }
+        cursor += 6 + size;
+    }
+
+    Ok(header)
+}
+
+/**
+ * Reads only the TES4 record of a plugin, without loading the rest of the file.
+ */
+pub fn read_plugin_header(path: &Path) -> Result<PluginHeader, Error> {
+    let file_name = path
+        .file_name()
+        .map(|f| f.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let mut file = File::open(path)?;
+
+    let mut buf = vec![0; RECORD_HEADER_SIZE];
+    file.read_exact(&mut buf)?;
+    if buf[0..4] != *b"TES4" {
+        return Err(invalid_data(format!("{file_name} is not a Skyrim plugin")));
+    }
+    let data_size = read_u32(&buf, 4)? as usize;
+    if data_size > MAX_HEADER_DATA_SIZE {
+        return Err(invalid_data(format!(
+            "{file_name} has a {data_size} byte header, which is too large for a plugin"
+        )));
+    }
+    buf.resize(RECORD_HEADER_SIZE + data_size, 0);
+    file.read_exact(&mut buf[RECORD_HEADER_SIZE..])?;
+
+    parse_plugin_header(&buf, &file_name)
+}
+
+/**
+ * Finds a plugin in the Data folder. Windows file names are case insensitive, so fall back
+ * to comparing names without case when the exact name doesn't exist.
+ */
+pub fn find_plugin_file(data_path: &Path, name: &str) -> Option<PathBuf> {
+    let exact = data_path.join(name);
+    if exact.exists() {
+        return Some(exact);
+    }
+    std::fs::read_dir(data_path)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .find(|entry| {
+            entry
+                .file_name()
+                .to_string_lossy()
+                .eq_ignore_ascii_case(name)
+        })
+        .map(|entry| entry.path())
+}
+
+pub fn read_plugin_header_from_data_folder(
+    data_path: &Path,
+    name: &str,
+) -> Result<PluginHeader, Error> {
+    match find_plugin_file(data_path, name) {
+        Some(path) => read_plugin_header(&path),
+        None => Err(Error::new(
+            ErrorKind::NotFound,
+            format!("{name} is not in {}", data_path.to_string_lossy()),
+        )),
+    }
+}
+
+#[cfg(test)]
+pub mod test_data {
+    use encoding_rs::WINDOWS_1252;
+
+    /**
+     * Builds the TES4 record of a plugin with the given flags and masters, which are
+     * written in Windows-1252 like the game does.
+     */
+    pub fn plugin_bytes(flags: u32, masters: &[&str]) -> Vec<u8> {
+        let mut subrecords = Vec::new();
+        let mut push_subrecord = |subrecord_type: &[u8], data: &[u8]| {
+            subrecords.extend_from_slice(subrecord_type);
+            subrecords.extend_from_slice(&(data.len() as u16).to_le_bytes());
+            subrecords.extend_from_slice(data);
+        };
+
+        let mut hedr = Vec::new();
+        hedr.extend_from_slice(&1.7f32.to_le_bytes());
+        hedr.extend_from_slice(&42u32.to_le_bytes());
+        hedr.extend_from_slice(&0x800u32.to_le_bytes());
+        push_subrecord(b"HEDR", &hedr);
+        push_subrecord(b"CNAM", b"Arthmoor\0");
+        push_subrecord(b"SNAM", b"Fixes things\0");
+        for master in masters {
+            push_subrecord(b"MAST", &WINDOWS_1252.encode(&format!("{master}\0")).0);
+            push_subrecord(b"DATA", &0u64.to_le_bytes());
+        }
+
+        let mut buf = Vec::new();
+        buf.extend_from_slice(b"TES4");
+        buf.extend_from_slice(&(subrecords.len() as u32).to_le_bytes());
+        buf.extend_from_slice(&flags.to_le_bytes());
+        buf.extend_from_slice(&[0; 12]);
+        buf.extend_from_slice(&subrecords);
+        // The first group of records follows the header
+        buf.extend_from_slice(b"GRUP");
+        buf
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_data::plugin_bytes;
+    use super::*;
+
+    #[test]
+    fn parse_plugin_header_reads_masters_and_metadata() {
+        let buf = plugin_bytes(FLAG_MASTER | FLAG_LOCALIZED, &["Skyrim.esm", "Update.esm"]);
+        let header =
+            parse_plugin_header(&buf, "Unofficial Skyrim Special Edition Patch.esp").unwrap();
+
+        assert_eq!(header.masters, vec!["Skyrim.esm", "Update.esm"]);
+        assert_eq!(header.author.as_deref(), Some("Arthmoor"));
+        assert_eq!(header.description.as_deref(), Some("Fixes things"));
+        assert_eq!(header.version, 1.7);
+        assert_eq!(header.record_count, 42);
+        assert!(header.is_master());
+        assert!(header.is_localized());
+        assert!(!header.is_light());
+    }
+
+    #[test]
+    fn masters_are_read_as_windows_1252() {
+        let buf = plugin_bytes(0, &["Café.esp"]);
+        assert!(buf.windows(9).any(|w| w == b"Caf\xe9.esp\0"));
+        let header = parse_plugin_header(&buf, "Patch.esp").unwrap();
+        assert_eq!(header.masters, vec!["Café.esp"]);
+    }
+
+    #[test]
+    fn esl_extension_makes_a_light_master() {
+        let header = parse_plugin_header(&plugin_bytes(0, &[]), "ccBGSSSE001-Fish.esl").unwrap();
+        assert!(header.is_light());
+        assert!(header.is_master());
+    }
+
+    #[test]
+    fn other_files_are_rejected() {
+        assert!(parse_plugin_header(b"BSA\0", "Skyrim - Textures0.bsa").is_err());
+        assert!(parse_plugin_header(b"TES4", "Truncated.esp").is_err());
+    }
+
+    #[test]
+    fn truncated_headers_are_errors() {
+        let buf = plugin_bytes(0, &["Skyrim.esm"]);
+        for length in [
+            RECORD_HEADER_SIZE + 3,
+            RECORD_HEADER_SIZE + 8,
+            buf.len() - 12,
+        ] {
+            assert_eq!(
+                parse_plugin_header(&buf[..length], "Truncated.esp")
+                    .unwrap_err()
+                    .kind(),
+                ErrorKind::InvalidData
+            );
+        }
+    }
+
+    #[test]
+    fn oversized_headers_are_not_read() {
+        let path = std::env::temp_dir().join(format!("arcanaeum-huge-{}.esp", std::process::id()));
+        let mut buf = plugin_bytes(0, &[]);
+        buf[4..8].copy_from_slice(&u32::MAX.to_le_bytes());
+        std::fs::write(&path, &buf).unwrap();
+        assert_eq!(
+            read_plugin_header(&path).unwrap_err().kind(),
+            ErrorKind::InvalidData
+        );
+
+        std::fs::write(&path, b"GRUP\xff\xff\xff\xff0000000000000000").unwrap();
+        assert_eq!(
+            read_plugin_header(&path).unwrap_err().kind(),
+            ErrorKind::InvalidData
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+}
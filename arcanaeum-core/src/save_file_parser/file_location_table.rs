@@ -0,0 +1,81 @@
+use serde::{Deserialize, Serialize};
+
+use super::utils::read_u32;
+
+/**
+ * Size in bytes of the file location table: 10 fields followed by 15 unused u32 values.
+ */
+pub const FILE_LOCATION_TABLE_SIZE: usize = 100;
+
+/**
+ * The offsets in this table are absolute offsets into the save file, as if it were
+ * uncompressed. They need to be moved whenever anything before them changes size.
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileLocationTable {
+    pub form_id_array_count_offset: u32,
+    pub unknown_table_3_offset: u32,
+    pub global_data_table_1_offset: u32,
+    pub global_data_table_2_offset: u32,
+    pub change_forms_offset: u32,
+    pub global_data_table_3_offset: u32,
+    pub global_data_table_1_count: u32,
+    pub global_data_table_2_count: u32,
+    pub global_data_table_3_count: u32,
+    pub change_form_count: u32,
+    pub unused: [u32; 15],
+}
+
+impl FileLocationTable {
+    /**
+     * Returns a copy of the table with every offset moved by `shift` bytes.
+     */
+    pub fn shifted(&self, shift: i64) -> FileLocationTable {
+        let move_offset = |offset: u32| (offset as i64 + shift) as u32;
+        FileLocationTable {
+            form_id_array_count_offset: move_offset(self.form_id_array_count_offset),
+            unknown_table_3_offset: move_offset(self.unknown_table_3_offset),
+            global_data_table_1_offset: move_offset(self.global_data_table_1_offset),
+            global_data_table_2_offset: move_offset(self.global_data_table_2_offset),
+            change_forms_offset: move_offset(self.change_forms_offset),
+            global_data_table_3_offset: move_offset(self.global_data_table_3_offset),
+            ..self.clone()
+        }
+    }
+}
+
+pub fn read_file_location_table(buf: &[u8], start: usize) -> (FileLocationTable, usize) {
+    let (form_id_array_count_offset, cursor) = read_u32(buf, start);
+    let (unknown_table_3_offset, cursor) = read_u32(buf, cursor);
+    let (global_data_table_1_offset, cursor) = read_u32(buf, cursor);
+    let (global_data_table_2_offset, cursor) = read_u32(buf, cursor);
+    let (change_forms_offset, cursor) = read_u32(buf, cursor);
+    let (global_data_table_3_offset, cursor) = read_u32(buf, cursor);
+    let (global_data_table_1_count, cursor) = read_u32(buf, cursor);
+    let (global_data_table_2_count, cursor) = read_u32(buf, cursor);
+    let (global_data_table_3_count, cursor) = read_u32(buf, cursor);
+    let (change_form_count, cursor) = read_u32(buf, cursor);
+
+    let mut unused = [0; 15];
+    let mut index_cursor = cursor;
+    for value in unused.iter_mut() {
+        let (n, cursor) = read_u32(buf, index_cursor);
+        *value = n;
+        index_cursor = cursor;
+    }
+
+    let table = FileLocationTable {
+        form_id_array_count_offset,
+        unknown_table_3_offset,
+        global_data_table_1_offset,
+        global_data_table_2_offset,
+        change_forms_offset,
+        global_data_table_3_offset,
+        global_data_table_1_count,
+        global_data_table_2_count,
+        global_data_table_3_count,
+        change_form_count,
+        unused,
+    };
+    (table, index_cursor)
+}
@@ -0,0 +1,69 @@
+use super::utils::read_u32;
+
+/**
+ * Forms created in game (0xFF) and light plugin forms (0xFE) use the top byte as a marker
+ * instead of a plugin index.
+ */
+pub const CREATED_FORM_INDEX: u32 = 0xFF;
+pub const LIGHT_PLUGIN_INDEX: u32 = 0xFE;
+
+pub fn read_form_id_array(buf: &[u8], start: usize) -> (Vec<u32>, usize) {
+    let (count, cursor) = read_u32(buf, start);
+
+    let mut form_ids = Vec::new();
+    let mut index_cursor = cursor;
+    for _ in 0..count {
+        let (form_id, cursor) = read_u32(buf, index_cursor);
+        form_ids.push(form_id);
+        index_cursor = cursor;
+    }
+    (form_ids, index_cursor)
+}
+
+/**
+ * Index of the regular plugin a form id belongs to, if it belongs to one.
+ */
+pub fn get_plugin_index(form_id: u32) -> Option<usize> {
+    let index = form_id >> 24;
+    match index {
+        LIGHT_PLUGIN_INDEX | CREATED_FORM_INDEX => None,
+        _ => Some(index as usize),
+    }
+}
+
+/**
+ * Index of the light plugin a form id belongs to, if it belongs to one.
+ */
+pub fn get_light_plugin_index(form_id: u32) -> Option<usize> {
+    match form_id >> 24 {
+        LIGHT_PLUGIN_INDEX => Some(((form_id >> 12) & 0xFFF) as usize),
+        _ => None,
+    }
+}
+
+pub fn with_plugin_index(form_id: u32, index: usize) -> u32 {
+    ((index as u32) << 24) | (form_id & 0x00FF_FFFF)
+}
+
+pub fn with_light_plugin_index(form_id: u32, index: usize) -> u32 {
+    (LIGHT_PLUGIN_INDEX << 24) | ((index as u32 & 0xFFF) << 12) | (form_id & 0xFFF)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn regular_plugin_index_comes_from_top_byte() {
+        assert_eq!(get_plugin_index(0x0301_2345), Some(3));
+        assert_eq!(get_plugin_index(0xFF00_0801), None);
+        assert_eq!(with_plugin_index(0x0301_2345, 5), 0x0501_2345);
+    }
+
+    #[test]
+    fn light_plugin_index_is_twelve_bits() {
+        assert_eq!(get_light_plugin_index(0xFE00_2801), Some(2));
+        assert_eq!(get_plugin_index(0xFE00_2801), None);
+        assert_eq!(with_light_plugin_index(0xFE00_2801, 0x10), 0xFE01_0801);
+    }
+}
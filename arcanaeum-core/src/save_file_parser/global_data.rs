@@ -0,0 +1,236 @@
+use std::io::{Error, ErrorKind};
+
+use serde::{Deserialize, Serialize};
+
+use super::file_location_table::FileLocationTable;
+
+/**
+ * An entry of a global data table, like the player location (type 1) or the weather
+ * (type 4). The data of each type has its own layout, which is kept as is.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GlobalDataEntry {
+    pub data_type: u32,
+    #[serde(with = "super::hex")]
+    pub data: Vec<u8>,
+}
+
+/**
+ * What the game saved about a form that changed during play.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChangeForm {
+    /**
+     * A 3 byte RefID. The top two bits tell what the rest is: an index into the form id
+     * array (0), a form of Skyrim.esm (1) or a form created in game (2).
+     */
+    pub form_id: u32,
+    pub change_flags: u32,
+
+    /**
+     * The lower 6 bits of the type byte.
+     */
+    pub form_type: u8,
+
+    /**
+     * The upper 2 bits of the type byte, telling whether the lengths are stored as
+     * a u8 (0), u16 (1) or u32 (2).
+     */
+    pub length_size: u8,
+    pub version: u8,
+
+    /**
+     * The size of the data once inflated with zlib, 0 when it isn't compressed.
+     */
+    pub uncompressed_length: u32,
+    #[serde(with = "super::hex")]
+    pub data: Vec<u8>,
+}
+
+/**
+ * The tables between the file location table and the form id array, in the order they
+ * are stored.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GlobalDataTables {
+    pub table_1: Vec<GlobalDataEntry>,
+    pub table_2: Vec<GlobalDataEntry>,
+    pub change_forms: Vec<ChangeForm>,
+    pub table_3: Vec<GlobalDataEntry>,
+
+    /**
+     * Skyrim writes more entries to the third table than it counts in the file location
+     * table, so the count is kept relative to the entries.
+     */
+    pub uncounted_table_3_entries: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum GlobalData {
+    Tables(GlobalDataTables),
+
+    /**
+     * The data as is, for saves whose tables don't match their file location table, so
+     * that they can still be written back.
+     */
+    Raw(#[serde(with = "super::hex")] Vec<u8>),
+}
+
+fn invalid_data(message: String) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+/**
+ * Reads from a slice without ever going past its end.
+ */
+struct Reader<'a> {
+    buf: &'a [u8],
+    cursor: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, length: usize) -> Result<&'a [u8], Error> {
+        let bytes = self
+            .cursor
+            .checked_add(length)
+            .and_then(|end| self.buf.get(self.cursor..end))
+            .ok_or_else(|| {
+                invalid_data(format!(
+                    "{} bytes at {} run past the global data",
+                    length, self.cursor
+                ))
+            })?;
+        self.cursor += length;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, Error> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, Error> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn length(&mut self, length_size: u8) -> Result<u32, Error> {
+        let bytes = match length_size {
+            0 => self.take(1)?,
+            1 => self.take(2)?,
+            2 => self.take(4)?,
+            _ => {
+                return Err(invalid_data(format!(
+                    "Unknown change form length size {} at {}",
+                    length_size, self.cursor
+                )))
+            }
+        };
+        Ok(bytes
+            .iter()
+            .rev()
+            .fold(0, |length, byte| (length << 8) | *byte as u32))
+    }
+}
+
+fn read_entry(reader: &mut Reader) -> Result<GlobalDataEntry, Error> {
+    let data_type = reader.u32()?;
+    let length = reader.u32()?;
+    let data = reader.take(length as usize)?.to_vec();
+    Ok(GlobalDataEntry { data_type, data })
+}
+
+fn read_entries(reader: &mut Reader, count: u32) -> Result<Vec<GlobalDataEntry>, Error> {
+    (0..count).map(|_| read_entry(reader)).collect()
+}
+
+fn read_change_form(reader: &mut Reader) -> Result<ChangeForm, Error> {
+    let form_id = reader
+        .take(3)?
+        .iter()
+        .fold(0, |form_id, byte| (form_id << 8) | *byte as u32);
+    let change_flags = reader.u32()?;
+    let type_byte = reader.u8()?;
+    let length_size = type_byte >> 6;
+    let version = reader.u8()?;
+    let length = reader.length(length_size)?;
+    let uncompressed_length = reader.length(length_size)?;
+    let data = reader.take(length as usize)?.to_vec();
+    Ok(ChangeForm {
+        form_id,
+        change_flags,
+        form_type: type_byte & 0x3F,
+        length_size,
+        version,
+        uncompressed_length,
+        data,
+    })
+}
+
+fn read_tables(buf: &[u8], table: &FileLocationTable) -> Result<GlobalDataTables, Error> {
+    let mut reader = Reader { buf, cursor: 0 };
+    let expect_table = |reader: &Reader, name: &str, offset: u32| {
+        let expected = offset.checked_sub(table.global_data_table_1_offset);
+        match expected == Some(reader.cursor as u32) {
+            true => Ok(()),
+            false => Err(invalid_data(format!(
+                "The {} should start at {}, not {}",
+                name, offset, reader.cursor
+            ))),
+        }
+    };
+
+    let table_1 = read_entries(&mut reader, table.global_data_table_1_count)?;
+    expect_table(
+        &reader,
+        "second global data table",
+        table.global_data_table_2_offset,
+    )?;
+    let table_2 = read_entries(&mut reader, table.global_data_table_2_count)?;
+    expect_table(&reader, "change forms", table.change_forms_offset)?;
+    let change_forms = (0..table.change_form_count)
+        .map(|_| read_change_form(&mut reader))
+        .collect::<Result<Vec<_>, Error>>()?;
+    expect_table(
+        &reader,
+        "third global data table",
+        table.global_data_table_3_offset,
+    )?;
+    let mut table_3 = Vec::new();
+    while reader.cursor < buf.len() {
+        table_3.push(read_entry(&mut reader)?);
+    }
+
+    let uncounted_table_3_entries =
+        (table_3.len() as u32).saturating_sub(table.global_data_table_3_count);
+    Ok(GlobalDataTables {
+        table_1,
+        table_2,
+        change_forms,
+        table_3,
+        uncounted_table_3_entries,
+    })
+}
+
+/**
+ * Reads the global data tables and change forms, which span from where the file location
+ * table puts the first table up to the form id array.
+ */
+pub fn read_global_data(
+    buf: &[u8],
+    start: usize,
+    table: &FileLocationTable,
+) -> (GlobalData, usize) {
+    let size = table
+        .form_id_array_count_offset
+        .saturating_sub(table.global_data_table_1_offset) as usize;
+    let end = start.saturating_add(size).min(buf.len());
+    let data = &buf[start.min(end)..end];
+    let global_data = match read_tables(data, table) {
+        Ok(tables) => GlobalData::Tables(tables),
+        Err(e) => {
+            tracing::error!("Keeping the global data as is: {}", e);
+            GlobalData::Raw(data.to_vec())
+        }
+    };
+    (global_data, end)
+}
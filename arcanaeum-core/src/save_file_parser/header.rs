@@ -1,8 +1,10 @@
 use std::fmt::{self, Display};
 
+use serde::{Deserialize, Serialize};
+
 use super::utils::{read_f32, read_u16, read_u32, read_w_string};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Sex {
     Male,
     Female,
@@ -25,7 +27,7 @@ impl Display for Sex {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileTime {
     pub dw_low: u32,
     pub dw_high: u32,
@@ -38,7 +40,7 @@ pub fn read_filetime(buf: &[u8], start: usize) -> (FileTime, usize) {
     (ft, cursor)
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Header {
     pub version: u32,
     pub save_number: u32,
@@ -0,0 +1,43 @@
+//! Serializes byte blobs as a hex string, so that JSON exports stay a readable size.
+
+use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    let hex: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+    serializer.serialize_str(&hex)
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+    let hex = String::deserialize(deserializer)?;
+    if hex.len() % 2 != 0 {
+        return Err(D::Error::custom("hex data has an odd number of digits"));
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            hex.get(i..i + 2)
+                .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+                .ok_or_else(|| D::Error::custom(format!("invalid hex data at {}", i)))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Blob(#[serde(with = "super")] Vec<u8>);
+
+    #[test]
+    fn bytes_are_a_hex_string() {
+        let json = serde_json::to_string(&Blob(vec![0, 0x1f, 0xff])).unwrap();
+        assert_eq!(json, "\"001fff\"");
+        assert_eq!(
+            serde_json::from_str::<Blob>(&json).unwrap(),
+            Blob(vec![0, 0x1f, 0xff])
+        );
+        assert!(serde_json::from_str::<Blob>("\"0g\"").is_err());
+        assert!(serde_json::from_str::<Blob>("\"abc\"").is_err());
+    }
+}
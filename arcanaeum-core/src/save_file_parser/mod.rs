@@ -1,33 +1,64 @@
 use lz4_flex::decompress;
+use serde::{Deserialize, Serialize};
 use std::fmt::{self, Formatter};
 
 use crate::save_file_parser::{
+    file_location_table::read_file_location_table,
+    form_id_array::read_form_id_array,
+    global_data::read_global_data,
     header::read_header,
-    plugin_info::read_plugin_info,
+    plugin_info::{has_light_plugins, read_plugin_info},
     utils::{read_bytes, read_charray, read_u32, read_u8, se_only},
 };
 
-use self::{header::Header, plugin_info::PluginInfo};
+use self::{
+    file_location_table::FileLocationTable, global_data::GlobalData, header::Header,
+    plugin_info::PluginInfo,
+};
 
+pub mod file_location_table;
+pub mod form_id_array;
+pub mod global_data;
 pub mod header;
-mod plugin_info;
-mod utils;
+mod hex;
+pub mod plugin_info;
+pub(crate) mod utils;
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct SaveInfo {
     pub magic_string: String,
     pub header_size: u32,
     pub header: Header,
+    #[serde(with = "hex")]
     pub screenshot_data: Vec<u8>,
     pub uncompressed_length: u32,
     pub compressed_length: u32,
     pub form_version: u8,
     pub plugin_info_size: u32,
     pub plugin_info: PluginInfo,
+    pub file_location_table: FileLocationTable,
+
+    /**
+     * The global data tables and change forms between the file location table and the
+     * form id array.
+     */
+    pub global_data: GlobalData,
+    pub form_id_array: Vec<u32>,
+    pub visited_worldspace_array: Vec<u32>,
+
+    /**
+     * Unknown table 3, which follows the visited worldspaces, kept as is.
+     */
+    #[serde(with = "hex")]
+    pub remaining_data: Vec<u8>,
 }
 
 impl fmt::Debug for SaveInfo {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let global_data = match &self.global_data {
+            GlobalData::Tables(tables) => format!("{} change forms", tables.change_forms.len()),
+            GlobalData::Raw(data) => format!("{} unread bytes", data.len()),
+        };
         write!(
             f,
             "magic_string: {:?} \n\
@@ -38,6 +69,11 @@ impl fmt::Debug for SaveInfo {
             form_version: {:?} \n\
             plugin_info_size: {:?} \n\
             plugin_info: {:?} \n\
+            file_location_table: {:?} \n\
+            global_data: {} \n\
+            form_id_array_count: {:?} \n\
+            visited_worldspace_count: {:?} \n\
+            remaining_data_size: {:?} \n\
             ",
             self.magic_string,
             self.header_size,
@@ -47,6 +83,11 @@ impl fmt::Debug for SaveInfo {
             self.form_version,
             self.plugin_info_size,
             self.plugin_info,
+            self.file_location_table,
+            global_data,
+            self.form_id_array.len(),
+            self.visited_worldspace_array.len(),
+            self.remaining_data.len(),
         )
     }
 }
@@ -126,9 +167,18 @@ pub fn parse(buf: Vec<u8>) -> SaveInfo {
 
     let (form_version, cursor) = read_u8(buf, 0); //we need to start the cursor from 0 again
     let (plugin_info_size, cursor) = read_u32(buf, cursor);
-    let (plugin_info, cursor) = read_plugin_info(buf, cursor);
-
-    println!("Cursor position at {:?}", cursor);
+    let light_plugins = has_light_plugins(header.is_se, form_version);
+    let (plugin_info, _) = read_plugin_info(buf, cursor, light_plugins);
+    let cursor = cursor + plugin_info_size as usize;
+    let (file_location_table, cursor) = read_file_location_table(buf, cursor);
+
+    // The global data tables start right after the file location table, which lets us
+    // turn the absolute offsets of the table into positions in the body.
+    let (global_data, cursor) = read_global_data(buf, cursor, &file_location_table);
+    let (form_id_array, cursor) = read_form_id_array(buf, cursor);
+    let (visited_worldspace_array, cursor) = read_form_id_array(buf, cursor);
+
+    tracing::info!("Cursor position at {:?}", cursor);
     SaveInfo {
         magic_string,
         header_size,
@@ -139,6 +189,11 @@ pub fn parse(buf: Vec<u8>) -> SaveInfo {
         form_version,
         plugin_info_size,
         plugin_info,
+        file_location_table,
+        global_data,
+        form_id_array,
+        visited_worldspace_array,
+        remaining_data: buf[cursor..buf.len()].to_vec(),
     }
 }
 
@@ -0,0 +1,58 @@
+use serde::{Deserialize, Serialize};
+
+use super::utils::{read_u16, read_u8, read_w_string};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginInfo {
+    pub plugin_count: u8,
+    pub plugins: Vec<String>,
+
+    /**
+     * (SE only, form version 78 and up)
+     * Light plugins (.esl and ESL flagged plugins) are stored in their own list
+     * after the regular plugins.
+     */
+    pub light_plugin_count: u16,
+    pub light_plugins: Vec<String>,
+}
+
+/**
+ * Light plugins were added to the save format with form version 78 of Skyrim SE.
+ */
+pub fn has_light_plugins(is_se: bool, form_version: u8) -> bool {
+    is_se && form_version >= 78
+}
+
+fn read_plugin_names(buf: &[u8], start: usize, count: usize) -> (Vec<String>, usize) {
+    let mut plugins = Vec::new();
+    let mut index_cursor = start;
+    for _ in 0..count {
+        let (str, cursor) = read_w_string(buf, index_cursor);
+        plugins.push(str);
+        index_cursor = cursor;
+    }
+    (plugins, index_cursor)
+}
+
+pub fn read_plugin_info(buf: &[u8], start: usize, light_plugins: bool) -> (PluginInfo, usize) {
+    let (plugin_count, cursor) = read_u8(buf, start);
+    let (plugins, cursor) = read_plugin_names(buf, cursor, plugin_count as usize);
+
+    let (light_plugin_count, light_plugins, cursor) = match light_plugins {
+        true => {
+            let (light_plugin_count, cursor) = read_u16(buf, cursor);
+            let (light_plugins, cursor) =
+                read_plugin_names(buf, cursor, light_plugin_count as usize);
+            (light_plugin_count, light_plugins, cursor)
+        }
+        false => (0, Vec::new(), cursor),
+    };
+
+    let plugin_info = PluginInfo {
+        plugin_count,
+        plugins,
+        light_plugin_count,
+        light_plugins,
+    };
+    (plugin_info, cursor)
+}
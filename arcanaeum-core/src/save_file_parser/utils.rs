@@ -1,3 +1,5 @@
+use encoding_rs::WINDOWS_1252;
+
 pub fn read_charray(buf: &[u8], start: usize, end: usize) -> (String, usize) {
     let chunk = &buf[start..end];
     let s = match std::str::from_utf8(chunk) {
@@ -71,18 +73,16 @@ pub fn read_u8(buf: &[u8], start: usize) -> (u8, usize) {
     (n, end)
 }
 
+/**
+ * Strings in saves are Windows-1252. Every byte decodes to a character, so strings that
+ * aren't edited are written back byte for byte.
+ */
 pub fn read_w_string(buf: &[u8], start: usize) -> (String, usize) {
     let (length, start) = read_u16(buf, start);
     let end = start + length as usize;
     let chunk = &buf[start..end];
-    let str = match std::str::from_utf8(chunk) {
-        Ok(s) => s.to_string(),
-        Err(e) => {
-            tracing::error!("Error parsing string: {:?}", e);
-            "".to_string()
-        }
-    };
-    (str, end)
+    let (str, _) = WINDOWS_1252.decode_without_bom_handling(chunk);
+    (str.into_owned(), end)
 }
 
 pub fn read_bytes(buf: &[u8], start: usize, bytes: usize) -> (&[u8], usize) {
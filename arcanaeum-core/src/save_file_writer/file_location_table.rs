@@ -0,0 +1,19 @@
+use crate::save_file_parser::file_location_table::FileLocationTable;
+
+use super::utils::write_u32;
+
+pub fn write_file_location_table(buf: &mut Vec<u8>, table: &FileLocationTable) {
+    write_u32(buf, table.form_id_array_count_offset);
+    write_u32(buf, table.unknown_table_3_offset);
+    write_u32(buf, table.global_data_table_1_offset);
+    write_u32(buf, table.global_data_table_2_offset);
+    write_u32(buf, table.change_forms_offset);
+    write_u32(buf, table.global_data_table_3_offset);
+    write_u32(buf, table.global_data_table_1_count);
+    write_u32(buf, table.global_data_table_2_count);
+    write_u32(buf, table.global_data_table_3_count);
+    write_u32(buf, table.change_form_count);
+    for value in table.unused {
+        write_u32(buf, value);
+    }
+}
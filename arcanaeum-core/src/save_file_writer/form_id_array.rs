@@ -0,0 +1,8 @@
+use super::utils::write_u32;
+
+pub fn write_form_id_array(buf: &mut Vec<u8>, form_ids: &[u32]) {
+    write_u32(buf, form_ids.len() as u32);
+    for form_id in form_ids {
+        write_u32(buf, *form_id);
+    }
+}
@@ -0,0 +1,133 @@
+use std::io::{Error, ErrorKind};
+
+use crate::save_file_parser::global_data::{ChangeForm, GlobalData, GlobalDataEntry};
+
+use super::utils::{write_bytes, write_u32, write_u8};
+
+/**
+ * Where the tables after the first one start, relative to the first one.
+ */
+pub struct TableStarts {
+    pub table_2: usize,
+    pub change_forms: usize,
+    pub table_3: usize,
+}
+
+fn invalid_data(message: String) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+fn write_entry(buf: &mut Vec<u8>, entry: &GlobalDataEntry) -> Result<(), Error> {
+    let length = u32::try_from(entry.data.len()).map_err(|_| {
+        invalid_data(format!(
+            "Global data of type {} is too long",
+            entry.data_type
+        ))
+    })?;
+    write_u32(buf, entry.data_type);
+    write_u32(buf, length);
+    write_bytes(buf, &entry.data);
+    Ok(())
+}
+
+fn write_length(buf: &mut Vec<u8>, length: u32, length_size: u8) {
+    let bytes = length.to_le_bytes();
+    match length_size {
+        0 => write_bytes(buf, &bytes[..1]),
+        1 => write_bytes(buf, &bytes[..2]),
+        _ => write_bytes(buf, &bytes),
+    }
+}
+
+/**
+ * Checks the fields the format stores in fewer bits than they are kept in, as an
+ * imported JSON file could have anything in them.
+ */
+fn validate_change_form(change_form: &ChangeForm) -> Result<(), Error> {
+    let form_id = change_form.form_id;
+    if form_id > 0xFF_FFFF {
+        return Err(invalid_data(format!(
+            "Change form {:06X} does not fit in a 3 byte RefID",
+            form_id
+        )));
+    }
+    if change_form.form_type > 0x3F {
+        return Err(invalid_data(format!(
+            "Change form {:06X} has type {}, which is more than 63",
+            form_id, change_form.form_type
+        )));
+    }
+    let max_length = match change_form.length_size {
+        0 => u8::MAX as usize,
+        1 => u16::MAX as usize,
+        2 => u32::MAX as usize,
+        length_size => {
+            return Err(invalid_data(format!(
+                "Change form {:06X} has length size {}, which is not 0, 1 or 2",
+                form_id, length_size
+            )))
+        }
+    };
+    if change_form.data.len() > max_length || change_form.uncompressed_length as usize > max_length
+    {
+        return Err(invalid_data(format!(
+            "The data of change form {:06X} is too long for length size {}",
+            form_id, change_form.length_size
+        )));
+    }
+    Ok(())
+}
+
+fn write_change_form(buf: &mut Vec<u8>, change_form: &ChangeForm) -> Result<(), Error> {
+    validate_change_form(change_form)?;
+    write_bytes(buf, &change_form.form_id.to_be_bytes()[1..]);
+    write_u32(buf, change_form.change_flags);
+    write_u8(buf, (change_form.length_size << 6) | change_form.form_type);
+    write_u8(buf, change_form.version);
+    write_length(buf, change_form.data.len() as u32, change_form.length_size);
+    write_length(
+        buf,
+        change_form.uncompressed_length,
+        change_form.length_size,
+    );
+    write_bytes(buf, &change_form.data);
+    Ok(())
+}
+
+/**
+ * Writes the global data, returning where its tables start when it was read as tables.
+ */
+pub fn write_global_data(
+    buf: &mut Vec<u8>,
+    global_data: &GlobalData,
+) -> Result<Option<TableStarts>, Error> {
+    let tables = match global_data {
+        GlobalData::Tables(tables) => tables,
+        GlobalData::Raw(data) => {
+            write_bytes(buf, data);
+            return Ok(None);
+        }
+    };
+
+    let start = buf.len();
+    for entry in &tables.table_1 {
+        write_entry(buf, entry)?;
+    }
+    let table_2 = buf.len() - start;
+    for entry in &tables.table_2 {
+        write_entry(buf, entry)?;
+    }
+    let change_forms = buf.len() - start;
+    for change_form in &tables.change_forms {
+        write_change_form(buf, change_form)?;
+    }
+    let table_3 = buf.len() - start;
+    for entry in &tables.table_3 {
+        write_entry(buf, entry)?;
+    }
+    Ok(Some(TableStarts {
+        table_2,
+        change_forms,
+        table_3,
+    }))
+}
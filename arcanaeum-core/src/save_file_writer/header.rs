@@ -0,0 +1,39 @@
+use std::io::Error;
+
+use crate::save_file_parser::header::{FileTime, Header, Sex};
+
+use super::utils::{write_f32, write_u16, write_u32, write_w_string};
+
+fn sex_to_bit(sex: &Sex) -> u16 {
+    match sex {
+        Sex::Male => 0,
+        Sex::Female => 1,
+        Sex::Undefined => 2,
+    }
+}
+
+pub fn write_filetime(buf: &mut Vec<u8>, filetime: &FileTime) {
+    write_u32(buf, filetime.dw_low);
+    write_u32(buf, filetime.dw_high);
+}
+
+pub fn write_header(buf: &mut Vec<u8>, header: &Header) -> Result<(), Error> {
+    write_u32(buf, header.version);
+    write_u32(buf, header.save_number);
+    write_w_string(buf, &header.player_name)?;
+    write_u32(buf, header.player_level);
+    write_w_string(buf, &header.player_location)?;
+    write_w_string(buf, &header.game_date)?;
+    write_w_string(buf, &header.player_race_editor_id)?;
+    write_u16(buf, sex_to_bit(&header.player_sex));
+    write_f32(buf, header.player_current_xp);
+    write_f32(buf, header.player_level_up_xp);
+    write_filetime(buf, &header.filetime);
+    write_u32(buf, header.screenshot_width);
+    write_u32(buf, header.screenshot_height);
+
+    if header.is_se {
+        write_u16(buf, header.compression_type);
+    }
+    Ok(())
+}
@@ -0,0 +1,594 @@
+use lz4_flex::compress;
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+
+use crate::save_file_parser::{
+    file_location_table::{FileLocationTable, FILE_LOCATION_TABLE_SIZE},
+    get_screenshot_data_size,
+    global_data::GlobalData,
+    plugin_info::has_light_plugins,
+    SaveInfo,
+};
+
+use self::{
+    file_location_table::write_file_location_table,
+    form_id_array::write_form_id_array,
+    global_data::{write_global_data, TableStarts},
+    header::write_header,
+    plugin_info::write_plugin_info,
+    utils::{write_bytes, write_charray, write_u32, write_u8},
+};
+
+mod file_location_table;
+mod form_id_array;
+mod global_data;
+mod header;
+pub mod plugin_edit;
+mod plugin_info;
+mod utils;
+
+/**
+ * Offset of the body (form version onwards) in the save file as if it were uncompressed.
+ */
+fn get_body_offset(save_info: &SaveInfo, header_size: usize) -> usize {
+    let compression_lengths_size = if save_info.header.is_se { 8 } else { 0 };
+    save_info.magic_string.len()
+        + 4
+        + header_size
+        + save_info.screenshot_data.len()
+        + compression_lengths_size
+}
+
+/**
+ * Offset of the data following the file location table, relative to the start of the body.
+ */
+fn get_body_data_offset(plugin_info_size: usize) -> usize {
+    1 + 4 + plugin_info_size + FILE_LOCATION_TABLE_SIZE
+}
+
+/**
+ * Works out how far the offsets in the file location table have to move to still point
+ * at the same data once the header and plugin info have been rewritten.
+ */
+fn get_offset_shift(save_info: &SaveInfo, header_size: usize, plugin_info_size: usize) -> i64 {
+    let old_body_offset = get_body_offset(save_info, save_info.header_size as usize);
+    let new_body_offset = get_body_offset(save_info, header_size);
+    let old_data_offset = get_body_data_offset(save_info.plugin_info_size as usize);
+    let new_data_offset = get_body_data_offset(plugin_info_size);
+
+    // The global data tables directly follow the file location table, so this tells us
+    // whether the offsets count from the start of the file or from the start of the body.
+    let first_table = save_info.file_location_table.global_data_table_1_offset as usize;
+    let body_shift = new_data_offset as i64 - old_data_offset as i64;
+    if first_table == old_body_offset + old_data_offset {
+        body_shift + new_body_offset as i64 - old_body_offset as i64
+    } else {
+        body_shift
+    }
+}
+
+/**
+ * Moves the file location table along with the header and plugin info, then points it at
+ * the data after the first table as it will be written, so that edited tables and arrays
+ * are found by the game.
+ */
+fn locate_tables(
+    save_info: &SaveInfo,
+    shift: i64,
+    table_starts: Option<&TableStarts>,
+    global_data_size: usize,
+) -> FileLocationTable {
+    let mut table = save_info.file_location_table.shifted(shift);
+    let start = table.global_data_table_1_offset as usize;
+    if let (GlobalData::Tables(tables), Some(starts)) = (&save_info.global_data, table_starts) {
+        table.global_data_table_2_offset = (start + starts.table_2) as u32;
+        table.change_forms_offset = (start + starts.change_forms) as u32;
+        table.global_data_table_3_offset = (start + starts.table_3) as u32;
+        table.global_data_table_1_count = tables.table_1.len() as u32;
+        table.global_data_table_2_count = tables.table_2.len() as u32;
+        table.change_form_count = tables.change_forms.len() as u32;
+        table.global_data_table_3_count =
+            (tables.table_3.len() as u32).saturating_sub(tables.uncounted_table_3_entries);
+    }
+
+    let form_id_array_count_offset = start + global_data_size;
+    let visited_worldspaces_offset =
+        form_id_array_count_offset + 4 + 4 * save_info.form_id_array.len();
+    table.form_id_array_count_offset = form_id_array_count_offset as u32;
+    table.unknown_table_3_offset =
+        (visited_worldspaces_offset + 4 + 4 * save_info.visited_worldspace_array.len()) as u32;
+    table
+}
+
+/**
+ * Checks what the header says about the data around it, as an imported JSON file could
+ * have been edited into something the game can't load.
+ */
+fn validate(save_info: &SaveInfo) -> Result<(), Error> {
+    if save_info.magic_string != "TESV_SAVEGAME" {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "{:?} is not the magic string of a save",
+                save_info.magic_string
+            ),
+        ));
+    }
+    let screenshot_size = get_screenshot_data_size(&save_info.header);
+    if save_info.screenshot_data.len() != screenshot_size {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "A {}x{} screenshot is {} bytes, not {}",
+                save_info.header.screenshot_width,
+                save_info.header.screenshot_height,
+                screenshot_size,
+                save_info.screenshot_data.len()
+            ),
+        ));
+    }
+    Ok(())
+}
+
+fn compress_body(body: Vec<u8>, compression_type: u16) -> Result<(Vec<u8>, u32, u32), Error> {
+    let uncompressed_length = body.len() as u32;
+    match compression_type {
+        0 => Ok((body, uncompressed_length, 0)),
+        2 => {
+            let compressed = compress(&body);
+            let compressed_length = compressed.len() as u32;
+            Ok((compressed, uncompressed_length, compressed_length))
+        }
+        _ => Err(Error::new(
+            ErrorKind::Unsupported,
+            format!("Cannot write saves with compression type {compression_type}"),
+        )),
+    }
+}
+
+pub fn get_compression_type_name(compression_type: u16) -> &'static str {
+    match compression_type {
+        0 => "Uncompressed",
+        1 => "zLib",
+        2 => "LZ4",
+        _ => "Unknown",
+    }
+}
+
+/**
+ * Returns a copy of the save that will be written with the given compression type. Only
+ * SE saves have a compression type, and only no compression and LZ4 can be written.
+ */
+pub fn with_compression_type(
+    save_info: &SaveInfo,
+    compression_type: u16,
+) -> Result<SaveInfo, Error> {
+    if !save_info.header.is_se {
+        return Err(Error::new(
+            ErrorKind::Unsupported,
+            "Only Skyrim Special Edition saves can be compressed",
+        ));
+    }
+    if compression_type != 0 && compression_type != 2 {
+        return Err(Error::new(
+            ErrorKind::Unsupported,
+            format!(
+                "Cannot convert to {} compression",
+                get_compression_type_name(compression_type)
+            ),
+        ));
+    }
+
+    let mut converted = save_info.clone();
+    converted.header.compression_type = compression_type;
+    Ok(converted)
+}
+
+/**
+ * Serialises a save back into the ESS format. Size, count, length and offset fields are
+ * recalculated, so the header and plugin info can be edited before writing.
+ */
+pub fn write(save_info: &SaveInfo) -> Result<Vec<u8>, Error> {
+    validate(save_info)?;
+    let header = &save_info.header;
+
+    let mut header_buf = Vec::new();
+    write_header(&mut header_buf, header)?;
+
+    let mut plugin_info_buf = Vec::new();
+    let light_plugins = has_light_plugins(header.is_se, save_info.form_version);
+    write_plugin_info(&mut plugin_info_buf, &save_info.plugin_info, light_plugins)?;
+
+    let mut global_data_buf = Vec::new();
+    let table_starts = write_global_data(&mut global_data_buf, &save_info.global_data)?;
+
+    let shift = get_offset_shift(save_info, header_buf.len(), plugin_info_buf.len());
+    let file_location_table = locate_tables(
+        save_info,
+        shift,
+        table_starts.as_ref(),
+        global_data_buf.len(),
+    );
+
+    let mut body = Vec::new();
+    write_u8(&mut body, save_info.form_version);
+    write_u32(&mut body, plugin_info_buf.len() as u32);
+    write_bytes(&mut body, &plugin_info_buf);
+    write_file_location_table(&mut body, &file_location_table);
+    write_bytes(&mut body, &global_data_buf);
+    write_form_id_array(&mut body, &save_info.form_id_array);
+    write_form_id_array(&mut body, &save_info.visited_worldspace_array);
+    write_bytes(&mut body, &save_info.remaining_data);
+
+    let mut buf = Vec::new();
+    write_charray(&mut buf, &save_info.magic_string);
+    write_u32(&mut buf, header_buf.len() as u32);
+    write_bytes(&mut buf, &header_buf);
+    write_bytes(&mut buf, &save_info.screenshot_data);
+
+    if header.is_se {
+        let (body, uncompressed_length, compressed_length) =
+            compress_body(body, header.compression_type)?;
+        write_u32(&mut buf, uncompressed_length);
+        write_u32(&mut buf, compressed_length);
+        write_bytes(&mut buf, &body);
+    } else {
+        write_bytes(&mut buf, &body);
+    }
+
+    Ok(buf)
+}
+
+/**
+ * Finds a file name next to the original save that is not taken yet, so that edited
+ * saves never overwrite anything.
+ */
+pub fn get_edited_save_path(path: &str, suffix: &str) -> PathBuf {
+    let path = Path::new(path);
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let mut attempt = 1;
+    loop {
+        let file_name = match attempt {
+            1 => format!("{stem}_{suffix}.ess"),
+            _ => format!("{stem}_{suffix}_{attempt}.ess"),
+        };
+        let candidate = path.with_file_name(file_name);
+        if !candidate.exists() {
+            return candidate;
+        }
+        attempt += 1;
+    }
+}
+
+/**
+ * Copies the SKSE co-save of a save, if there is one, so that it stays paired with the copy.
+ */
+pub fn copy_skse_cosave(from: &Path, to: &Path) -> Result<(), Error> {
+    let cosave = from.with_extension("skse");
+    if cosave.exists() {
+        std::fs::copy(cosave, to.with_extension("skse"))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub mod test_data {
+    use crate::save_file_parser::{
+        file_location_table::FileLocationTable,
+        global_data::{ChangeForm, GlobalData, GlobalDataEntry, GlobalDataTables},
+        header::{FileTime, Header, Sex},
+        plugin_info::PluginInfo,
+        SaveInfo,
+    };
+
+    use super::{
+        get_body_data_offset, get_body_offset, global_data::write_global_data,
+        header::write_header, locate_tables, plugin_info::write_plugin_info,
+    };
+
+    fn entry(data_type: u32, data: &[u8]) -> GlobalDataEntry {
+        GlobalDataEntry {
+            data_type,
+            data: data.to_vec(),
+        }
+    }
+
+    /**
+     * Builds a small but structurally valid SE save for round trip tests.
+     */
+    pub fn sample_save_info(compression_type: u16) -> SaveInfo {
+        let header = Header {
+            version: 12,
+            save_number: 3,
+            player_name: "Aluna Messana".to_string(),
+            player_level: 1,
+            player_location: "Old Hroldan Inn".to_string(),
+            game_date: "000.11.05".to_string(),
+            player_race_editor_id: "RedguardRace".to_string(),
+            player_sex: Sex::Female,
+            player_current_xp: 0.0,
+            player_level_up_xp: 75.0,
+            filetime: FileTime {
+                dw_low: 1,
+                dw_high: 2,
+            },
+            screenshot_width: 2,
+            screenshot_height: 1,
+            compression_type,
+            is_se: true,
+        };
+        let plugin_info = PluginInfo {
+            plugin_count: 2,
+            plugins: vec!["Skyrim.esm".to_string(), "SkyUI.esp".to_string()],
+            light_plugin_count: 1,
+            light_plugins: vec!["ccQDRSSE001-SurvivalMode.esl".to_string()],
+        };
+        let form_id_array = vec![0x0000_0014, 0x0100_0D62, 0xFE00_0801, 0xFF00_1234];
+
+        let mut save_info = SaveInfo {
+            magic_string: "TESV_SAVEGAME".to_string(),
+            header_size: 0,
+            header,
+            screenshot_data: vec![7; 8],
+            uncompressed_length: 0,
+            compressed_length: 0,
+            form_version: 78,
+            plugin_info_size: 0,
+            plugin_info,
+            file_location_table: FileLocationTable {
+                form_id_array_count_offset: 0,
+                unknown_table_3_offset: 0,
+                global_data_table_1_offset: 0,
+                global_data_table_2_offset: 0,
+                change_forms_offset: 0,
+                global_data_table_3_offset: 0,
+                global_data_table_1_count: 0,
+                global_data_table_2_count: 0,
+                global_data_table_3_count: 0,
+                change_form_count: 0,
+                unused: [0; 15],
+            },
+            global_data: GlobalData::Tables(GlobalDataTables {
+                table_1: vec![entry(0, &[1, 2, 3, 4]), entry(1, &[5; 6])],
+                table_2: vec![entry(100, &[7; 3])],
+                change_forms: vec![
+                    ChangeForm {
+                        form_id: 0x40_0014,
+                        change_flags: 0x8000_0002,
+                        form_type: 9,
+                        length_size: 0,
+                        version: 78,
+                        uncompressed_length: 0,
+                        data: vec![8; 5],
+                    },
+                    ChangeForm {
+                        form_id: 0x00_0002,
+                        change_flags: 1,
+                        form_type: 1,
+                        length_size: 1,
+                        version: 78,
+                        uncompressed_length: 300,
+                        data: vec![9; 260],
+                    },
+                ],
+                table_3: vec![entry(1000, &[]), entry(1001, &[1])],
+                uncounted_table_3_entries: 1,
+            }),
+            form_id_array,
+            visited_worldspace_array: vec![0x0000_003C],
+            remaining_data: vec![4, 0, 0, 0, 9, 9, 9, 9],
+        };
+
+        // Fill in the sizes a parsed save would have, then point the table at the body data.
+        let mut header_buf = Vec::new();
+        write_header(&mut header_buf, &save_info.header).unwrap();
+        let mut plugin_info_buf = Vec::new();
+        write_plugin_info(&mut plugin_info_buf, &save_info.plugin_info, true).unwrap();
+        save_info.header_size = header_buf.len() as u32;
+        save_info.plugin_info_size = plugin_info_buf.len() as u32;
+
+        let data_offset = (get_body_offset(&save_info, header_buf.len())
+            + get_body_data_offset(plugin_info_buf.len())) as u32;
+        save_info.file_location_table.global_data_table_1_offset = data_offset;
+        let mut global_data_buf = Vec::new();
+        let table_starts = write_global_data(&mut global_data_buf, &save_info.global_data).unwrap();
+        save_info.file_location_table =
+            locate_tables(&save_info, 0, table_starts.as_ref(), global_data_buf.len());
+        save_info
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_data::sample_save_info;
+    use super::*;
+    use crate::save_file_parser::{global_data::GlobalDataEntry, parse};
+
+    #[test]
+    fn write_round_trips_compressed_save() {
+        let save_info = sample_save_info(2);
+        let written = write(&save_info).unwrap();
+        let parsed = parse(written.clone());
+
+        assert_eq!(parsed.header.player_name, "Aluna Messana");
+        assert_eq!(parsed.plugin_info.plugins, save_info.plugin_info.plugins);
+        assert_eq!(
+            parsed.plugin_info.light_plugins,
+            save_info.plugin_info.light_plugins
+        );
+        assert_eq!(parsed.global_data, save_info.global_data);
+        assert_eq!(parsed.form_id_array, save_info.form_id_array);
+        assert_eq!(parsed.remaining_data, save_info.remaining_data);
+        assert_eq!(write(&parsed).unwrap(), written);
+    }
+
+    #[test]
+    fn renaming_player_moves_file_location_table_offsets() {
+        let save_info = sample_save_info(0);
+        let mut edited = save_info.clone();
+        edited.header.player_name = "Aluna".to_string();
+
+        let parsed = parse(write(&edited).unwrap());
+        let old_table = &save_info.file_location_table;
+        let new_table = &parsed.file_location_table;
+
+        assert_eq!(parsed.header.player_name, "Aluna");
+        assert_eq!(parsed.header_size, save_info.header_size - 8);
+        assert_eq!(
+            new_table.global_data_table_1_offset,
+            old_table.global_data_table_1_offset - 8
+        );
+        assert_eq!(
+            new_table.form_id_array_count_offset,
+            old_table.form_id_array_count_offset - 8
+        );
+        assert_eq!(parsed.form_id_array, save_info.form_id_array);
+    }
+
+    #[test]
+    fn renaming_keeps_accented_header_strings() {
+        let mut save_info = sample_save_info(0);
+        save_info.header.player_location = "Ysgramor's Tomb – Nórdic".to_string();
+        let parsed = parse(write(&save_info).unwrap());
+
+        let mut edited = parsed.clone();
+        edited.header.player_name = "Ænna".to_string();
+        let renamed = parse(write(&edited).unwrap());
+        assert_eq!(renamed.header.player_name, "Ænna");
+        assert_eq!(renamed.header.player_location, "Ysgramor's Tomb – Nórdic");
+
+        edited.header.player_name = "Алуна".to_string();
+        assert_eq!(write(&edited).unwrap_err().kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn converting_between_compression_types_keeps_body() {
+        let save_info = sample_save_info(2);
+
+        let uncompressed = with_compression_type(&save_info, 0).unwrap();
+        let written = write(&uncompressed).unwrap();
+        let parsed = parse(written.clone());
+        assert_eq!(parsed.header.compression_type, 0);
+        assert_eq!(parsed.compressed_length, 0);
+        let body_length = written.len() - get_body_offset(&parsed, parsed.header_size as usize);
+        assert_eq!(parsed.uncompressed_length as usize, body_length);
+        assert_eq!(parsed.form_id_array, save_info.form_id_array);
+
+        let compressed = with_compression_type(&parsed, 2).unwrap();
+        let parsed = parse(write(&compressed).unwrap());
+        assert_eq!(parsed.header.compression_type, 2);
+        assert_eq!(parsed.uncompressed_length as usize, body_length);
+        assert_eq!(parsed.global_data, save_info.global_data);
+        assert_eq!(parsed.remaining_data, save_info.remaining_data);
+    }
+
+    #[test]
+    fn legendary_edition_saves_cannot_be_compressed() {
+        let mut save_info = sample_save_info(0);
+        save_info.header.is_se = false;
+        assert!(with_compression_type(&save_info, 2).is_err());
+        assert!(with_compression_type(&sample_save_info(0), 1).is_err());
+    }
+
+    #[test]
+    fn json_export_can_be_written_back() {
+        let save_info = sample_save_info(2);
+        let json = serde_json::to_string(&save_info).unwrap();
+        let imported: crate::save_file_parser::SaveInfo = serde_json::from_str(&json).unwrap();
+        assert_eq!(write(&imported).unwrap(), write(&save_info).unwrap());
+    }
+
+    #[test]
+    fn global_data_tables_are_read() {
+        let save_info = sample_save_info(2);
+        let parsed = parse(write(&save_info).unwrap());
+        assert_eq!(parsed.global_data, save_info.global_data);
+        assert_eq!(
+            parsed.visited_worldspace_array,
+            save_info.visited_worldspace_array
+        );
+
+        let table = &parsed.file_location_table;
+        assert_eq!(table.global_data_table_1_count, 2);
+        assert_eq!(table.change_form_count, 2);
+        assert_eq!(table.global_data_table_3_count, 1);
+        assert_eq!(
+            table.change_forms_offset - table.global_data_table_2_offset,
+            8 + 3
+        );
+    }
+
+    #[test]
+    fn edited_tables_move_the_offsets_after_them() {
+        let save_info = sample_save_info(0);
+        let mut edited = save_info.clone();
+        if let GlobalData::Tables(tables) = &mut edited.global_data {
+            tables.change_forms[0].data = vec![1; 15];
+            tables.table_3.push(GlobalDataEntry {
+                data_type: 1002,
+                data: vec![2; 4],
+            });
+        }
+        edited.form_id_array.push(0x0200_0800);
+
+        let parsed = parse(write(&edited).unwrap());
+        let old_table = &save_info.file_location_table;
+        let new_table = &parsed.file_location_table;
+        assert_eq!(parsed.global_data, edited.global_data);
+        assert_eq!(parsed.form_id_array, edited.form_id_array);
+        assert_eq!(parsed.remaining_data, save_info.remaining_data);
+        assert_eq!(
+            new_table.global_data_table_3_offset,
+            old_table.global_data_table_3_offset + 10
+        );
+        assert_eq!(new_table.global_data_table_3_count, 2);
+        assert_eq!(
+            new_table.unknown_table_3_offset,
+            old_table.unknown_table_3_offset + 10 + 12 + 4
+        );
+    }
+
+    #[test]
+    fn imports_that_do_not_fit_the_format_are_refused() {
+        let mut save_info = sample_save_info(0);
+        save_info.screenshot_data.pop();
+        assert_eq!(
+            write(&save_info).unwrap_err().kind(),
+            ErrorKind::InvalidData
+        );
+
+        let mut save_info = sample_save_info(0);
+        if let GlobalData::Tables(tables) = &mut save_info.global_data {
+            tables.change_forms[0].data = vec![0; 256];
+        }
+        assert_eq!(
+            write(&save_info).unwrap_err().kind(),
+            ErrorKind::InvalidData
+        );
+
+        let mut save_info = sample_save_info(0);
+        save_info.plugin_info.plugins = vec!["A.esp".to_string(); 256];
+        assert_eq!(
+            write(&save_info).unwrap_err().kind(),
+            ErrorKind::InvalidData
+        );
+    }
+
+    #[test]
+    fn json_export_is_compact() {
+        let save_info = sample_save_info(2);
+        let json = serde_json::to_string_pretty(&save_info).unwrap();
+        assert!(json.contains("\"screenshot_data\": \"0707070707070707\""));
+        assert!(json.contains("\"data\": \"01020304\""));
+    }
+
+    #[test]
+    fn zlib_saves_are_not_written() {
+        let save_info = sample_save_info(1);
+        assert!(write(&save_info).is_err());
+    }
+}
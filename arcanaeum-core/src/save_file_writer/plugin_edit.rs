@@ -0,0 +1,191 @@
+use std::io::{Error, ErrorKind};
+
+use crate::save_file_parser::{
+    form_id_array::{
+        get_light_plugin_index, get_plugin_index, with_light_plugin_index, with_plugin_index,
+    },
+    SaveInfo,
+};
+
+/**
+ * The top byte of a form id is the plugin index, with 0xFE and 0xFF reserved.
+ */
+const MAX_PLUGINS: usize = 0xFE;
+const MAX_LIGHT_PLUGINS: usize = 0x1000;
+
+/**
+ * A plugin in an edited plugin list. `original_index` is where the plugin was in the
+ * save's own list, so renamed and moved plugins keep their forms.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct PluginEntry {
+    pub name: String,
+    pub original_index: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PluginEditReport {
+    pub remapped_form_ids: usize,
+    pub orphaned_form_ids: usize,
+}
+
+pub fn entries_from_names(names: &[String]) -> Vec<PluginEntry> {
+    names
+        .iter()
+        .enumerate()
+        .map(|(index, name)| PluginEntry {
+            name: name.clone(),
+            original_index: Some(index),
+        })
+        .collect()
+}
+
+/**
+ * Maps every original index to its new position, or None if the plugin was removed.
+ */
+fn get_index_map(entries: &[PluginEntry], original_count: usize) -> Vec<Option<usize>> {
+    let mut index_map = vec![None; original_count];
+    for (new_index, entry) in entries.iter().enumerate() {
+        if let Some(original_index) = entry.original_index {
+            if original_index < original_count {
+                index_map[original_index] = Some(new_index);
+            }
+        }
+    }
+    index_map
+}
+
+fn validate_entries(entries: &[PluginEntry], max: usize) -> Result<(), Error> {
+    if entries.len() > max {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("A save can hold at most {max} plugins of this kind"),
+        ));
+    }
+    if entries.iter().any(|e| e.name.trim().is_empty()) {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Plugin names cannot be empty",
+        ));
+    }
+    Ok(())
+}
+
+/**
+ * Applies an edited plugin list to a save. Form ids in the form id array are moved to the
+ * new plugin indices. Form ids of removed plugins are cleared, so the game treats them
+ * like forms from a missing plugin instead of resolving them against the wrong plugin.
+ */
+pub fn apply_plugin_edits(
+    save_info: &SaveInfo,
+    plugins: &[PluginEntry],
+    light_plugins: &[PluginEntry],
+) -> Result<(SaveInfo, PluginEditReport), Error> {
+    validate_entries(plugins, MAX_PLUGINS)?;
+    validate_entries(light_plugins, MAX_LIGHT_PLUGINS)?;
+
+    let plugin_map = get_index_map(plugins, save_info.plugin_info.plugins.len());
+    let light_plugin_map = get_index_map(light_plugins, save_info.plugin_info.light_plugins.len());
+
+    let mut report = PluginEditReport::default();
+    let mut remap = |original_index: usize, index_map: &Vec<Option<usize>>| {
+        match index_map.get(original_index) {
+            Some(Some(new_index)) => {
+                if *new_index != original_index {
+                    report.remapped_form_ids += 1;
+                }
+                Some(*new_index)
+            }
+            Some(None) => {
+                report.orphaned_form_ids += 1;
+                None
+            }
+            // Not one of the save's plugins to begin with, leave it alone
+            None => Some(original_index),
+        }
+    };
+
+    let form_id_array = save_info
+        .form_id_array
+        .iter()
+        .map(|form_id| {
+            if let Some(index) = get_plugin_index(*form_id) {
+                remap(index, &plugin_map).map_or(0, |i| with_plugin_index(*form_id, i))
+            } else if let Some(index) = get_light_plugin_index(*form_id) {
+                remap(index, &light_plugin_map).map_or(0, |i| with_light_plugin_index(*form_id, i))
+            } else {
+                *form_id
+            }
+        })
+        .collect();
+
+    let mut edited = save_info.clone();
+    edited.plugin_info.plugins = plugins.iter().map(|e| e.name.trim().to_string()).collect();
+    edited.plugin_info.plugin_count = plugins.len() as u8;
+    edited.plugin_info.light_plugins = light_plugins
+        .iter()
+        .map(|e| e.name.trim().to_string())
+        .collect();
+    edited.plugin_info.light_plugin_count = light_plugins.len() as u16;
+    edited.form_id_array = form_id_array;
+
+    Ok((edited, report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::save_file_parser::parse;
+    use crate::save_file_writer::{test_data::sample_save_info, write};
+
+    #[test]
+    fn renaming_a_plugin_keeps_its_forms() {
+        let save_info = sample_save_info(2);
+        let mut plugins = entries_from_names(&save_info.plugin_info.plugins);
+        plugins[1].name = "SkyUI_SE.esp".to_string();
+        let light_plugins = entries_from_names(&save_info.plugin_info.light_plugins);
+
+        let (edited, report) = apply_plugin_edits(&save_info, &plugins, &light_plugins).unwrap();
+
+        assert_eq!(report, PluginEditReport::default());
+        assert_eq!(edited.form_id_array, save_info.form_id_array);
+        let parsed = parse(write(&edited).unwrap());
+        assert_eq!(
+            parsed.plugin_info.plugins,
+            vec!["Skyrim.esm", "SkyUI_SE.esp"]
+        );
+    }
+
+    #[test]
+    fn moving_a_plugin_rewrites_form_ids() {
+        let save_info = sample_save_info(2);
+        let mut plugins = entries_from_names(&save_info.plugin_info.plugins);
+        plugins.swap(0, 1);
+        plugins.push(PluginEntry {
+            name: "Unofficial Skyrim Special Edition Patch.esp".to_string(),
+            original_index: None,
+        });
+        let light_plugins = entries_from_names(&save_info.plugin_info.light_plugins);
+
+        let (edited, report) = apply_plugin_edits(&save_info, &plugins, &light_plugins).unwrap();
+
+        assert_eq!(report.remapped_form_ids, 2);
+        assert_eq!(
+            edited.form_id_array,
+            vec![0x0100_0014, 0x0000_0D62, 0xFE00_0801, 0xFF00_1234]
+        );
+        assert_eq!(edited.plugin_info.plugin_count, 3);
+    }
+
+    #[test]
+    fn removing_plugins_clears_their_form_ids() {
+        let save_info = sample_save_info(2);
+        let plugins = entries_from_names(&save_info.plugin_info.plugins[0..1]);
+
+        let (edited, report) = apply_plugin_edits(&save_info, &plugins, &[]).unwrap();
+
+        assert_eq!(report.orphaned_form_ids, 2);
+        assert_eq!(edited.form_id_array, vec![0x0000_0014, 0, 0, 0xFF00_1234]);
+        assert!(edited.plugin_info.light_plugins.is_empty());
+    }
+}
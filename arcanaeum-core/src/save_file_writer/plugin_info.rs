@@ -0,0 +1,43 @@
+use std::io::{Error, ErrorKind};
+
+use crate::save_file_parser::plugin_info::PluginInfo;
+
+use super::utils::{write_u16, write_u8, write_w_string};
+
+/**
+ * Counts are taken from the plugin lists rather than the count fields, so that
+ * edited lists are always written consistently.
+ */
+pub fn write_plugin_info(
+    buf: &mut Vec<u8>,
+    plugin_info: &PluginInfo,
+    light_plugins: bool,
+) -> Result<(), Error> {
+    let count = u8::try_from(plugin_info.plugins.len()).map_err(|_| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("A save can't list {} plugins", plugin_info.plugins.len()),
+        )
+    })?;
+    write_u8(buf, count);
+    for plugin in &plugin_info.plugins {
+        write_w_string(buf, plugin)?;
+    }
+
+    if light_plugins {
+        let count = u16::try_from(plugin_info.light_plugins.len()).map_err(|_| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "A save can't list {} light plugins",
+                    plugin_info.light_plugins.len()
+                ),
+            )
+        })?;
+        write_u16(buf, count);
+        for plugin in &plugin_info.light_plugins {
+            write_w_string(buf, plugin)?;
+        }
+    }
+    Ok(())
+}
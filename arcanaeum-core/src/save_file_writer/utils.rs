@@ -0,0 +1,91 @@
+use std::io::{Error, ErrorKind};
+
+use encoding_rs::WINDOWS_1252;
+
+pub fn write_charray(buf: &mut Vec<u8>, value: &str) {
+    buf.extend_from_slice(value.as_bytes());
+}
+
+pub fn write_f32(buf: &mut Vec<u8>, value: f32) {
+    buf.extend_from_slice(&value.to_le_bytes());
+}
+
+pub fn write_u32(buf: &mut Vec<u8>, value: u32) {
+    buf.extend_from_slice(&value.to_le_bytes());
+}
+
+pub fn write_u16(buf: &mut Vec<u8>, value: u16) {
+    buf.extend_from_slice(&value.to_le_bytes());
+}
+
+pub fn write_u8(buf: &mut Vec<u8>, value: u8) {
+    buf.push(value);
+}
+
+/**
+ * Writes a string prefixed with its length as a u16, the counterpart of `read_w_string`.
+ * The game reads these as Windows-1252, so strings it can't represent are refused.
+ */
+pub fn write_w_string(buf: &mut Vec<u8>, value: &str) -> Result<(), Error> {
+    let (bytes, _, unmappable) = WINDOWS_1252.encode(value);
+    if unmappable {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("\"{value}\" has characters that can't be stored in a save"),
+        ));
+    }
+    let length = u16::try_from(bytes.len()).map_err(|_| {
+        Error::new(
+            ErrorKind::InvalidInput,
+            format!("\"{value}\" is too long to be stored in a save"),
+        )
+    })?;
+    write_u16(buf, length);
+    buf.extend_from_slice(&bytes);
+    Ok(())
+}
+
+pub fn write_bytes(buf: &mut Vec<u8>, bytes: &[u8]) {
+    buf.extend_from_slice(bytes);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_w_string_prefixes_length() {
+        let mut buf = Vec::new();
+        write_w_string(&mut buf, "Skyrim.esm").unwrap();
+        assert_eq!(buf[0..2], [10, 0]);
+        assert_eq!(&buf[2..], "Skyrim.esm".as_bytes());
+    }
+
+    #[test]
+    fn write_u32_is_little_endian() {
+        let mut buf = Vec::new();
+        write_u32(&mut buf, 97);
+        assert_eq!(buf, vec![97, 0, 0, 0]);
+    }
+
+    #[test]
+    fn w_strings_are_windows_1252() {
+        let mut buf = Vec::new();
+        write_w_string(&mut buf, "Hjalmarch Ærsk").unwrap();
+        assert_eq!(buf[0..2], [14, 0]);
+        assert_eq!(buf[12], 0xC6);
+        assert!(write_w_string(&mut Vec::new(), "Алуна").is_err());
+    }
+
+    #[test]
+    fn w_strings_round_trip_every_byte() {
+        let bytes: Vec<u8> = (0..=255).collect();
+        let mut buf = vec![0, 1];
+        buf.extend_from_slice(&bytes);
+        let (value, _) = crate::save_file_parser::utils::read_w_string(&buf, 0);
+
+        let mut written = Vec::new();
+        write_w_string(&mut written, &value).unwrap();
+        assert_eq!(written, buf);
+    }
+}
@@ -30,6 +30,17 @@ pub fn find_skyrim_install_path() -> Option<PathBuf> {
     None
 }
 
+/**
+ * Find the Data folder that plugins are loaded from.
+ */
+pub fn find_skyrim_data_path() -> Option<PathBuf> {
+    let data_path = find_skyrim_install_path()?.join("Data");
+    match data_path.exists() {
+        true => Some(data_path),
+        false => None,
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -1,17 +1,44 @@
 use eframe::egui::{self};
 use std::collections::HashMap;
-use std::collections::HashSet;
+use std::io::Error;
+use std::path::PathBuf;
+use std::sync::Arc;
 
+use crate::components::category_rules_window::CategoryRulesWindow;
 use crate::components::detail_view::DetailView;
+use crate::components::nexus_search::{NexusKeyCheck, NexusSearch};
 use crate::components::save_file_selector::{
-    get_default_save_folder, read_folder_contents, SaveFileSelector,
+    get_default_save_folder, get_known_save_folders, SaveFileSelector,
 };
-use crate::load_saveinfo_from_path;
-use crate::mod_search::vortex_scanner::Plugin;
-use crate::save_file_parser::header::Header;
-use crate::save_file_parser::SaveInfo;
+use crate::components::settings_window::{ResolvedMasterlist, SettingsWindow};
+use crate::config::{load_config, save_config, Config};
+use crate::saves::{read_folder_contents, SaveFile};
 use crate::sktypes::skui_value::{SkUIValue, UIValueType};
-use crate::{load_installed, load_mod_map};
+use arcanaeum_core::categories::{Categorizer, CategorySettings, PluginFacts};
+use arcanaeum_core::load_order::comparison::LoadOrderComparison;
+use arcanaeum_core::load_order::dependency_graph::{
+    build_dependency_graph_from_files, DependencyGraph,
+};
+use arcanaeum_core::load_order::official::OfficialPlugins;
+use arcanaeum_core::load_order::profile::ProfilePlugin;
+use arcanaeum_core::loot::condition::InstallEnvironment;
+use arcanaeum_core::loot::masterlist::MasterlistIndex;
+use arcanaeum_core::loot::sources::MasterlistSource;
+use arcanaeum_core::loot::warnings::{get_save_warnings, LootWarning};
+use arcanaeum_core::mod_manager::{
+    create_mod_manager, find_plugin_path, get_installed_plugins, InstalledPlugin, ModManager,
+    Profile,
+};
+use arcanaeum_core::mod_search::mo2_scanner::{find_instances, Mo2Instance};
+use arcanaeum_core::mod_search::vortex_scanner::Plugin;
+use arcanaeum_core::plugin_file::PluginHeader;
+use arcanaeum_core::save_file_parser::SaveInfo;
+use arcanaeum_core::save_file_writer::plugin_edit::{entries_from_names, PluginEntry};
+use arcanaeum_core::utils::skyrim_se::find_skyrim_data_path;
+use arcanaeum_core::{load_masterlist, load_mod_map};
+use arcanaeum_core::{
+    load_saveinfo_from_json_path, load_saveinfo_from_path, write_saveinfo_to_path,
+};
 
 #[derive(Clone)]
 pub struct DetailState {
@@ -19,7 +46,96 @@ pub struct DetailState {
     pub save_info: Option<SaveInfo>,
     pub plugins: Option<Vec<SkUIValue>>,
     pub mod_map: HashMap<String, Plugin>,
-    pub installed: HashSet<String>,
+    /**
+     * Nexus searches for plugins that weren't found, by plugin name.
+     */
+    pub nexus_searches: HashMap<String, NexusSearch>,
+    /**
+     * Plugins active in any profile of the mod manager, keyed by lowercased name.
+     */
+    pub installed: HashMap<String, InstalledPlugin>,
+    pub mod_manager: Option<Arc<dyn ModManager>>,
+    pub header_edit: HeaderEditState,
+    pub plugin_edit: PluginEditState,
+    pub conversion_status: Option<String>,
+    pub export_status: Option<String>,
+    pub data_path: Option<PathBuf>,
+    pub official_plugins: OfficialPlugins,
+    /**
+     * Creation Club plugins the save uses that are not in the Data folder, None when
+     * there is no Data folder to look in.
+     */
+    pub missing_creation_club: Option<Result<Vec<String>, String>>,
+    pub selected_plugin: Option<String>,
+    pub plugin_header: Option<Result<PluginHeader, String>>,
+    pub dependency_graph: Option<DependencyGraph>,
+    pub show_dependency_graph: bool,
+    /**
+     * LOOT's warnings for the save's plugins, keyed by lowercased name.
+     */
+    pub loot_warnings: HashMap<String, Vec<LootWarning>>,
+    pub categories: CategorySettings,
+    /**
+     * The category of each of the save's plugins that matched a rule, keyed by lowercased
+     * name.
+     */
+    pub plugin_categories: HashMap<String, String>,
+    pub masterlist_source: MasterlistSource,
+    pub load_order_comparison: LoadOrderComparisonState,
+    pub load_order_sort: LoadOrderSortState,
+}
+
+impl DetailState {
+    /**
+     * The game the selected save is from, as "skyrim" for LE and "skyrimse" for SE.
+     */
+    pub fn game(&self) -> &'static str {
+        match &self.save_info {
+            Some(save_info) if !save_info.header.is_se => "skyrim",
+            _ => "skyrimse",
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct HeaderEditState {
+    pub player_name: String,
+    pub save_number: u32,
+    pub status: Option<String>,
+}
+
+#[derive(Clone, Default)]
+pub struct PluginEditState {
+    pub plugins: Vec<PluginEntry>,
+    pub light_plugins: Vec<PluginEntry>,
+    pub status: Option<String>,
+}
+
+#[derive(Clone, Default)]
+pub struct LoadOrderComparisonState {
+    pub profiles: Vec<Profile>,
+    pub profile_path: Option<PathBuf>,
+    pub result: Option<Result<LoadOrderComparison, String>>,
+    pub restore_status: Option<String>,
+}
+
+/**
+ * A sorted load order waiting to be written, with how it differs from the current one.
+ */
+#[derive(Clone)]
+pub struct SortProposal {
+    /**
+     * The profile that was sorted, which is the only one the proposal may be written to.
+     */
+    pub profile_path: PathBuf,
+    pub plugins: Vec<ProfilePlugin>,
+    pub comparison: LoadOrderComparison,
+}
+
+#[derive(Clone, Default)]
+pub struct LoadOrderSortState {
+    pub proposal: Option<Result<SortProposal, String>>,
+    pub status: Option<String>,
 }
 
 #[derive(Clone)]
@@ -27,9 +143,20 @@ pub struct SavesState {
     pub save_file_list: Vec<SaveFile>,
     pub characters: HashMap<String, Character>,
     pub save_folder_path: String,
+    /**
+     * Save folders to pick from, as (label, path).
+     */
+    pub save_folders: Vec<(String, String)>,
     pub selected_character: Option<String>,
 }
 
+impl SavesState {
+    pub fn refresh(&mut self) {
+        self.save_file_list = read_folder_contents(self.save_folder_path.as_str());
+        self.characters = group_saves_by_character(&self.save_file_list);
+    }
+}
+
 #[derive(Clone)]
 pub struct AppState {
     pub error: Option<String>,
@@ -38,13 +165,17 @@ pub struct AppState {
     pub saves_state: SavesState,
     pub show_resolution_window: bool,
     pub show_settings_window: bool,
-}
-
-#[derive(Clone)]
-pub struct SaveFile {
-    pub path: String,
-    pub file_name: String,
-    pub header: Option<Header>,
+    pub resolved_masterlist: Option<ResolvedMasterlist>,
+    pub nexus_key_check: Option<NexusKeyCheck>,
+    pub show_category_rules_window: bool,
+    pub config: Config,
+    pub mo2_instances: Vec<Mo2Instance>,
+    /**
+     * The mod manager last built and the game it was built for. Building one reads the
+     * whole Vortex state or MO2 instance, so it is only done again when the settings or
+     * the game change.
+     */
+    pub mod_manager_cache: Option<(String, Option<Arc<dyn ModManager>>)>,
 }
 
 #[derive(Clone)]
@@ -53,10 +184,18 @@ pub struct Character {
     pub saves: Vec<SaveFile>,
 }
 
-pub fn convert_plugins_to_skui(plugins: &Vec<String>) -> Vec<SkUIValue> {
+pub fn convert_plugins_to_skui(
+    plugins: &Vec<String>,
+    official: &OfficialPlugins,
+) -> Vec<SkUIValue> {
     let mut skui_plugins = Vec::new();
     for plugin in plugins {
-        let new_plugin = SkUIValue::new(plugin.as_str(), plugin.to_string(), UIValueType::Plugin);
+        let new_plugin = SkUIValue::new(
+            plugin.as_str(),
+            plugin.to_string(),
+            UIValueType::Plugin,
+            official,
+        );
         skui_plugins.push(new_plugin);
     }
     skui_plugins
@@ -84,6 +223,181 @@ fn group_saves_by_character(saves: &Vec<SaveFile>) -> HashMap<String, Character>
     character_map
 }
 
+fn load_mod_manager(
+    config: &Config,
+    game: &str,
+    data_path: Option<&PathBuf>,
+) -> Option<Arc<dyn ModManager>> {
+    match create_mod_manager(
+        config.mod_manager,
+        game,
+        config.mo2_instance_path.as_deref(),
+        data_path.map(|p| p.as_path()),
+    ) {
+        Ok(manager) => Some(manager),
+        Err(e) => {
+            tracing::error!("Could not load {}: {}", config.mod_manager, e);
+            None
+        }
+    }
+}
+
+/**
+ * The plugins active in the mod manager's current profile, which LOOT's conditions are
+ * checked against.
+ */
+fn get_profile_active_plugins(manager: Option<&dyn ModManager>) -> Option<Vec<String>> {
+    let manager = manager?;
+    let profile = manager.active_profile()?;
+    match manager.active_plugins(&profile) {
+        Ok(plugins) => Some(plugins),
+        Err(e) => {
+            tracing::error!("Could not read the plugins of profile {}: {}", profile, e);
+            None
+        }
+    }
+}
+
+/**
+ * Runs the category rules over the plugins, with what the mod manager and the masterlist
+ * know about them.
+ */
+pub fn categorize_plugins(
+    plugins: &[String],
+    settings: &CategorySettings,
+    manager: Option<&dyn ModManager>,
+    masterlist: Option<&MasterlistIndex>,
+) -> HashMap<String, String> {
+    let categorizer = Categorizer::new(&settings.rules);
+    plugins
+        .iter()
+        .filter_map(|name| {
+            let facts = PluginFacts {
+                name,
+                mo2_categories: manager
+                    .map(|manager| manager.mod_categories(name))
+                    .unwrap_or_default(),
+                loot_group: masterlist
+                    .and_then(|masterlist| masterlist.get(name))
+                    .and_then(|metadata| metadata.group),
+            };
+            let category = categorizer.categorize(&facts)?;
+            Some((name.to_lowercase(), category.to_string()))
+        })
+        .collect()
+}
+
+impl AppState {
+    /**
+     * Reads the installed plugins and profiles from the configured mod manager.
+     */
+    fn load_mod_manager_state(&mut self, game: &str) {
+        let manager = match &self.mod_manager_cache {
+            Some((cached_game, manager)) if cached_game == game => manager.clone(),
+            _ => {
+                let manager =
+                    load_mod_manager(&self.config, game, self.detail_state.data_path.as_ref());
+                self.mod_manager_cache = Some((game.to_string(), manager.clone()));
+                manager
+            }
+        };
+        self.detail_state.installed = manager
+            .as_deref()
+            .map(get_installed_plugins)
+            .unwrap_or_default();
+        self.detail_state.load_order_comparison = LoadOrderComparisonState {
+            profiles: manager
+                .as_ref()
+                .and_then(|m| m.profiles().ok())
+                .unwrap_or_default(),
+            ..Default::default()
+        };
+        self.detail_state.load_order_sort = LoadOrderSortState::default();
+        self.saves_state.save_folders = get_known_save_folders(manager.as_deref());
+        self.detail_state.mod_manager = manager;
+    }
+
+    fn game(&self) -> &'static str {
+        self.detail_state.game()
+    }
+
+    fn categorize_save_plugins(&mut self) {
+        let save_info = match &self.detail_state.save_info {
+            Some(save_info) => save_info,
+            None => return,
+        };
+        let plugins: Vec<String> = save_info
+            .plugin_info
+            .plugins
+            .iter()
+            .chain(&save_info.plugin_info.light_plugins)
+            .cloned()
+            .collect();
+        let masterlist = load_masterlist(self.game(), &self.detail_state.masterlist_source).ok();
+        self.detail_state.plugin_categories = categorize_plugins(
+            &plugins,
+            &self.detail_state.categories,
+            self.detail_state.mod_manager.as_deref(),
+            masterlist.as_deref(),
+        );
+    }
+
+    fn categories_changed(&mut self) {
+        if self.config.categories == self.detail_state.categories {
+            return;
+        }
+        if let Err(e) = save_config(&self.config) {
+            self.error = Some(format!("Could not save settings: {}", e));
+        }
+        self.detail_state.categories = self.config.categories.clone();
+        self.categorize_save_plugins();
+    }
+
+    fn settings_changed(&mut self) {
+        if let Err(e) = save_config(&self.config) {
+            self.error = Some(format!("Could not save settings: {}", e));
+        }
+        self.mod_manager_cache = None;
+        let game = self.game();
+        if self.detail_state.masterlist_source != self.config.masterlist_source {
+            self.detail_state.masterlist_source = self.config.masterlist_source.clone();
+            if self.detail_state.save_info.is_some() {
+                self.detail_state.mod_map =
+                    load_mod_map(game, &self.detail_state.masterlist_source);
+            }
+        }
+        self.load_mod_manager_state(game);
+        self.categorize_save_plugins();
+    }
+
+    /**
+     * Turns a JSON export, possibly edited by other tools, back into a save file.
+     */
+    fn import_json_save(&mut self) -> Result<(), Error> {
+        let json_path = match rfd::FileDialog::new()
+            .add_filter("JSON", &["json"])
+            .pick_file()
+        {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let save_info = load_saveinfo_from_json_path(&json_path)?;
+
+        let file_name = json_path.with_extension("ess");
+        let save_path = rfd::FileDialog::new()
+            .add_filter("Skyrim Save", &["ess"])
+            .set_directory(&self.saves_state.save_folder_path)
+            .set_file_name(&file_name.file_name().unwrap().to_string_lossy())
+            .save_file();
+
+        if let Some(save_path) = save_path {
+            write_saveinfo_to_path(&save_info, &save_path)?;
+            self.saves_state.refresh();
+        }
+        Ok(())
+    }
+}
+
 impl eframe::App for AppState {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         egui::SidePanel::left("side-panel").show(ctx, |ui| {
@@ -92,6 +406,18 @@ impl eframe::App for AppState {
                 //pop up a new window with a button to fix the resolution
                 self.show_resolution_window = true;
             }
+            if ui.button("Settings").clicked() {
+                self.show_settings_window = true;
+            }
+            if ui.button("Plugin categories").clicked() {
+                self.show_category_rules_window = true;
+            }
+            if ui.button("Import save from JSON").clicked() {
+                self.error = self.import_json_save().err().map(|e| e.to_string());
+            }
+            if let Some(error) = &self.error {
+                ui.colored_label(egui::Color32::from_rgb(200, 50, 50), error);
+            }
             if self.show_resolution_window {
                 egui::Window::new("Resolution Fixer")
                     .open(&mut self.show_resolution_window)
@@ -99,40 +425,116 @@ impl eframe::App for AppState {
                         ui.label("Hello from the new window!");
                     });
             }
-            if self.show_settings_window {
-                egui::Window::new("Settings")
-                    .open(&mut self.show_resolution_window)
-                    .show(ctx, |ui| {
-                        ui.label("Hello from the new window!");
-                    });
+            let game = self.game();
+            if self.show_settings_window
+                && SettingsWindow::new(
+                    &mut self.config,
+                    &self.mo2_instances,
+                    game,
+                    &mut self.resolved_masterlist,
+                    &mut self.nexus_key_check,
+                )
+                .show(ctx, &mut self.show_settings_window)
+            {
+                self.settings_changed();
+            }
+            if self.show_category_rules_window
+                && CategoryRulesWindow::new(&mut self.config.categories)
+                    .show(ctx, &mut self.show_category_rules_window)
+            {
+                self.categories_changed();
             }
 
+            let mut selected_save = None;
             SaveFileSelector::new(&mut self.saves_state).show(ui, |item| {
+                selected_save = Some(item);
+            });
+            if let Some(item) = selected_save {
                 self.detail_state.file_path = item.path.clone();
                 match load_saveinfo_from_path(self.detail_state.file_path.to_string()) {
                     Ok(save_file) => {
-                        if save_file.header.is_se {
-                            self.detail_state.mod_map = load_mod_map("skyrimse");
-                            self.detail_state.installed = load_installed("skyrimse");
-                        } else {
-                            self.detail_state.mod_map = load_mod_map("skyrim");
-                            self.detail_state.installed = load_installed("skyrim");
-                        }
+                        let game = match save_file.header.is_se {
+                            true => "skyrimse",
+                            false => "skyrim",
+                        };
+                        self.detail_state.mod_map =
+                            load_mod_map(game, &self.detail_state.masterlist_source);
+                        self.detail_state.nexus_searches = HashMap::new();
+                        self.load_mod_manager_state(game);
 
-                        let plugins = convert_plugins_to_skui(&save_file.plugin_info.plugins);
+                        // Light plugins are listed on their own in the save, but are
+                        // shown with the others
+                        let save_plugins: Vec<String> = save_file
+                            .plugin_info
+                            .plugins
+                            .iter()
+                            .chain(&save_file.plugin_info.light_plugins)
+                            .cloned()
+                            .collect();
+                        let plugins = convert_plugins_to_skui(
+                            &save_plugins,
+                            &self.detail_state.official_plugins,
+                        );
+
+                        self.detail_state.header_edit = HeaderEditState {
+                            player_name: save_file.header.player_name.clone(),
+                            save_number: save_file.header.save_number,
+                            status: None,
+                        };
+                        self.detail_state.conversion_status = None;
+                        self.detail_state.export_status = None;
+                        self.detail_state.selected_plugin = None;
+                        self.detail_state.plugin_header = None;
+                        let manager = self.detail_state.mod_manager.as_deref();
+                        let data_path = self.detail_state.data_path.as_deref();
+                        self.detail_state.dependency_graph =
+                            (manager.is_some() || data_path.is_some()).then(|| {
+                                build_dependency_graph_from_files(
+                                    &save_file.plugin_info.plugins,
+                                    &save_file.plugin_info.light_plugins,
+                                    |name| find_plugin_path(manager, data_path, name),
+                                )
+                            });
+                        self.detail_state.missing_creation_club =
+                            self.detail_state.data_path.as_ref().map(|data_path| {
+                                self.detail_state
+                                    .official_plugins
+                                    .missing_creation_club(&save_plugins, data_path)
+                                    .map_err(|e| e.to_string())
+                            });
+                        let environment = InstallEnvironment::new(
+                            self.detail_state.data_path.as_deref(),
+                            get_profile_active_plugins(self.detail_state.mod_manager.as_deref())
+                                .unwrap_or_else(|| save_plugins.clone()),
+                        );
+                        self.detail_state.loot_warnings =
+                            load_masterlist(game, &self.detail_state.masterlist_source)
+                                .map(|masterlist| {
+                                    get_save_warnings(&masterlist, &save_plugins, &environment)
+                                })
+                                .unwrap_or_default();
+                        self.detail_state.plugin_edit = PluginEditState {
+                            plugins: entries_from_names(&save_file.plugin_info.plugins),
+                            light_plugins: entries_from_names(&save_file.plugin_info.light_plugins),
+                            status: None,
+                        };
 
                         self.detail_state.plugins = Some(plugins);
                         self.detail_state.save_info = Some(save_file);
+                        self.categorize_save_plugins();
                     }
                     Err(e) => {
                         self.error = Some(e.to_string());
                     }
                 }
-            });
+            }
         });
 
         egui::CentralPanel::default().show(ctx, |ui| {
-            DetailView::new(&mut self.detail_state).show(ctx, ui);
+            DetailView::new(&mut self.detail_state).show(ctx, ui, |path| {
+                tracing::info!("Refreshing saves after writing {}", path);
+                self.saves_state.refresh();
+            });
         });
     }
 }
@@ -142,23 +544,55 @@ impl Default for AppState {
         let folder_path = get_default_save_folder();
         let saves = read_folder_contents(folder_path.as_str());
         let characters = group_saves_by_character(&saves);
+        let config = load_config();
+        let data_path = find_skyrim_data_path();
+        let mod_manager = load_mod_manager(&config, "skyrimse", data_path.as_ref());
+        let masterlist_source = config.masterlist_source.clone();
+        let official_plugins = OfficialPlugins::from_data_path(data_path.as_deref());
+        let categories = config.categories.clone();
 
         Self {
             show_settings_window: false,
+            resolved_masterlist: None,
+            nexus_key_check: None,
+            show_category_rules_window: false,
             show_resolution_window: false,
             folder_path,
             error: None,
+            config,
+            mo2_instances: find_instances(&[]),
+            mod_manager_cache: Some(("skyrimse".to_string(), mod_manager.clone())),
             detail_state: DetailState {
                 file_path: String::from(""),
                 save_info: None,
                 plugins: None,
                 mod_map: HashMap::new(),
-                installed: HashSet::new(),
+                nexus_searches: HashMap::new(),
+                installed: HashMap::new(),
+                mod_manager: None,
+                header_edit: HeaderEditState::default(),
+                plugin_edit: PluginEditState::default(),
+                conversion_status: None,
+                export_status: None,
+                data_path,
+                official_plugins,
+                missing_creation_club: None,
+                selected_plugin: None,
+                plugin_header: None,
+                dependency_graph: None,
+                show_dependency_graph: false,
+                loot_warnings: HashMap::new(),
+                categories,
+                plugin_categories: HashMap::new(),
+                masterlist_source,
+                load_order_comparison: LoadOrderComparisonState::default(),
+                load_order_sort: LoadOrderSortState::default(),
             },
             saves_state: SavesState {
                 save_file_list: read_folder_contents(get_default_save_folder().as_str()),
                 characters,
                 save_folder_path: get_default_save_folder(),
+                save_folders: get_known_save_folders(mod_manager.as_deref()),
                 selected_character: None,
             },
         }
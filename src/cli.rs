@@ -0,0 +1,424 @@
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+
+use serde::Serialize;
+
+use arcanaeum_core::{
+    load_mod_map,
+    load_order::official::OfficialPlugins,
+    load_saveinfo_from_path,
+    mod_manager::{create_mod_manager, get_installed_plugins, InstalledPlugin},
+    mod_search::vortex_scanner::Plugin,
+    save_file_parser::{header::Header, SaveInfo},
+    save_file_writer::{get_compression_type_name, with_compression_type},
+    utils::skyrim_se::find_skyrim_data_path,
+    write_saveinfo_copy,
+};
+
+use crate::{
+    config::load_config,
+    saves::read_folder_contents,
+    sktypes::skui_value::{plugin_type_from_name, PluginType},
+};
+
+const USAGE: &str = "Usage:
+    arcanaeum                                  Start the graphical interface
+    arcanaeum info <save> [--json]             Show the header of a save
+    arcanaeum plugins <save> [--missing] [--json]
+                                               List the plugins used by a save
+    arcanaeum list <folder> [--json]           List the saves in a folder
+    arcanaeum convert <save> <none|lz4>        Write a copy with another compression
+
+Exit codes: 0 on success, 1 on errors, 2 when --missing finds missing plugins.";
+
+#[derive(Serialize)]
+struct SaveSummary {
+    path: String,
+    game: String,
+    save_number: u32,
+    player_name: String,
+    player_level: u32,
+    player_race: String,
+    player_sex: String,
+    player_location: String,
+    game_date: String,
+    compression: String,
+    plugin_count: usize,
+    light_plugin_count: usize,
+}
+
+#[derive(Serialize)]
+struct PluginSummary {
+    index: String,
+    name: String,
+    kind: String,
+    status: String,
+    source: Option<String>,
+    urls: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct SaveListEntry {
+    file_name: String,
+    path: String,
+    player_name: String,
+    player_level: u32,
+    player_location: String,
+    save_number: u32,
+}
+
+struct Options {
+    positional: Vec<String>,
+    json: bool,
+    missing: bool,
+}
+
+fn parse_options(args: &[String]) -> Result<Options, Error> {
+    let mut options = Options {
+        positional: Vec::new(),
+        json: false,
+        missing: false,
+    };
+    for arg in args {
+        match arg.as_str() {
+            "--json" => options.json = true,
+            "--missing" => options.missing = true,
+            flag if flag.starts_with("--") => {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Unknown option {flag}"),
+                ))
+            }
+            _ => options.positional.push(arg.clone()),
+        }
+    }
+    Ok(options)
+}
+
+fn get_positional(options: &Options, index: usize, name: &str) -> Result<String, Error> {
+    options.positional.get(index).cloned().ok_or_else(|| {
+        Error::new(
+            ErrorKind::InvalidInput,
+            format!("Missing argument <{name}>"),
+        )
+    })
+}
+
+fn get_game(header: &Header) -> &'static str {
+    match header.is_se {
+        true => "skyrimse",
+        false => "skyrim",
+    }
+}
+
+fn print_json<T: Serialize>(value: &T) -> Result<(), Error> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+fn summarise_save(path: &str, save_info: &SaveInfo) -> SaveSummary {
+    let header = &save_info.header;
+    let game = match header.is_se {
+        true => "Skyrim Special Edition",
+        false => "Skyrim",
+    };
+    SaveSummary {
+        path: path.to_string(),
+        game: game.to_string(),
+        save_number: header.save_number,
+        player_name: header.player_name.clone(),
+        player_level: header.player_level,
+        player_race: header.player_race_editor_id.clone(),
+        player_sex: format!("{:?}", header.player_sex),
+        player_location: header.player_location.clone(),
+        game_date: header.game_date.clone(),
+        compression: get_compression_type_name(header.compression_type).to_string(),
+        plugin_count: save_info.plugin_info.plugins.len(),
+        light_plugin_count: save_info.plugin_info.light_plugins.len(),
+    }
+}
+
+fn summarise_plugin(
+    index: String,
+    name: &str,
+    installed: &HashMap<String, InstalledPlugin>,
+    mod_map: &HashMap<String, Plugin>,
+    official: &OfficialPlugins,
+) -> PluginSummary {
+    let mut urls = Vec::new();
+    let installed = installed.get(&name.to_lowercase());
+    let (kind, status) = match plugin_type_from_name(name.to_string(), official) {
+        PluginType::Native => ("Native", "Original Game File/DLC"),
+        PluginType::CreationClub => ("Creation Club", "Creation Club Mod"),
+        PluginType::NotAPlugin => ("Not a plugin", ""),
+        PluginType::Mod => {
+            if installed.is_some_and(|plugin| plugin.enabled) {
+                ("Mod", "Installed")
+            } else if installed.is_some() {
+                ("Mod", "Disabled")
+            } else if let Some(plugin) = mod_map.get(name) {
+                urls = plugin.urls.clone();
+                ("Mod", "Not Installed")
+            } else {
+                ("Mod", "Not Found")
+            }
+        }
+    };
+    PluginSummary {
+        index,
+        name: name.to_string(),
+        kind: kind.to_string(),
+        status: status.to_string(),
+        source: installed.map(|plugin| match &plugin.mod_name {
+            Some(mod_name) => mod_name.clone(),
+            None => plugin.profile.clone(),
+        }),
+        urls,
+    }
+}
+
+fn info(options: &Options) -> Result<i32, Error> {
+    let path = get_positional(options, 0, "save")?;
+    let save_info = load_saveinfo_from_path(path.clone())?;
+    let summary = summarise_save(&path, &save_info);
+
+    if options.json {
+        print_json(&summary)?;
+    } else {
+        println!("File path:          {}", summary.path);
+        println!("Game Type:          {}", summary.game);
+        println!("Save Number:        {}", summary.save_number);
+        println!("Character Name:     {}", summary.player_name);
+        println!("Character Level:    {}", summary.player_level);
+        println!("Character Sex:      {}", summary.player_sex);
+        println!("Character Race:     {}", summary.player_race);
+        println!("In Game Date:       {}", summary.game_date);
+        println!("Player Location:    {}", summary.player_location);
+        println!("Compression:        {}", summary.compression);
+        println!("Plugins:            {}", summary.plugin_count);
+        println!("Light Plugins:      {}", summary.light_plugin_count);
+    }
+    Ok(0)
+}
+
+fn plugins(options: &Options) -> Result<i32, Error> {
+    let path = get_positional(options, 0, "save")?;
+    let save_info = load_saveinfo_from_path(path)?;
+    let game = get_game(&save_info.header);
+    let config = load_config();
+    let mod_map = load_mod_map(game, &config.masterlist_source);
+    let installed = match create_mod_manager(
+        config.mod_manager,
+        game,
+        config.mo2_instance_path.as_deref(),
+        None,
+    ) {
+        Ok(manager) => get_installed_plugins(manager.as_ref()),
+        Err(e) => {
+            tracing::warn!("Could not load {}: {}", config.mod_manager, e);
+            HashMap::new()
+        }
+    };
+
+    let official = OfficialPlugins::from_data_path(find_skyrim_data_path().as_deref());
+
+    let plugin_info = &save_info.plugin_info;
+    let plugins = plugin_info
+        .plugins
+        .iter()
+        .enumerate()
+        .map(|(index, name)| (format!("{:02X}", index), name));
+    let light_plugins = plugin_info
+        .light_plugins
+        .iter()
+        .enumerate()
+        .map(|(index, name)| (format!("FE:{:03X}", index), name));
+    let summaries: Vec<PluginSummary> = plugins
+        .chain(light_plugins)
+        .map(|(index, name)| summarise_plugin(index, name, &installed, &mod_map, &official))
+        .filter(|p| !options.missing || (p.kind == "Mod" && p.status != "Installed"))
+        .collect();
+
+    if options.json {
+        print_json(&summaries)?;
+    } else {
+        for plugin in &summaries {
+            println!("{:<6}  {:<60} {}", plugin.index, plugin.name, plugin.status);
+            for url in &plugin.urls {
+                println!("     {}", url);
+            }
+        }
+    }
+
+    match options.missing && !summaries.is_empty() {
+        true => Ok(2),
+        false => Ok(0),
+    }
+}
+
+fn list(options: &Options) -> Result<i32, Error> {
+    let folder = get_positional(options, 0, "folder")?;
+    if !Path::new(&folder).is_dir() {
+        return Err(Error::new(
+            ErrorKind::NotFound,
+            format!("{folder} is not a folder"),
+        ));
+    }
+
+    let mut entries: Vec<SaveListEntry> = read_folder_contents(&folder)
+        .into_iter()
+        .filter_map(|save| {
+            let header = save.header?;
+            Some(SaveListEntry {
+                file_name: save.file_name,
+                path: save.path,
+                player_name: header.player_name,
+                player_level: header.player_level,
+                player_location: header.player_location,
+                save_number: header.save_number,
+            })
+        })
+        .collect();
+    entries.sort_by(|a, b| (&a.player_name, a.save_number).cmp(&(&b.player_name, b.save_number)));
+
+    if options.json {
+        print_json(&entries)?;
+    } else {
+        for entry in &entries {
+            println!(
+                "{:<24} {:>3}  {:<40} {}",
+                entry.player_name, entry.player_level, entry.player_location, entry.file_name
+            );
+        }
+    }
+    Ok(0)
+}
+
+fn convert(options: &Options) -> Result<i32, Error> {
+    let path = get_positional(options, 0, "save")?;
+    let compression_type = match get_positional(options, 1, "none|lz4")?.as_str() {
+        "none" => 0,
+        "lz4" => 2,
+        other => {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Unknown compression {other}, expected none or lz4"),
+            ))
+        }
+    };
+
+    let save_info = load_saveinfo_from_path(path.clone())?;
+    let converted = with_compression_type(&save_info, compression_type)?;
+    let suffix = match compression_type {
+        0 => "uncompressed",
+        _ => "lz4",
+    };
+    let written = write_saveinfo_copy(&path, &converted, suffix)?;
+    println!("{}", written.to_string_lossy());
+    Ok(0)
+}
+
+/**
+ * Runs a headless command and returns the process exit code.
+ */
+pub fn run(args: &[String]) -> i32 {
+    let (command, rest) = match args.split_first() {
+        Some((command, rest)) => (command.as_str(), rest),
+        None => ("help", args),
+    };
+
+    let result = parse_options(rest).and_then(|options| match command {
+        "info" => info(&options),
+        "plugins" => plugins(&options),
+        "list" => list(&options),
+        "convert" => convert(&options),
+        "help" | "--help" | "-h" => {
+            println!("{USAGE}");
+            Ok(0)
+        }
+        _ => Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("Unknown command {command}\n\n{USAGE}"),
+        )),
+    });
+
+    match result {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("{e}");
+            1
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_options_separates_flags() {
+        let options = parse_options(&args(&["save.ess", "--missing", "--json"])).unwrap();
+        assert_eq!(options.positional, vec!["save.ess"]);
+        assert!(options.json);
+        assert!(options.missing);
+    }
+
+    #[test]
+    fn parse_options_rejects_unknown_flags() {
+        assert!(parse_options(&args(&["--verbose"])).is_err());
+    }
+
+    #[test]
+    fn unknown_commands_fail() {
+        assert_eq!(run(&args(&["frobnicate"])), 1);
+        assert_eq!(run(&args(&["info"])), 1);
+    }
+
+    #[test]
+    fn native_plugins_are_never_missing() {
+        let summary = summarise_plugin(
+            "00".to_string(),
+            "Skyrim.esm",
+            &HashMap::new(),
+            &HashMap::new(),
+            &OfficialPlugins::default(),
+        );
+        assert_eq!(summary.kind, "Native");
+
+        let summary = summarise_plugin(
+            "01".to_string(),
+            "SkyUI_SE.esp",
+            &HashMap::new(),
+            &HashMap::new(),
+            &OfficialPlugins::default(),
+        );
+        assert_eq!(summary.status, "Not Found");
+    }
+
+    #[test]
+    fn disabled_plugins_are_told_apart() {
+        let installed = HashMap::from([(
+            "skyui_se.esp".to_string(),
+            InstalledPlugin {
+                name: "SkyUI_SE.esp".to_string(),
+                profile: "Survival".to_string(),
+                mod_name: None,
+                enabled: false,
+            },
+        )]);
+        let summary = summarise_plugin(
+            "01".to_string(),
+            "SKYUI_SE.esp",
+            &installed,
+            &HashMap::new(),
+            &OfficialPlugins::default(),
+        );
+        assert_eq!(summary.status, "Disabled");
+        assert_eq!(summary.source.as_deref(), Some("Survival"));
+    }
+}
@@ -0,0 +1,160 @@
+use eframe::egui::{self, Color32};
+
+use arcanaeum_core::categories::{
+    validate_rule, Category, CategoryRule, CategorySettings, RuleMatch,
+};
+
+/**
+ * Edits the plugin categories and the rules that put plugins in them.
+ */
+pub struct CategoryRulesWindow<'a> {
+    settings: &'a mut CategorySettings,
+}
+
+impl<'a> CategoryRulesWindow<'a> {
+    pub fn new(settings: &'a mut CategorySettings) -> CategoryRulesWindow<'a> {
+        CategoryRulesWindow { settings }
+    }
+
+    /**
+     * Returns true when the changes should be applied: right after a click, and once a
+     * text field loses focus rather than on every key press.
+     */
+    pub fn show(&mut self, ctx: &egui::Context, open: &mut bool) -> bool {
+        let mut changed = false;
+        egui::Window::new("Plugin Categories")
+            .open(open)
+            .show(ctx, |ui| {
+                changed |= self.show_categories(ui);
+                ui.separator();
+                changed |= self.show_rules(ui);
+            });
+        changed
+    }
+
+    fn show_categories(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut changed = false;
+        let mut removed = None;
+        let mut renamed = None;
+        ui.strong("Categories");
+        let rules = &self.settings.rules;
+        egui::Grid::new("plugin_categories").show(ui, |ui| {
+            for (index, category) in self.settings.categories.iter_mut().enumerate() {
+                changed |= ui.color_edit_button_srgb(&mut category.color).changed();
+                // The name from before the edit is kept to rename the category in the rules
+                let response = ui.text_edit_singleline(&mut category.name);
+                if response.gained_focus() {
+                    ui.data_mut(|data| data.insert_temp(response.id, category.name.clone()));
+                }
+                if response.lost_focus() {
+                    if let Some(old_name) = ui.data_mut(|data| data.get_temp::<String>(response.id))
+                    {
+                        renamed = Some((old_name, category.name.clone()));
+                    }
+                    changed = true;
+                }
+
+                let used_by = rules.iter().filter(|r| r.category == category.name).count();
+                if ui
+                    .add_enabled(used_by == 0, egui::Button::new("Remove"))
+                    .on_disabled_hover_text(format!(
+                        "Used by {} rules, change or remove them first",
+                        used_by
+                    ))
+                    .clicked()
+                {
+                    removed = Some(index);
+                }
+                ui.end_row();
+            }
+        });
+        if let Some((old_name, new_name)) = renamed {
+            for rule in self
+                .settings
+                .rules
+                .iter_mut()
+                .filter(|rule| rule.category == old_name)
+            {
+                rule.category = new_name.clone();
+            }
+        }
+        // Checked again as a rename may only have reached the rules this frame
+        let settings = &mut self.settings;
+        if let Some(index) = removed.filter(|index| {
+            let name = &settings.categories[*index].name;
+            !settings.rules.iter().any(|rule| &rule.category == name)
+        }) {
+            settings.categories.remove(index);
+            changed = true;
+        }
+        if ui.button("Add category").clicked() {
+            self.settings.categories.push(Category {
+                name: "New category".to_string(),
+                color: [200, 200, 200],
+            });
+            changed = true;
+        }
+        changed
+    }
+
+    fn show_rules(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut changed = false;
+        let mut removed = None;
+        ui.strong("Rules");
+        ui.label("The first rule matching a plugin picks its category");
+        let categories: Vec<String> = self
+            .settings
+            .categories
+            .iter()
+            .map(|c| c.name.clone())
+            .collect();
+        egui::Grid::new("plugin_category_rules").show(ui, |ui| {
+            for (index, rule) in self.settings.rules.iter_mut().enumerate() {
+                egui::ComboBox::from_id_source(("rule_kind", index))
+                    .selected_text(rule.matches.kind())
+                    .show_ui(ui, |ui| {
+                        for kind in RuleMatch::KINDS {
+                            if ui
+                                .selectable_label(rule.matches.kind() == kind, kind)
+                                .clicked()
+                            {
+                                rule.matches = rule.matches.with_kind(kind);
+                                changed = true;
+                            }
+                        }
+                    });
+                changed |= ui
+                    .text_edit_singleline(rule.matches.pattern_mut())
+                    .lost_focus();
+                egui::ComboBox::from_id_source(("rule_category", index))
+                    .selected_text(rule.category.as_str())
+                    .show_ui(ui, |ui| {
+                        for category in &categories {
+                            changed |= ui
+                                .selectable_value(&mut rule.category, category.clone(), category)
+                                .changed();
+                        }
+                    });
+                if ui.button("Remove").clicked() {
+                    removed = Some(index);
+                }
+                if let Err(e) = validate_rule(rule) {
+                    ui.colored_label(Color32::from_rgb(200, 50, 50), e.to_string());
+                }
+                ui.end_row();
+            }
+        });
+        if let Some(index) = removed {
+            self.settings.rules.remove(index);
+            changed = true;
+        }
+        if ui.button("Add rule").clicked() {
+            self.settings.rules.push(CategoryRule {
+                matches: RuleMatch::Glob(String::new()),
+                category: categories.first().cloned().unwrap_or_default(),
+            });
+            changed = true;
+        }
+        changed
+    }
+}
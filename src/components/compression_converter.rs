@@ -0,0 +1,93 @@
+use std::io::{Error, ErrorKind};
+use std::path::PathBuf;
+
+use eframe::egui::{self, Color32};
+
+use arcanaeum_core::{
+    save_file_writer::{get_compression_type_name, with_compression_type},
+    write_saveinfo_copy,
+};
+
+use crate::app::DetailState;
+
+/**
+ * Writes a copy of an SE save with a different compression type, for tools that can only
+ * read uncompressed saves, or to shrink old uncompressed saves.
+ */
+pub struct CompressionConverter<'a> {
+    state: &'a mut DetailState,
+}
+
+impl<'a> CompressionConverter<'a> {
+    pub fn new(state: &'a mut DetailState) -> CompressionConverter<'a> {
+        CompressionConverter { state }
+    }
+
+    fn write_converted_save(&self, compression_type: u16) -> Result<PathBuf, Error> {
+        let save_info = match &self.state.save_info {
+            Some(save_info) => save_info,
+            None => return Err(Error::new(ErrorKind::NotFound, "No save selected")),
+        };
+
+        let converted = with_compression_type(save_info, compression_type)?;
+        let suffix = match compression_type {
+            0 => "uncompressed",
+            _ => "lz4",
+        };
+        write_saveinfo_copy(&self.state.file_path, &converted, suffix)
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui, save_written: impl FnOnce(String)) {
+        let current = match &self.state.save_info {
+            Some(save_info) if save_info.header.is_se => save_info.header.compression_type,
+            _ => return,
+        };
+
+        let mut written = None;
+        egui::CollapsingHeader::new("Convert Compression").show(ui, |ui| {
+            ui.label(format!(
+                "Compression: {}",
+                get_compression_type_name(current)
+            ));
+
+            let mut target = None;
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(current != 0, egui::Button::new("Write uncompressed copy"))
+                    .clicked()
+                {
+                    target = Some(0);
+                }
+                if ui
+                    .add_enabled(current != 2, egui::Button::new("Write LZ4 compressed copy"))
+                    .clicked()
+                {
+                    target = Some(2);
+                }
+            });
+
+            if let Some(compression_type) = target {
+                match self.write_converted_save(compression_type) {
+                    Ok(path) => {
+                        let path = path.to_string_lossy().to_string();
+                        tracing::info!("Wrote converted save to {}", path);
+                        self.state.conversion_status = Some(format!("Written to {}", path));
+                        written = Some(path);
+                    }
+                    Err(e) => {
+                        tracing::error!("Could not convert save: {}", e);
+                        self.state.conversion_status = Some(e.to_string());
+                    }
+                }
+            }
+
+            if let Some(status) = &self.state.conversion_status {
+                ui.colored_label(Color32::from_rgb(200, 200, 50), status);
+            }
+        });
+
+        if let Some(path) = written {
+            save_written(path);
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use eframe::egui::{self, Color32};
+
+use crate::app::DetailState;
+
+/**
+ * Lists the Creation Club content the save was made with that the game is missing, which
+ * the game drops without a word when the save is loaded.
+ */
+pub struct CreationClubView<'a> {
+    state: &'a mut DetailState,
+}
+
+impl<'a> CreationClubView<'a> {
+    pub fn new(state: &'a mut DetailState) -> CreationClubView<'a> {
+        CreationClubView { state }
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui) {
+        if self.state.save_info.is_none() {
+            return;
+        }
+
+        egui::CollapsingHeader::new("Creation Club").show(ui, |ui| {
+            match &self.state.missing_creation_club {
+                Some(Ok(missing)) if missing.is_empty() => {
+                    ui.colored_label(
+                        Color32::from_rgb(50, 200, 50),
+                        "All Creation Club content the save uses is installed",
+                    );
+                }
+                Some(Ok(missing)) => {
+                    ui.label(format!(
+                        "{} Creation Club plugins the save uses are not installed. They come with the Anniversary Edition or have to be bought separately.",
+                        missing.len()
+                    ));
+                    for name in missing {
+                        ui.colored_label(Color32::from_rgb(200, 50, 50), name);
+                    }
+                }
+                Some(Err(e)) => {
+                    ui.colored_label(Color32::from_rgb(200, 50, 50), e);
+                }
+                None => {
+                    ui.label("The game's Data folder was not found");
+                }
+            }
+        });
+    }
+}
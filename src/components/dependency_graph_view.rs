@@ -0,0 +1,66 @@
+use eframe::egui::{self, Color32};
+
+use arcanaeum_core::load_order::dependency_graph::DependencyGraph;
+
+/**
+ * Masters of masters are nested, deep chains are rare but cycles between broken plugins
+ * are possible, so the tree is cut off at this depth.
+ */
+const MAX_DEPTH: usize = 16;
+
+fn show_masters(ui: &mut egui::Ui, graph: &DependencyGraph, name: &str, path: &mut Vec<String>) {
+    let node = match graph.get(name) {
+        Some(node) => node,
+        None => {
+            ui.colored_label(
+                Color32::from_rgb(200, 50, 50),
+                format!("{} (not in save)", name),
+            );
+            return;
+        }
+    };
+
+    if node.masters.is_empty() || path.len() >= MAX_DEPTH || path.contains(&node.name) {
+        ui.label(&node.name);
+        return;
+    }
+
+    path.push(node.name.clone());
+    egui::CollapsingHeader::new(&node.name)
+        .id_source(path.join("/"))
+        .show(ui, |ui| {
+            for problem in &node.problems {
+                ui.colored_label(Color32::from_rgb(230, 140, 30), problem.to_string());
+            }
+            for master in &node.masters {
+                show_masters(ui, graph, master, path);
+            }
+        });
+    path.pop();
+}
+
+/**
+ * Window with the master dependency tree of every plugin in the save.
+ */
+pub struct DependencyGraphView<'a> {
+    graph: &'a DependencyGraph,
+}
+
+impl<'a> DependencyGraphView<'a> {
+    pub fn new(graph: &'a DependencyGraph) -> DependencyGraphView<'a> {
+        DependencyGraphView { graph }
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, open: &mut bool) {
+        egui::Window::new("Dependency Graph")
+            .open(open)
+            .default_height(500.)
+            .show(ctx, |ui| {
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for node in &self.graph.nodes {
+                        show_masters(ui, self.graph, &node.name, &mut Vec::new());
+                    }
+                });
+            });
+    }
+}
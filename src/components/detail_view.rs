@@ -1,6 +1,24 @@
-use eframe::egui::{self, Ui, Color32};
+use eframe::egui::{self, Color32, RichText, Ui};
 
-use crate::{sktypes::{types::SkTypeReadable, self}, app::DetailState};
+use std::collections::HashMap;
+use std::path::Path;
+
+use arcanaeum_core::categories::{Category, CategorySettings};
+use arcanaeum_core::loot::masterlist::MessageType;
+use arcanaeum_core::mod_manager::{get_plugin_state, PluginState};
+use arcanaeum_core::write_saveinfo_json_to_path;
+
+use crate::{
+    app::DetailState,
+    sktypes::{self, skui_value::SkUIValue, types::SkTypeReadable},
+};
+
+use super::{
+    compression_converter::CompressionConverter, creation_club::CreationClubView,
+    dependency_graph_view::DependencyGraphView, header_editor::HeaderEditor,
+    load_order_comparison::LoadOrderComparisonView, load_order_sort::LoadOrderSortView,
+    nexus_search::NexusSearch, plugin_details::PluginDetails, plugin_list_editor::PluginListEditor,
+};
 
 fn label_line(ui: &mut Ui, name: &str, value: &str) {
     ui.label(name);
@@ -8,26 +26,102 @@ fn label_line(ui: &mut Ui, name: &str, value: &str) {
     ui.end_row();
 }
 
+fn category_color(category: &Category) -> Color32 {
+    let [r, g, b] = category.color;
+    Color32::from_rgb(r, g, b)
+}
+
+/**
+ * The plugins in the order their categories are listed, keeping the load order within a
+ * category. Plugins without one come last.
+ */
+fn group_by_category<'a>(
+    plugins: &'a [SkUIValue],
+    plugin_categories: &HashMap<String, String>,
+    settings: &'a CategorySettings,
+) -> Vec<(Option<&'a Category>, &'a SkUIValue)> {
+    let mut grouped: Vec<(Option<usize>, &SkUIValue)> = plugins
+        .iter()
+        .map(|plugin| {
+            let position = plugin_categories
+                .get(&plugin.get_name().to_lowercase())
+                .and_then(|name| settings.categories.iter().position(|c| &c.name == name));
+            (position, plugin)
+        })
+        .collect();
+    grouped.sort_by_key(|(position, _)| position.unwrap_or(usize::MAX));
+    grouped
+        .into_iter()
+        .map(|(position, plugin)| (position.map(|p| &settings.categories[p]), plugin))
+        .collect()
+}
+
 //set column width const
 const COL_WIDTH: f32 = 400.0;
 
 pub struct DetailView<'a> {
-    state: &'a DetailState,
+    state: &'a mut DetailState,
 }
 
 impl <'a> DetailView <'a> {
-    pub fn new(state: &'a DetailState) -> DetailView<'a> {
+    pub fn new(state: &'a mut DetailState) -> DetailView<'a> {
         DetailView {
             state
         }
     }
 
-    pub fn show(&mut self,ctx: &egui::Context , _ui: &mut egui::Ui) {
+    fn export_json(&mut self) {
+        let save_info = match &self.state.save_info {
+            Some(save_info) => save_info,
+            None => return,
+        };
+
+        let save_path = Path::new(&self.state.file_path);
+        let json_path = save_path.with_extension("json");
+        let file_name = json_path.file_name().unwrap().to_string_lossy();
+        let mut dialog = rfd::FileDialog::new()
+            .add_filter("JSON", &["json"])
+            .set_file_name(&file_name);
+        if let Some(folder) = save_path.parent() {
+            dialog = dialog.set_directory(folder);
+        }
+
+        if let Some(path) = dialog.save_file() {
+            self.state.export_status = match write_saveinfo_json_to_path(save_info, &path) {
+                Ok(_) => Some(format!("Exported to {}", path.to_string_lossy())),
+                Err(e) => {
+                    tracing::error!("Could not export JSON: {}", e);
+                    Some(e.to_string())
+                }
+            };
+        }
+    }
+
+    pub fn show(&mut self,ctx: &egui::Context , _ui: &mut egui::Ui, save_written: impl FnOnce(String)) {
         egui::TopBottomPanel::top("top-panel").show(ctx, |ui| {
             ui.heading("Selected Save File");
             ui.label("File path:");
             ui.label(&self.state.file_path);
+            if self.state.save_info.is_some() {
+                ui.horizontal(|ui| {
+                    if ui.button("Export as JSON").clicked() {
+                        self.export_json();
+                    }
+                    if let Some(status) = &self.state.export_status {
+                        ui.label(status);
+                    }
+                });
+            }
         });
+        PluginDetails::new(self.state).show(ctx);
+
+        if let Some(graph) = &self.state.dependency_graph {
+            DependencyGraphView::new(graph).show(ctx, &mut self.state.show_dependency_graph);
+        }
+
+        let mut written = None;
+        let mut clicked_plugin = None;
+        let mut searched_plugin = None;
         egui::CentralPanel::default().show(ctx, |ui| {
             egui::Grid::new("values")
             .striped(true)
@@ -68,10 +162,24 @@ impl <'a> DetailView <'a> {
                 }
             });
 
+            HeaderEditor::new(self.state).show(ui, |path| written = Some(path));
+            PluginListEditor::new(self.state).show(ui, |path| written = Some(path));
+            CompressionConverter::new(self.state).show(ui, |path| written = Some(path));
+            LoadOrderComparisonView::new(self.state).show(ui);
+            LoadOrderSortView::new(self.state).show(ui);
+            CreationClubView::new(self.state).show(ui);
+
             if let Some(si) = &self.state.save_info {
                 if si.plugin_info.plugin_count > 0 {
                     ui.separator();
-                    ui.heading("Plugins");
+                    ui.horizontal(|ui| {
+                        ui.heading("Plugins");
+                        if self.state.dependency_graph.is_some()
+                            && ui.button("Dependency Graph").clicked()
+                        {
+                            self.state.show_dependency_graph = true;
+                        }
+                    });
                     ui.separator();
                 }
             }
@@ -84,38 +192,149 @@ impl <'a> DetailView <'a> {
                 .max_col_width(COL_WIDTH)
                 .show(ui, |ui| {
                     if let Some(si) = &self.state.plugins {
-                        for value_entry in si {
-                            ui.label(value_entry.get_name());
-                            match value_entry.plugin_type {
-                                sktypes::skui_value::PluginType::Native => {
-                                    ui.label("Original Game File/DLC");
-                                }
-                                sktypes::skui_value::PluginType::CreationClub => {
-                                    ui.label("Creation Club Mod");
+                        let mut current_category = None;
+                        for (category, value_entry) in group_by_category(si, &self.state.plugin_categories, &self.state.categories) {
+                            if category != current_category {
+                                match category {
+                                    Some(category) => {
+                                        ui.label(RichText::new(&category.name).strong().color(category_color(category)));
+                                    }
+                                    None => {
+                                        ui.strong("Uncategorized");
+                                    }
                                 }
-                                sktypes::skui_value::PluginType::Mod => {
-                                    let key = &value_entry.get_value_string();
+                                ui.end_row();
+                                current_category = category;
+                            }
+                            let name = value_entry.get_name();
+                            let selected = self.state.selected_plugin.as_ref() == Some(&name);
+                            let text = match category {
+                                Some(category) => RichText::new(name.as_str()).color(category_color(category)),
+                                None => RichText::new(name.as_str()),
+                            };
+                            if ui.selectable_label(selected, text).clicked() {
+                                clicked_plugin = Some(name.clone());
+                            }
+                            // One cell for the status and everything reported about the plugin, so the
+                            // columns stay lined up
+                            ui.vertical(|ui| {
+                                match value_entry.plugin_type {
+                                    sktypes::skui_value::PluginType::Native => {
+                                        ui.label("Original Game File/DLC");
+                                    }
+                                    sktypes::skui_value::PluginType::CreationClub => {
+                                        ui.label("Creation Club Mod");
+                                    }
+                                    sktypes::skui_value::PluginType::Mod => {
+                                        let key = &value_entry.get_value_string();
 
-                                    if self.state.installed.contains(key) {
-                                        ui.colored_label(
-                                            Color32::from_rgb(50, 200, 50),
-                                            "Installed",
-                                        );
-                                    } else if self.state.mod_map.contains_key(key) {
-                                        let value = self.state.mod_map.get(key).unwrap();
-                                        for l in value.urls.clone() {
-                                            ui.hyperlink(l.as_str());
-                                            ui.end_row();
+                                        let source = self
+                                            .state
+                                            .installed
+                                            .get(&key.to_lowercase())
+                                            .map(|installed| match &self.state.mod_manager {
+                                                Some(manager) => installed.source(manager.kind()),
+                                                None => installed.profile.clone(),
+                                            });
+
+                                        match get_plugin_state(&self.state.installed, key) {
+                                            PluginState::Enabled => {
+                                                let details = self
+                                                    .state
+                                                    .mod_manager
+                                                    .as_ref()
+                                                    .and_then(|manager| manager.mod_details(key));
+                                                let provided_by = self
+                                                    .state
+                                                    .installed
+                                                    .get(&key.to_lowercase())
+                                                    .and_then(|installed| installed.mod_name.clone());
+                                                let installed_color = Color32::from_rgb(50, 200, 50);
+                                                ui.horizontal(|ui| match (details, provided_by) {
+                                                    (Some(details), _) => {
+                                                        ui.colored_label(installed_color, "Installed, provided by");
+                                                        let title = match &details.version {
+                                                            Some(version) => format!("{} {}", details.name, version),
+                                                            None => details.name.clone(),
+                                                        };
+                                                        match &details.url {
+                                                            Some(url) => {
+                                                                ui.hyperlink_to(title, url);
+                                                            }
+                                                            None => {
+                                                                ui.label(title);
+                                                            }
+                                                        }
+                                                    }
+                                                    (None, Some(mod_name)) => {
+                                                        ui.colored_label(
+                                                            installed_color,
+                                                            format!("Installed, provided by {}", mod_name),
+                                                        );
+                                                    }
+                                                    (None, None) => {
+                                                        ui.colored_label(
+                                                            installed_color,
+                                                            format!("Installed from {}", source.unwrap_or_default()),
+                                                        );
+                                                    }
+                                                });
+                                            }
+                                            PluginState::Disabled => {
+                                                ui.colored_label(
+                                                    Color32::from_rgb(200, 200, 50),
+                                                    format!("Disabled in {}", source.unwrap_or_default()),
+                                                );
+                                            }
+                                            PluginState::Absent => {
+                                                if let Some(value) = self.state.mod_map.get(key) {
+                                                    for l in value.urls.clone() {
+                                                        ui.hyperlink(l.as_str());
+                                                    }
+                                                } else {
+                                                    ui.vertical(|ui| {
+                                                        ui.horizontal(|ui| {
+                                                            ui.colored_label(
+                                                                Color32::from_rgb(200, 50, 50),
+                                                                "Not Found",
+                                                            );
+                                                            if ui.button("Search Nexus").clicked() {
+                                                                searched_plugin = Some(key.clone());
+                                                            }
+                                                        });
+                                                        if let Some(search) = self.state.nexus_searches.get(key) {
+                                                            search.show(ui);
+                                                        }
+                                                    });
+                                                }
+                                            }
                                         }
-                                    } else {
+                                    }
+                                    sktypes::skui_value::PluginType::NotAPlugin => {}
+                                }
+                                if let Some(node) = self
+                                    .state
+                                    .dependency_graph
+                                    .as_ref()
+                                    .and_then(|graph| graph.get(&name))
+                                {
+                                    for problem in &node.problems {
                                         ui.colored_label(
-                                            Color32::from_rgb(200, 50, 50),
-                                            "Not Found",
+                                            Color32::from_rgb(230, 140, 30),
+                                            problem.to_string(),
                                         );
                                     }
                                 }
-                                sktypes::skui_value::PluginType::NotAPlugin => {}
-                            }
+                                if let Some(warnings) = self.state.loot_warnings.get(&name.to_lowercase()) {
+                                    for warning in warnings {
+                                        let color = match warning.level {
+                                            MessageType::Error => Color32::from_rgb(200, 50, 50),
+                                            _ => Color32::from_rgb(200, 200, 50),
+                                        };
+                                        ui.colored_label(color, format!("LOOT: {}", warning));
+                                    }
+                                }
+                            });
                             ui.end_row();
                         }
                     }
@@ -124,5 +343,22 @@ impl <'a> DetailView <'a> {
 
 
         });
+
+        if let Some(name) = searched_plugin {
+            let game = match &self.state.save_info {
+                Some(save_info) if !save_info.header.is_se => "skyrim",
+                _ => "skyrimse",
+            };
+            let search = NexusSearch::start(ctx.clone(), game, &name);
+            self.state.nexus_searches.insert(name, search);
+        }
+
+        if let Some(name) = clicked_plugin {
+            PluginDetails::new(self.state).select(&name);
+        }
+
+        if let Some(path) = written {
+            save_written(path);
+        }
     }
 }
\ No newline at end of file
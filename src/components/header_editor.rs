@@ -0,0 +1,89 @@
+use std::io::{Error, ErrorKind};
+use std::path::PathBuf;
+
+use eframe::egui::{self, Color32};
+
+use arcanaeum_core::write_saveinfo_copy;
+
+use crate::app::DetailState;
+
+/**
+ * Edits the header fields that are only used for display, like the name shown in the
+ * load menu. Edits are always written to a new file next to the original.
+ */
+pub struct HeaderEditor<'a> {
+    state: &'a mut DetailState,
+}
+
+impl<'a> HeaderEditor<'a> {
+    pub fn new(state: &'a mut DetailState) -> HeaderEditor<'a> {
+        HeaderEditor { state }
+    }
+
+    fn write_edited_save(&self) -> Result<PathBuf, Error> {
+        let save_info = match &self.state.save_info {
+            Some(save_info) => save_info,
+            None => return Err(Error::new(ErrorKind::NotFound, "No save selected")),
+        };
+
+        let player_name = self.state.header_edit.player_name.trim();
+        if player_name.is_empty() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Character name cannot be empty",
+            ));
+        }
+
+        let mut edited = save_info.clone();
+        edited.header.player_name = player_name.to_string();
+        edited.header.save_number = self.state.header_edit.save_number;
+
+        write_saveinfo_copy(&self.state.file_path, &edited, "edited")
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui, save_written: impl FnOnce(String)) {
+        if self.state.save_info.is_none() {
+            return;
+        }
+
+        let mut written = None;
+        egui::CollapsingHeader::new("Edit Save Header").show(ui, |ui| {
+            egui::Grid::new("header_editor")
+                .min_row_height(22.)
+                .show(ui, |ui| {
+                    ui.label("Character Name");
+                    ui.text_edit_singleline(&mut self.state.header_edit.player_name);
+                    ui.end_row();
+
+                    ui.label("Save Number");
+                    ui.add(egui::DragValue::new(
+                        &mut self.state.header_edit.save_number,
+                    ));
+                    ui.end_row();
+                });
+
+            if ui.button("Write as new save").clicked() {
+                match self.write_edited_save() {
+                    Ok(path) => {
+                        let path = path.to_string_lossy().to_string();
+                        tracing::info!("Wrote edited save to {}", path);
+                        self.state.header_edit.status = Some(format!("Written to {}", path));
+                        written = Some(path);
+                    }
+                    Err(e) => {
+                        tracing::error!("Could not write edited save: {}", e);
+                        self.state.header_edit.status = Some(e.to_string());
+                    }
+                }
+            }
+
+            if let Some(status) = &self.state.header_edit.status {
+                ui.colored_label(Color32::from_rgb(200, 200, 50), status);
+            }
+        });
+
+        if let Some(path) = written {
+            save_written(path);
+        }
+    }
+}
@@ -0,0 +1,251 @@
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+
+use eframe::egui::{self, Color32};
+
+use arcanaeum_core::{
+    load_order::{
+        comparison::{
+            compare_load_orders, light_plugins_last, LoadOrderChange, LoadOrderComparison,
+        },
+        profile::{
+            get_active_plugins, read_profile_load_order, restore_load_order,
+            write_profile_load_order,
+        },
+    },
+    plugin_file::read_plugin_header_from_data_folder,
+    save_file_parser::SaveInfo,
+};
+
+use crate::app::DetailState;
+
+/**
+ * Shows how the load order of a mod manager profile differs from the one the save was
+ * made with, to tell which plugins were added, removed or moved since.
+ */
+pub struct LoadOrderComparisonView<'a> {
+    state: &'a mut DetailState,
+}
+
+fn is_light(save_info: &SaveInfo, data_path: Option<&Path>, name: &str) -> bool {
+    if save_info
+        .plugin_info
+        .light_plugins
+        .iter()
+        .any(|p| p.eq_ignore_ascii_case(name))
+    {
+        return true;
+    }
+    match data_path.map(|data_path| read_plugin_header_from_data_folder(data_path, name)) {
+        Some(Ok(header)) => header.is_light(),
+        _ => Path::new(name)
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("esl")),
+    }
+}
+
+impl<'a> LoadOrderComparisonView<'a> {
+    pub fn new(state: &'a mut DetailState) -> LoadOrderComparisonView<'a> {
+        LoadOrderComparisonView { state }
+    }
+
+    fn compare_with(&self, profile_path: &Path) -> Result<LoadOrderComparison, Error> {
+        let save_info = match &self.state.save_info {
+            Some(save_info) => save_info,
+            None => return Err(Error::new(ErrorKind::NotFound, "No save selected")),
+        };
+
+        let game = self.state.game();
+        let profile = read_profile_load_order(profile_path, game)?;
+        let active = light_plugins_last(&get_active_plugins(&profile, game), |name| {
+            is_light(save_info, self.state.data_path.as_deref(), name)
+        });
+        Ok(compare_load_orders(
+            &Self::saved_plugins(save_info),
+            &active,
+        ))
+    }
+
+    fn saved_plugins(save_info: &SaveInfo) -> Vec<String> {
+        save_info
+            .plugin_info
+            .plugins
+            .iter()
+            .chain(save_info.plugin_info.light_plugins.iter())
+            .cloned()
+            .collect()
+    }
+
+    /**
+     * Writes the save's load order into the profile, keeping backups of the old files. A
+     * profile without a plugins.txt yet is treated as empty.
+     */
+    fn restore_into(&self, profile_path: &Path) -> Result<Vec<PathBuf>, Error> {
+        let save_info = match &self.state.save_info {
+            Some(save_info) => save_info,
+            None => return Err(Error::new(ErrorKind::NotFound, "No save selected")),
+        };
+        let game = self.state.game();
+        let profile = match read_profile_load_order(profile_path, game) {
+            Ok(profile) => profile,
+            Err(e) if e.kind() == ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        let restored = restore_load_order(&profile, &Self::saved_plugins(save_info));
+        write_profile_load_order(profile_path, &restored, game)
+    }
+
+    pub fn select_profile(&mut self, profile_path: PathBuf) {
+        let result = self.compare_with(&profile_path).map_err(|e| {
+            tracing::error!("Could not compare with {:?}: {}", profile_path, e);
+            e.to_string()
+        });
+        let comparison = &mut self.state.load_order_comparison;
+        comparison.profile_path = Some(profile_path);
+        comparison.result = Some(result);
+        comparison.restore_status = None;
+        // A sort proposal belongs to the profile it was made for
+        self.state.load_order_sort.proposal = None;
+    }
+
+    fn restore(&mut self) {
+        let profile_path = match &self.state.load_order_comparison.profile_path {
+            Some(profile_path) => profile_path.clone(),
+            None => return,
+        };
+        let status = match self.restore_into(&profile_path) {
+            Ok(backups) => {
+                tracing::info!("Restored load order into {:?}", profile_path);
+                match backups.is_empty() {
+                    true => "Load order restored".to_string(),
+                    false => format!(
+                        "Load order restored, backups: {}",
+                        backups
+                            .iter()
+                            .map(|b| b.to_string_lossy())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                }
+            }
+            Err(e) => {
+                tracing::error!("Could not restore load order: {}", e);
+                e.to_string()
+            }
+        };
+        self.select_profile(profile_path);
+        self.state.load_order_comparison.restore_status = Some(status);
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui) {
+        if self.state.save_info.is_none() {
+            return;
+        }
+
+        let mut selected = None;
+        let mut restore = false;
+        egui::CollapsingHeader::new("Compare Load Order").show(ui, |ui| {
+            let comparison = &self.state.load_order_comparison;
+            ui.horizontal(|ui| {
+                let current = comparison
+                    .profile_path
+                    .as_ref()
+                    .map(|path| path.to_string_lossy().to_string())
+                    .unwrap_or_else(|| "Choose a profile".to_string());
+                egui::ComboBox::from_label("Profile")
+                    .selected_text(current)
+                    .show_ui(ui, |ui| {
+                        for profile in &comparison.profiles {
+                            if ui.selectable_label(false, &profile.name).clicked() {
+                                selected = self
+                                    .state
+                                    .mod_manager
+                                    .as_ref()
+                                    .and_then(|manager| manager.profile_path(&profile.id).ok());
+                            }
+                        }
+                    });
+                if ui.button("Choose profile folder").clicked() {
+                    selected = rfd::FileDialog::new().pick_folder();
+                }
+            });
+
+            if comparison.profile_path.is_some()
+                && ui
+                    .button("Restore the save's load order into this profile")
+                    .on_hover_text(
+                        "Enables the plugins used by the save in its order and disables the rest",
+                    )
+                    .clicked()
+            {
+                restore = true;
+            }
+            if let Some(status) = &comparison.restore_status {
+                ui.colored_label(Color32::from_rgb(200, 200, 50), status);
+            }
+
+            match &comparison.result {
+                Some(Ok(result)) if result.is_same() => {
+                    ui.colored_label(Color32::from_rgb(50, 200, 50), "Load order is the same");
+                }
+                Some(Ok(result)) => {
+                    ui.label(format!("{} plugins unchanged", result.unchanged));
+                    egui::Grid::new("load_order_changes")
+                        .striped(true)
+                        .show(ui, |ui| {
+                            ui.strong("Change");
+                            ui.strong("Plugin");
+                            ui.strong("Save");
+                            ui.strong("Profile");
+                            ui.end_row();
+                            for change in &result.changes {
+                                let (label, color, old_index, new_index) = match change {
+                                    LoadOrderChange::Added { new_index, .. } => (
+                                        "Added",
+                                        Color32::from_rgb(50, 200, 50),
+                                        None,
+                                        Some(new_index),
+                                    ),
+                                    LoadOrderChange::Missing { old_index, .. } => (
+                                        "Missing",
+                                        Color32::from_rgb(200, 50, 50),
+                                        Some(old_index),
+                                        None,
+                                    ),
+                                    LoadOrderChange::Moved {
+                                        old_index,
+                                        new_index,
+                                        ..
+                                    } => (
+                                        "Moved",
+                                        Color32::from_rgb(230, 140, 30),
+                                        Some(old_index),
+                                        Some(new_index),
+                                    ),
+                                };
+                                let index = |index: Option<&usize>| {
+                                    index.map(|i| i.to_string()).unwrap_or_default()
+                                };
+                                ui.colored_label(color, label);
+                                ui.label(change.name());
+                                ui.label(index(old_index));
+                                ui.label(index(new_index));
+                                ui.end_row();
+                            }
+                        });
+                }
+                Some(Err(e)) => {
+                    ui.colored_label(Color32::from_rgb(200, 50, 50), e);
+                }
+                None => {}
+            }
+        });
+
+        if let Some(profile_path) = selected {
+            self.select_profile(profile_path);
+        }
+        if restore {
+            self.restore();
+        }
+    }
+}
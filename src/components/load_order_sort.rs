@@ -0,0 +1,183 @@
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+
+use eframe::egui::{self, Color32};
+
+use arcanaeum_core::{
+    load_masterlist,
+    load_order::{
+        comparison::{compare_load_orders, LoadOrderChange},
+        profile::{
+            get_active_plugins, read_profile_load_order, write_profile_load_order, ProfilePlugin,
+        },
+    },
+    loot::{condition::InstallEnvironment, sorting::sort_load_order},
+    mod_manager::find_plugin_path,
+};
+
+use crate::app::{DetailState, SortProposal};
+
+use super::load_order_comparison::LoadOrderComparisonView;
+
+/**
+ * Sorts the load order of the profile picked under Compare Load Order with LOOT's rules,
+ * and shows what would move before writing it.
+ */
+pub struct LoadOrderSortView<'a> {
+    state: &'a mut DetailState,
+}
+
+fn names(plugins: &[ProfilePlugin]) -> Vec<String> {
+    plugins.iter().map(|p| p.name.clone()).collect()
+}
+
+impl<'a> LoadOrderSortView<'a> {
+    pub fn new(state: &'a mut DetailState) -> LoadOrderSortView<'a> {
+        LoadOrderSortView { state }
+    }
+
+    fn propose(&self, profile_path: &Path) -> Result<SortProposal, String> {
+        let masterlist = load_masterlist(self.state.game(), &self.state.masterlist_source)
+            .map_err(|e| e.to_string())?;
+        let current =
+            read_profile_load_order(profile_path, self.state.game()).map_err(|e| e.to_string())?;
+        let data_path = self.state.data_path.as_deref();
+        let manager = self.state.mod_manager.as_deref();
+        let environment =
+            InstallEnvironment::new(data_path, get_active_plugins(&current, self.state.game()));
+        let find_plugin = |name: &str| find_plugin_path(manager, data_path, name);
+        let sorted = sort_load_order(&current, &find_plugin, &masterlist, &environment)
+            .map_err(|cycle| cycle.to_string())?;
+        Ok(SortProposal {
+            profile_path: profile_path.to_path_buf(),
+            comparison: compare_load_orders(&names(&current), &names(&sorted)),
+            plugins: sorted,
+        })
+    }
+
+    /**
+     * Writes the proposal into the profile it was made for, returning that profile.
+     */
+    fn write(&self) -> Result<(PathBuf, Vec<PathBuf>), Error> {
+        let proposal = match &self.state.load_order_sort.proposal {
+            Some(Ok(proposal)) => proposal,
+            _ => return Err(Error::new(ErrorKind::NotFound, "Nothing to write")),
+        };
+        let backups =
+            write_profile_load_order(&proposal.profile_path, &proposal.plugins, self.state.game())?;
+        Ok((proposal.profile_path.clone(), backups))
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui) {
+        if self.state.save_info.is_none() {
+            return;
+        }
+
+        let profile_path = self.state.load_order_comparison.profile_path.clone();
+        let mut sort = false;
+        let mut write = false;
+        egui::CollapsingHeader::new("Sort Load Order").show(ui, |ui| {
+            let sort_state = &self.state.load_order_sort;
+            match &profile_path {
+                Some(path) => {
+                    ui.label(format!("Profile: {}", path.to_string_lossy()));
+                    if ui
+                        .button("Sort with LOOT rules")
+                        .on_hover_text("Uses the masters, the masterlist and the userlists")
+                        .clicked()
+                    {
+                        sort = true;
+                    }
+                }
+                None => {
+                    ui.label("Choose a profile under Compare Load Order first");
+                }
+            }
+            if let Some(status) = &sort_state.status {
+                ui.colored_label(Color32::from_rgb(200, 200, 50), status);
+            }
+
+            match &sort_state.proposal {
+                Some(Ok(proposal)) if proposal.comparison.is_same() => {
+                    ui.colored_label(
+                        Color32::from_rgb(50, 200, 50),
+                        "Load order is already sorted",
+                    );
+                }
+                Some(Ok(proposal)) => {
+                    ui.label(format!(
+                        "{} plugins move, {} stay in place",
+                        proposal.comparison.changes.len(),
+                        proposal.comparison.unchanged
+                    ));
+                    if ui.button("Write plugins.txt").clicked() {
+                        write = true;
+                    }
+                    egui::Grid::new("load_order_sort_changes")
+                        .striped(true)
+                        .show(ui, |ui| {
+                            ui.strong("Plugin");
+                            ui.strong("Current");
+                            ui.strong("Sorted");
+                            ui.end_row();
+                            for change in &proposal.comparison.changes {
+                                if let LoadOrderChange::Moved {
+                                    name,
+                                    old_index,
+                                    new_index,
+                                } = change
+                                {
+                                    ui.colored_label(Color32::from_rgb(230, 140, 30), name);
+                                    ui.label(old_index.to_string());
+                                    ui.label(new_index.to_string());
+                                    ui.end_row();
+                                }
+                            }
+                        });
+                }
+                Some(Err(e)) => {
+                    ui.colored_label(Color32::from_rgb(200, 50, 50), e);
+                }
+                None => {}
+            }
+        });
+
+        let profile_path = match profile_path {
+            Some(profile_path) => profile_path,
+            None => return,
+        };
+        if sort {
+            let proposal = self.propose(&profile_path);
+            if let Err(e) = &proposal {
+                tracing::error!("Could not sort {:?}: {}", profile_path, e);
+            }
+            self.state.load_order_sort.proposal = Some(proposal);
+            self.state.load_order_sort.status = None;
+        }
+        if write {
+            let status = match self.write() {
+                Ok((written_path, backups)) => {
+                    tracing::info!("Wrote sorted load order to {:?}", written_path);
+                    self.state.load_order_sort.proposal = None;
+                    match backups.is_empty() {
+                        true => "Sorted load order written".to_string(),
+                        false => format!(
+                            "Sorted load order written, backups: {}",
+                            backups
+                                .iter()
+                                .map(|b| b.to_string_lossy())
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        ),
+                    }
+                }
+                Err(e) => {
+                    tracing::error!("Could not write sorted load order: {}", e);
+                    e.to_string()
+                }
+            };
+            self.state.load_order_sort.status = Some(status);
+            LoadOrderComparisonView::new(self.state).select_profile(profile_path);
+        }
+    }
+}
@@ -1,3 +1,14 @@
+pub mod category_rules_window;
+pub mod compression_converter;
+pub mod creation_club;
+pub mod dependency_graph_view;
 pub mod detail_view;
+pub mod header_editor;
+pub mod load_order_comparison;
+pub mod load_order_sort;
+pub mod nexus_search;
+pub mod plugin_details;
+pub mod plugin_list_editor;
 pub mod save_file_selector;
 pub mod selectable_item_list;
+pub mod settings_window;
@@ -0,0 +1,127 @@
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use eframe::egui::{self, Color32};
+
+use arcanaeum_core::mod_search::nexus_api::{NexusApiClient, NexusUser};
+use arcanaeum_core::mod_search::nexus_search::{NexusSearchClient, NexusSearchResult, SearchCache};
+
+pub type SearchOutcome = Result<Vec<NexusSearchResult>, String>;
+pub type KeyCheckOutcome = Result<NexusUser, String>;
+
+/**
+ * A Nexus search for a plugin. It runs on its own thread and fills in the outcome when
+ * it is done.
+ */
+#[derive(Clone, Default)]
+pub struct NexusSearch {
+    outcome: Arc<Mutex<Option<SearchOutcome>>>,
+}
+
+fn search_plugin(game: &str, plugin: &str) -> SearchOutcome {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| e.to_string())?;
+    let client = NexusSearchClient::new(game).with_cache(SearchCache::in_cache_dir());
+    runtime
+        .block_on(client.search_plugin(plugin))
+        .map_err(|e| e.to_string())
+}
+
+impl NexusSearch {
+    pub fn start(ctx: egui::Context, game: &str, plugin: &str) -> NexusSearch {
+        let search = NexusSearch::default();
+        let outcome = search.outcome.clone();
+        let (game, plugin) = (game.to_string(), plugin.to_string());
+        thread::spawn(move || {
+            let result = search_plugin(&game, &plugin);
+            if let Err(e) = &result {
+                tracing::error!("Nexus search for {} failed: {}", plugin, e);
+            }
+            *outcome.lock().unwrap() = Some(result);
+            ctx.request_repaint();
+        });
+        search
+    }
+
+    pub fn show(&self, ui: &mut egui::Ui) {
+        match &*self.outcome.lock().unwrap() {
+            None => {
+                ui.spinner();
+            }
+            Some(Ok(results)) if results.is_empty() => {
+                ui.label("No mods found on the Nexus");
+            }
+            Some(Ok(results)) => {
+                for result in results.iter().take(5) {
+                    ui.hyperlink_to(
+                        format!(
+                            "{} by {} ({} endorsements)",
+                            result.name, result.username, result.endorsements
+                        ),
+                        result.page_url(),
+                    );
+                }
+            }
+            Some(Err(e)) => {
+                ui.colored_label(Color32::from_rgb(200, 50, 50), e);
+            }
+        }
+    }
+}
+
+/**
+ * A check of the Nexus API key, asking the API which user it belongs to. Like a search it
+ * runs on its own thread.
+ */
+#[derive(Clone, Default)]
+pub struct NexusKeyCheck {
+    outcome: Arc<Mutex<Option<KeyCheckOutcome>>>,
+}
+
+fn validate_key(api_key: &str) -> KeyCheckOutcome {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| e.to_string())?;
+    let client = NexusApiClient::new(api_key);
+    runtime
+        .block_on(client.validate())
+        .map_err(|e| e.to_string())
+}
+
+impl NexusKeyCheck {
+    pub fn start(ctx: egui::Context, api_key: &str) -> NexusKeyCheck {
+        let check = NexusKeyCheck::default();
+        let outcome = check.outcome.clone();
+        let api_key = api_key.to_string();
+        thread::spawn(move || {
+            let result = validate_key(&api_key);
+            if let Err(e) = &result {
+                tracing::error!("Nexus API key check failed: {}", e);
+            }
+            *outcome.lock().unwrap() = Some(result);
+            ctx.request_repaint();
+        });
+        check
+    }
+
+    pub fn show(&self, ui: &mut egui::Ui) {
+        match &*self.outcome.lock().unwrap() {
+            None => {
+                ui.spinner();
+            }
+            Some(Ok(user)) => {
+                let membership = match user.is_premium {
+                    true => " (Premium)",
+                    false => "",
+                };
+                ui.label(format!("Key of {}{}", user.name, membership));
+            }
+            Some(Err(e)) => {
+                ui.colored_label(Color32::from_rgb(200, 50, 50), e);
+            }
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use eframe::egui::{self, Color32};
+
+use arcanaeum_core::plugin_file::{read_plugin_header_from_data_folder, PluginHeader};
+
+use crate::app::DetailState;
+
+fn yes_no(value: bool) -> &'static str {
+    match value {
+        true => "Yes",
+        false => "No",
+    }
+}
+
+fn header_line(ui: &mut egui::Ui, name: &str, value: &str) {
+    ui.label(name);
+    ui.label(value);
+    ui.end_row();
+}
+
+/**
+ * Side panel with the TES4 header of the plugin selected in the plugin list.
+ */
+pub struct PluginDetails<'a> {
+    state: &'a mut DetailState,
+}
+
+impl<'a> PluginDetails<'a> {
+    pub fn new(state: &'a mut DetailState) -> PluginDetails<'a> {
+        PluginDetails { state }
+    }
+
+    pub fn select(&mut self, name: &str) {
+        self.state.selected_plugin = Some(name.to_string());
+        self.state.plugin_header = Some(match &self.state.data_path {
+            Some(data_path) => {
+                read_plugin_header_from_data_folder(data_path, name).map_err(|e| e.to_string())
+            }
+            None => Err("Could not find the Skyrim Data folder".to_string()),
+        });
+    }
+
+    fn show_header(ui: &mut egui::Ui, header: &PluginHeader) {
+        egui::Grid::new("plugin_header")
+            .striped(true)
+            .min_row_height(22.)
+            .show(ui, |ui| {
+                header_line(ui, "Author", header.author.as_deref().unwrap_or(""));
+                header_line(ui, "Version", &format!("{:.2}", header.version));
+                header_line(ui, "Records", &header.record_count.to_string());
+                header_line(ui, "Master (ESM)", yes_no(header.is_master()));
+                header_line(ui, "Light (ESL)", yes_no(header.is_light()));
+                header_line(ui, "Localized", yes_no(header.is_localized()));
+            });
+
+        if let Some(description) = &header.description {
+            ui.separator();
+            ui.label(description);
+        }
+
+        ui.separator();
+        ui.heading("Masters");
+        for master in &header.masters {
+            ui.label(master);
+        }
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        let name = match &self.state.selected_plugin {
+            Some(name) => name.clone(),
+            None => return,
+        };
+
+        let mut open = true;
+        egui::SidePanel::right("plugin-details")
+            .min_width(300.)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.heading(&name);
+                    if ui.button("Close").clicked() {
+                        open = false;
+                    }
+                });
+                ui.separator();
+
+                match &self.state.plugin_header {
+                    Some(Ok(header)) => Self::show_header(ui, header),
+                    Some(Err(e)) => {
+                        ui.colored_label(Color32::from_rgb(200, 50, 50), e);
+                    }
+                    None => {}
+                }
+            });
+
+        if !open {
+            self.state.selected_plugin = None;
+            self.state.plugin_header = None;
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use std::io::{Error, ErrorKind};
+use std::path::PathBuf;
+
+use eframe::egui::{self, Color32};
+
+use arcanaeum_core::{
+    save_file_writer::plugin_edit::{apply_plugin_edits, entries_from_names, PluginEntry},
+    write_saveinfo_copy,
+};
+
+use crate::app::DetailState;
+
+enum EntryAction {
+    MoveUp(usize),
+    MoveDown(usize),
+    Remove(usize),
+}
+
+fn show_entry_list(ui: &mut egui::Ui, id_source: &str, entries: &mut Vec<PluginEntry>) {
+    let mut action = None;
+    let entry_count = entries.len();
+
+    egui::Grid::new(id_source)
+        .striped(true)
+        .min_row_height(22.)
+        .show(ui, |ui| {
+            for (index, entry) in entries.iter_mut().enumerate() {
+                ui.label(format!("{:02X}", index));
+                ui.add(egui::TextEdit::singleline(&mut entry.name).desired_width(300.));
+                if ui.add_enabled(index > 0, egui::Button::new("Up")).clicked() {
+                    action = Some(EntryAction::MoveUp(index));
+                }
+                if ui
+                    .add_enabled(index + 1 < entry_count, egui::Button::new("Down"))
+                    .clicked()
+                {
+                    action = Some(EntryAction::MoveDown(index));
+                }
+                if ui.button("Remove").clicked() {
+                    action = Some(EntryAction::Remove(index));
+                }
+                ui.end_row();
+            }
+        });
+
+    match action {
+        Some(EntryAction::MoveUp(index)) => entries.swap(index, index - 1),
+        Some(EntryAction::MoveDown(index)) => entries.swap(index, index + 1),
+        Some(EntryAction::Remove(index)) => {
+            entries.remove(index);
+        }
+        None => {}
+    }
+}
+
+/**
+ * Renames, reorders and removes entries in a save's plugin lists, for when a mod was
+ * renamed or merged after the save was made. Edits are written to a new file.
+ */
+pub struct PluginListEditor<'a> {
+    state: &'a mut DetailState,
+}
+
+impl<'a> PluginListEditor<'a> {
+    pub fn new(state: &'a mut DetailState) -> PluginListEditor<'a> {
+        PluginListEditor { state }
+    }
+
+    fn reset(&mut self) {
+        if let Some(save_info) = &self.state.save_info {
+            self.state.plugin_edit.plugins = entries_from_names(&save_info.plugin_info.plugins);
+            self.state.plugin_edit.light_plugins =
+                entries_from_names(&save_info.plugin_info.light_plugins);
+            self.state.plugin_edit.status = None;
+        }
+    }
+
+    fn write_edited_save(&self) -> Result<(PathBuf, String), Error> {
+        let save_info = match &self.state.save_info {
+            Some(save_info) => save_info,
+            None => return Err(Error::new(ErrorKind::NotFound, "No save selected")),
+        };
+
+        let (edited, report) = apply_plugin_edits(
+            save_info,
+            &self.state.plugin_edit.plugins,
+            &self.state.plugin_edit.light_plugins,
+        )?;
+
+        let path = write_saveinfo_copy(&self.state.file_path, &edited, "plugins")?;
+
+        let summary = format!(
+            "Written to {} ({} form ids moved, {} cleared)",
+            path.to_string_lossy(),
+            report.remapped_form_ids,
+            report.orphaned_form_ids
+        );
+        Ok((path, summary))
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui, save_written: impl FnOnce(String)) {
+        if self.state.save_info.is_none() {
+            return;
+        }
+
+        let mut written = None;
+        egui::CollapsingHeader::new("Edit Plugin List").show(ui, |ui| {
+            show_entry_list(
+                ui,
+                "plugin_list_editor",
+                &mut self.state.plugin_edit.plugins,
+            );
+
+            if !self.state.plugin_edit.light_plugins.is_empty() {
+                ui.separator();
+                ui.label("Light Plugins");
+                show_entry_list(
+                    ui,
+                    "light_plugin_list_editor",
+                    &mut self.state.plugin_edit.light_plugins,
+                );
+            }
+
+            ui.horizontal(|ui| {
+                if ui.button("Reset").clicked() {
+                    self.reset();
+                }
+                if ui.button("Write as new save").clicked() {
+                    match self.write_edited_save() {
+                        Ok((path, summary)) => {
+                            tracing::info!("{}", summary);
+                            self.state.plugin_edit.status = Some(summary);
+                            written = Some(path.to_string_lossy().to_string());
+                        }
+                        Err(e) => {
+                            tracing::error!("Could not write edited save: {}", e);
+                            self.state.plugin_edit.status = Some(e.to_string());
+                        }
+                    }
+                }
+            });
+
+            if let Some(status) = &self.state.plugin_edit.status {
+                ui.colored_label(Color32::from_rgb(200, 200, 50), status);
+            }
+        });
+
+        if let Some(path) = written {
+            save_written(path);
+        }
+    }
+}
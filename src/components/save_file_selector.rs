@@ -1,12 +1,10 @@
-use std::{collections::HashMap, io::Read};
+use std::collections::HashMap;
 
-use crate::{
-    app::{Character, SaveFile, SavesState},
-    save_file_parser::parse_header_only,
-};
+use crate::app::{Character, SavesState};
+use crate::saves::SaveFile;
+use arcanaeum_core::mod_manager::ModManager;
 use dirs;
 use eframe::{egui, emath::Align};
-use std::io::Error;
 
 use super::selectable_item_list::{SelectableItem, SelectableItemList};
 
@@ -22,48 +20,22 @@ pub fn get_default_save_folder() -> String {
     path.to_str().unwrap().to_string()
 }
 
-fn load_file_buffer(path: &str) -> Result<Vec<u8>, Error> {
-    let mut file = std::fs::File::open(path)?;
-
-    let mut buf: Vec<u8> = Vec::new();
-    file.read_to_end(&mut buf)?;
-    Ok(buf)
-}
-
-// Get all .ess files in the target folder and return them as a vector of SaveFile
-pub fn read_folder_contents(path: &str) -> Vec<SaveFile> {
-    let mut files = Vec::new();
-    match std::fs::read_dir(path) {
-        Ok(x) => {
-            for entry in x {
-                let entry = entry.unwrap();
-                let path = entry.path();
-                if path.is_file() && path.extension().unwrap() == "ess" {
-                    match load_file_buffer(path.to_str().unwrap()) {
-                        Ok(buf) => {
-                            let header = parse_header_only(buf);
-                            let file_name = path.file_name().unwrap().to_str().unwrap().to_string();
-
-                            let save_file = SaveFile {
-                                path: path.to_str().unwrap().to_string(),
-                                header: Some(header),
-                                file_name: file_name,
-                            };
-                            files.push(save_file);
-                        }
-                        Err(e) => {
-                            tracing::error!("Error loading file: {}", e);
-                        }
-                    }
-                }
-            }
-        }
-        Err(e) => {
-            tracing::error!("Error reading folder: {}", e);
-            return files;
+/**
+ * The default save folder, followed by the ones the mod manager knows about, like MO2
+ * profiles with local saves.
+ */
+pub fn get_known_save_folders(manager: Option<&dyn ModManager>) -> Vec<(String, String)> {
+    let mut folders = vec![(
+        "Skyrim Special Edition".to_string(),
+        get_default_save_folder(),
+    )];
+    for (label, path) in manager.map(|m| m.save_folders()).unwrap_or_default() {
+        let path = path.to_string_lossy().to_string();
+        if !folders.iter().any(|(_, p)| *p == path) {
+            folders.push((label, path));
         }
     }
-    files
+    folders
 }
 
 fn save_file_to_selectable_item(save_file: SaveFile) -> SelectableItem<SaveFile> {
@@ -104,6 +76,8 @@ impl<'a> SaveFileSelector<'a> {
         match res {
             Some(path_buf) => {
                 self.state.save_folder_path = String::from(path_buf.to_str().unwrap());
+                self.state.selected_character = None;
+                self.state.refresh();
 
                 tracing::info!("Selected folder: {}", self.state.save_folder_path);
             }
@@ -115,6 +89,31 @@ impl<'a> SaveFileSelector<'a> {
         if ui.button("Select Skyrim save folder").clicked() {
             self.handle_folder_select();
         }
+        if self.state.save_folders.len() > 1 {
+            let mut selected_folder = None;
+            let current = self
+                .state
+                .save_folders
+                .iter()
+                .find(|(_, path)| *path == self.state.save_folder_path)
+                .map(|(label, _)| label.clone())
+                .unwrap_or_else(|| self.state.save_folder_path.clone());
+            egui::ComboBox::from_label("Save folder")
+                .selected_text(current)
+                .show_ui(ui, |ui| {
+                    for (label, path) in &self.state.save_folders {
+                        let selected = *path == self.state.save_folder_path;
+                        if ui.selectable_label(selected, label).clicked() {
+                            selected_folder = Some(path.clone());
+                        }
+                    }
+                });
+            if let Some(path) = selected_folder {
+                self.state.save_folder_path = path;
+                self.state.selected_character = None;
+                self.state.refresh();
+            }
+        }
         ui.separator();
 
         ui.horizontal_top(|ui| {
@@ -0,0 +1,208 @@
+use std::path::PathBuf;
+
+use eframe::egui::{self, Color32};
+
+use arcanaeum_core::loot::sources::{find_masterlist, MasterlistSource};
+use arcanaeum_core::mod_manager::ModManagerKind;
+use arcanaeum_core::mod_search::mo2_scanner::Mo2Instance;
+
+use crate::components::nexus_search::NexusKeyCheck;
+use crate::config::Config;
+
+/**
+ * The masterlist file a source resolved to, kept between frames so that the folders are
+ * only searched again when the source or the game changes.
+ */
+#[derive(Clone)]
+pub struct ResolvedMasterlist {
+    game: String,
+    source: MasterlistSource,
+    path: Result<PathBuf, String>,
+}
+
+pub struct SettingsWindow<'a> {
+    config: &'a mut Config,
+    mo2_instances: &'a [Mo2Instance],
+    game: &'a str,
+    resolved_masterlist: &'a mut Option<ResolvedMasterlist>,
+    key_check: &'a mut Option<NexusKeyCheck>,
+}
+
+impl<'a> SettingsWindow<'a> {
+    pub fn new(
+        config: &'a mut Config,
+        mo2_instances: &'a [Mo2Instance],
+        game: &'a str,
+        resolved_masterlist: &'a mut Option<ResolvedMasterlist>,
+        key_check: &'a mut Option<NexusKeyCheck>,
+    ) -> SettingsWindow<'a> {
+        SettingsWindow {
+            config,
+            mo2_instances,
+            game,
+            resolved_masterlist,
+            key_check,
+        }
+    }
+
+    /**
+     * Returns true when a setting was changed.
+     */
+    pub fn show(&mut self, ctx: &egui::Context, open: &mut bool) -> bool {
+        let mut changed = false;
+        egui::Window::new("Settings").open(open).show(ctx, |ui| {
+            egui::ComboBox::from_label("Mod manager")
+                .selected_text(self.config.mod_manager.to_string())
+                .show_ui(ui, |ui| {
+                    for kind in ModManagerKind::ALL {
+                        changed |= ui
+                            .selectable_value(&mut self.config.mod_manager, kind, kind.to_string())
+                            .changed();
+                    }
+                });
+
+            if self.config.mod_manager == ModManagerKind::ModOrganizer2 {
+                changed |= self.show_mo2_instance(ui);
+            }
+            ui.separator();
+            changed |= self.show_masterlist_source(ui);
+            ui.separator();
+            changed |= self.show_nexus_api_key(ui);
+        });
+        changed
+    }
+
+    fn show_mo2_instance(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut changed = false;
+        let current = match &self.config.mo2_instance_path {
+            Some(path) => path.to_string_lossy().to_string(),
+            None => "First instance found".to_string(),
+        };
+        egui::ComboBox::from_label("MO2 instance")
+            .selected_text(current)
+            .show_ui(ui, |ui| {
+                changed |= ui
+                    .selectable_value(
+                        &mut self.config.mo2_instance_path,
+                        None,
+                        "First instance found",
+                    )
+                    .changed();
+                for instance in self.mo2_instances {
+                    let label = format!("{} ({})", instance.name, instance.game_name);
+                    changed |= ui
+                        .selectable_value(
+                            &mut self.config.mo2_instance_path,
+                            Some(instance.path.clone()),
+                            label,
+                        )
+                        .changed();
+                }
+            });
+        if ui.button("Choose portable instance folder").clicked() {
+            if let Some(path) = rfd::FileDialog::new().pick_folder() {
+                self.config.mo2_instance_path = Some(path);
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    fn show_masterlist_source(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut changed = false;
+        egui::ComboBox::from_label("Masterlist")
+            .selected_text(self.config.masterlist_source.to_string())
+            .show_ui(ui, |ui| {
+                for source in MasterlistSource::FOUND {
+                    let label = source.to_string();
+                    changed |= ui
+                        .selectable_value(&mut self.config.masterlist_source, source, label)
+                        .changed();
+                }
+            });
+        ui.horizontal(|ui| {
+            if ui.button("Choose masterlist file").clicked() {
+                if let Some(path) = rfd::FileDialog::new()
+                    .add_filter("masterlist", &["yaml"])
+                    .pick_file()
+                {
+                    self.config.masterlist_source = MasterlistSource::Custom(path);
+                    changed = true;
+                }
+            }
+            if ui
+                .button("Choose masterlist folder")
+                .on_hover_text("A folder with a masterlist.yaml, such as a git clone")
+                .clicked()
+            {
+                if let Some(path) = rfd::FileDialog::new().pick_folder() {
+                    self.config.masterlist_source = MasterlistSource::Custom(path);
+                    changed = true;
+                }
+            }
+        });
+        let source = &self.config.masterlist_source;
+        let resolved = match self.resolved_masterlist.take() {
+            Some(resolved) if resolved.game == self.game && resolved.source == *source => resolved,
+            _ => ResolvedMasterlist {
+                game: self.game.to_string(),
+                source: source.clone(),
+                path: find_masterlist(self.game, source).map_err(|e| e.to_string()),
+            },
+        };
+        match &resolved.path {
+            Ok(path) => {
+                ui.label(format!("Using {}", path.to_string_lossy()));
+            }
+            Err(e) => {
+                ui.colored_label(Color32::from_rgb(200, 50, 50), e);
+            }
+        }
+        *self.resolved_masterlist = Some(resolved);
+        changed
+    }
+
+    /**
+     * The key is saved once the field loses focus rather than on every key press, and
+     * then checked with the API.
+     */
+    fn show_nexus_api_key(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut key = self.config.nexus_api_key.clone().unwrap_or_default();
+        let response = ui
+            .horizontal(|ui| {
+                let response = ui.add(egui::TextEdit::singleline(&mut key).password(true));
+                ui.label("Nexus API key");
+                response
+            })
+            .inner;
+        // Remembered until focus is lost so that only an edited key is saved
+        if response.changed() {
+            self.config.nexus_api_key = Some(key.trim().to_string()).filter(|k| !k.is_empty());
+            ui.data_mut(|data| data.insert_temp(response.id, true));
+        }
+        let saved = response.lost_focus()
+            && ui.data_mut(|data| {
+                let edited = data.get_temp::<bool>(response.id).is_some();
+                data.remove::<bool>(response.id);
+                edited
+            });
+        if saved {
+            *self.key_check = None;
+        }
+        if let Some(key) = &self.config.nexus_api_key {
+            ui.horizontal(|ui| {
+                if ui.button("Check key").clicked() || saved {
+                    *self.key_check = Some(NexusKeyCheck::start(ui.ctx().clone(), key));
+                }
+                if let Some(check) = self.key_check.as_ref() {
+                    check.show(ui);
+                }
+            });
+        }
+        ui.hyperlink_to(
+            "Get your personal API key",
+            "https://www.nexusmods.com/users/myaccount?tab=api",
+        );
+        saved
+    }
+}
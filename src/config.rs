@@ -1,7 +1,31 @@
 use std::env;
 use std::fs;
+#[cfg(feature = "gui")]
+use std::io::Error;
 use std::path::PathBuf;
 
+use serde::{Deserialize, Serialize};
+
+use arcanaeum_core::categories::CategorySettings;
+use arcanaeum_core::loot::sources::MasterlistSource;
+use arcanaeum_core::mod_manager::ModManagerKind;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub mod_manager: ModManagerKind,
+    /**
+     * The MO2 instance to use, the first one found for the game when not set.
+     */
+    pub mo2_instance_path: Option<PathBuf>,
+    pub masterlist_source: MasterlistSource,
+    pub categories: CategorySettings,
+    /**
+     * The personal API key from the Nexus Mods account page.
+     */
+    pub nexus_api_key: Option<String>,
+}
+
 fn get_config_folder_path() -> PathBuf {
     let app_data_path = env::var("APPDATA").unwrap();
     let mut path_buf = PathBuf::new();
@@ -15,6 +39,7 @@ fn get_config_path() -> PathBuf {
     path_buf
 }
 
+#[cfg(feature = "gui")]
 fn create_config_file_if_not_exists() {
     let path_buf = get_config_path();
 
@@ -26,6 +51,7 @@ fn create_config_file_if_not_exists() {
     }
 }
 
+#[cfg(feature = "gui")]
 fn create_config_folder_if_not_exists() {
     let app_data_path = env::var("APPDATA").unwrap();
     let mut path_buf = PathBuf::new();
@@ -37,15 +63,43 @@ fn create_config_folder_if_not_exists() {
     }
 }
 
+#[cfg(feature = "gui")]
 pub fn create_config_if_not_exists() {
     create_config_folder_if_not_exists();
     create_config_file_if_not_exists();
 }
 
+/**
+ * Reads the config, falling back to the defaults when it is missing or broken.
+ */
+pub fn load_config() -> Config {
+    let contents = match env::var("APPDATA") {
+        Ok(_) => fs::read_to_string(get_config_path()),
+        Err(_) => return Config::default(),
+    };
+    match contents.map(|c| serde_json::from_str(&c)) {
+        Ok(Ok(config)) => config,
+        Ok(Err(e)) => {
+            tracing::error!("Could not parse config: {}", e);
+            Config::default()
+        }
+        Err(e) => {
+            tracing::error!("Could not read config: {}", e);
+            Config::default()
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+pub fn save_config(config: &Config) -> Result<(), Error> {
+    fs::write(get_config_path(), serde_json::to_string_pretty(config)?)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[cfg(feature = "gui")]
     #[test]
     fn test_create_config_if_not_exists() {
         create_config_if_not_exists();
@@ -55,4 +109,10 @@ mod tests {
         path_buf.push("Arcaneum");
         assert!(path_buf.exists());
     }
+
+    #[test]
+    fn empty_config_uses_defaults() {
+        let config: Config = serde_json::from_str("{\n        }").unwrap();
+        assert_eq!(config, Config::default());
+    }
 }
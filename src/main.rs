@@ -1,58 +1,39 @@
-use std::collections::HashMap;
-use std::collections::HashSet;
-use std::io::Error;
-use std::io::Read;
-
+#[cfg(feature = "gui")]
 use eframe::egui;
 
-use mod_search::vortex_scanner::get_installed_from_all_profiles;
-use mod_search::vortex_scanner::get_masterlist_data;
-use mod_search::vortex_scanner::Plugin;
-use save_file_parser::SaveInfo;
-
+#[cfg(feature = "gui")]
 use crate::app::AppState;
+#[cfg(feature = "gui")]
 use crate::config::create_config_if_not_exists;
-use crate::save_file_parser::parse;
 
+#[cfg(feature = "gui")]
 mod app;
+mod cli;
+#[cfg(feature = "gui")]
 mod components;
 mod config;
+#[cfg(feature = "gui")]
 mod features;
-mod mod_search;
-mod save_file_parser;
+mod saves;
 mod sktypes;
-mod utils;
-
-fn load_mod_map(game: &str) -> HashMap<String, Plugin> {
-    let mut map = HashMap::new();
-    if let Ok(plugins) = get_masterlist_data(game) {
-        for plugin in plugins {
-            map.insert(plugin.name.clone(), plugin);
-        }
-    }
-    map
-}
 
-fn load_installed(game: &str) -> HashSet<String> {
-    let mut installed = HashSet::new();
-    for p in get_installed_from_all_profiles(game) {
-        installed.insert(p);
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() || cfg!(not(feature = "gui")) {
+        // Keep stdout clean for scripts, only warnings and errors go to stderr
+        tracing_subscriber::fmt()
+            .with_writer(std::io::stderr)
+            .with_max_level(tracing::Level::WARN)
+            .init();
+        std::process::exit(cli::run(&args));
     }
-    installed
-}
-
-fn load_saveinfo_from_path(path: String) -> Result<SaveInfo, Error> {
-    tracing::info!("Loading file: {:?}", path);
-    let mut file = std::fs::File::open(path)?;
 
-    let mut buf: Vec<u8> = Vec::new();
-    file.read_to_end(&mut buf)?;
-
-    let parsed = parse(buf);
-    Ok(parsed)
+    #[cfg(feature = "gui")]
+    run_gui();
 }
 
-fn main() {
+#[cfg(feature = "gui")]
+fn run_gui() {
     tracing_subscriber::fmt::init();
     tracing::info!("App booting...");
 
@@ -0,0 +1,54 @@
+use std::io::{Error, Read};
+
+use arcanaeum_core::save_file_parser::{header::Header, parse_header_only};
+
+#[derive(Clone)]
+pub struct SaveFile {
+    pub path: String,
+    pub file_name: String,
+    pub header: Option<Header>,
+}
+
+fn load_file_buffer(path: &str) -> Result<Vec<u8>, Error> {
+    let mut file = std::fs::File::open(path)?;
+
+    let mut buf: Vec<u8> = Vec::new();
+    file.read_to_end(&mut buf)?;
+    Ok(buf)
+}
+
+// Get all .ess files in the target folder and return them as a vector of SaveFile
+pub fn read_folder_contents(path: &str) -> Vec<SaveFile> {
+    let mut files = Vec::new();
+    match std::fs::read_dir(path) {
+        Ok(x) => {
+            for entry in x {
+                let entry = entry.unwrap();
+                let path = entry.path();
+                if path.is_file() && path.extension().is_some_and(|e| e == "ess") {
+                    match load_file_buffer(path.to_str().unwrap()) {
+                        Ok(buf) => {
+                            let header = parse_header_only(buf);
+                            let file_name = path.file_name().unwrap().to_str().unwrap().to_string();
+
+                            let save_file = SaveFile {
+                                path: path.to_str().unwrap().to_string(),
+                                header: Some(header),
+                                file_name: file_name,
+                            };
+                            files.push(save_file);
+                        }
+                        Err(e) => {
+                            tracing::error!("Error loading file: {}", e);
+                        }
+                    }
+                }
+            }
+        }
+        Err(e) => {
+            tracing::error!("Error reading folder: {}", e);
+            return files;
+        }
+    }
+    files
+}
@@ -1,2 +1,3 @@
+#[cfg(feature = "gui")]
 pub mod types;
 pub mod skui_value;
@@ -1,19 +1,21 @@
+use arcanaeum_core::load_order::official::{OfficialKind, OfficialPlugins};
+
+#[cfg(feature = "gui")]
 use super::types::SkTypeReadable;
 
-fn plugin_type_from_name(name: String) -> PluginType {
-    match name.as_str() {
-        "Skyrim.esm" | "Update.esm" | "Dragonborn.esm" | "HearthFires.esm" | "Dawnguard.esm" => {
+pub fn plugin_type_from_name(name: String, official: &OfficialPlugins) -> PluginType {
+    match official.kind(&name) {
+        Some(OfficialKind::Shipped) => {
             tracing::info!("{:?} is Native", name);
             PluginType::Native
         }
-        _ => {
-            if name.starts_with("cc") {
-                tracing::info!("{:?} is Creation Club", name);
-                PluginType::CreationClub
-            } else {
-                tracing::info!("{:?} is Mod", name);
-                PluginType::Mod
-            }
+        Some(OfficialKind::CreationClub) => {
+            tracing::info!("{:?} is Creation Club", name);
+            PluginType::CreationClub
+        }
+        None => {
+            tracing::info!("{:?} is Mod", name);
+            PluginType::Mod
         }
     }
 }
@@ -27,11 +29,13 @@ pub enum PluginType {
     NotAPlugin,
 }
 
+#[cfg(feature = "gui")]
 #[derive(Debug, Clone)]
 pub enum UIValueType {
     Plugin,
 }
 
+#[cfg(feature = "gui")]
 #[derive(Debug, Clone)]
 pub struct SkUIValue {
     name: String,
@@ -39,9 +43,15 @@ pub struct SkUIValue {
     pub value_type: UIValueType,
     pub plugin_type: PluginType,
 }
+#[cfg(feature = "gui")]
 impl SkUIValue {
-    pub fn new(name: &str, value: String, value_type: UIValueType) -> SkUIValue {
-        let plugin_type = plugin_type_from_name(value.clone());
+    pub fn new(
+        name: &str,
+        value: String,
+        value_type: UIValueType,
+        official: &OfficialPlugins,
+    ) -> SkUIValue {
+        let plugin_type = plugin_type_from_name(value.clone(), official);
         SkUIValue {
             name: name.to_string(),
             value,
@@ -50,6 +60,7 @@ impl SkUIValue {
         }
     }
 }
+#[cfg(feature = "gui")]
 impl SkTypeReadable for SkUIValue {
     fn get_value_string(&self) -> String {
         self.value.to_string()
@@ -67,21 +78,35 @@ mod tests {
     #[test]
     fn given_known_native_file_should_return_native_type() {
         let input = "Skyrim.esm".to_string();
-        let t = plugin_type_from_name(input);
+        let t = plugin_type_from_name(input, &OfficialPlugins::default());
         assert_eq!(t, PluginType::Native);
     }
 
     #[test]
     fn given_known_creation_club_file_should_return_creation_club_type() {
         let input = "cceejsse001-hstead.esm".to_string();
-        let t = plugin_type_from_name(input);
+        let t = plugin_type_from_name(input, &OfficialPlugins::default());
         assert_eq!(t, PluginType::CreationClub);
     }
 
     #[test]
     fn given_known_mod_file_should_return_native_type() {
         let input = "TrueStormsSE.esp".to_string();
-        let t = plugin_type_from_name(input);
+        let t = plugin_type_from_name(input, &OfficialPlugins::default());
+        assert_eq!(t, PluginType::Mod);
+    }
+
+    #[test]
+    fn given_community_mod_starting_with_cc_should_return_mod_type() {
+        let input = "ccbetterbows.esp".to_string();
+        let t = plugin_type_from_name(input, &OfficialPlugins::default());
         assert_eq!(t, PluginType::Mod);
     }
+
+    #[test]
+    fn given_resource_pack_should_return_native_type() {
+        let input = "_ResourcePack.esl".to_string();
+        let t = plugin_type_from_name(input, &OfficialPlugins::default());
+        assert_eq!(t, PluginType::Native);
+    }
 }